@@ -0,0 +1,31 @@
+#[path = "../../src/libs/mod.rs"]
+pub mod libs;
+
+#[path = "../../src/common"]
+pub mod common {
+    pub mod adaptors {
+        pub mod wifi_networks;
+    }
+    pub mod models {
+        pub mod device_settings;
+    }
+}
+
+#[path = "../../src/helpers"]
+pub mod helpers {
+    pub mod backoff;
+}
+
+#[path = "../../src/features"]
+pub mod features {
+    pub mod network {
+        pub mod connection_state;
+    }
+    pub mod provisioning {
+        pub mod captive_dns;
+        pub mod form;
+    }
+    pub mod segment_display {
+        pub mod message_queue;
+    }
+}
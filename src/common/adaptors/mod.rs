@@ -1 +1,3 @@
 pub mod network;
+pub mod settings_store;
+pub mod wifi_networks;
@@ -1,49 +1,77 @@
+use crate::common::adaptors::wifi_networks::{
+    ScannedAccessPoint, WifiAuth, WifiCandidate, WifiIpv4Mode, WifiNetwork, WifiNetworkSelector,
+};
 use crate::common::errors::wifi_errors::WifiError;
-use crate::constants::default_values::DefaultValues;
 use crate::constants::env_values::EnvValues;
 use embedded_svc::ipv4;
 use embedded_svc::ipv4::DHCPClientSettings;
-use embedded_svc::wifi::{AuthMethod, ClientConfiguration, Configuration, Wifi};
+use embedded_svc::wifi::{
+    AccessPointConfiguration, AuthMethod, ClientConfiguration, Configuration, Wifi,
+};
 use esp_idf_svc::netif::EspNetifStack;
 use esp_idf_svc::nvs::EspDefaultNvs;
 use esp_idf_svc::sysloop::EspSysLoopStack;
 use esp_idf_svc::wifi::EspWifi;
+use esp_idf_sys::c_types::c_void;
+use esp_idf_sys::{esp, esp_event_base_t};
+use std::net::Ipv4Addr;
 use std::sync::Arc;
-use std::thread::sleep;
-use std::time::Duration;
+
+/// A change of the station link, as the Wi-Fi driver posts it on the system event loop.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WifiDriverEvent {
+    /// The station has left the access point, or couldn't join it.
+    StaDisconnected,
+
+    /// The station has been given an ip address.
+    StaGotIp,
+}
+
+type WifiDriverEventHandler = Box<dyn Fn(WifiDriverEvent) + Send>;
 
 pub struct WifiAdaptor {
     pub esp_wifi: EspWifi,
+    networks: WifiNetworkSelector,
 }
 
 impl WifiAdaptor {
-    pub fn connect(&mut self) -> anyhow::Result<()> {
-        let wifi_channel: Option<u8>;
-        loop {
-            let channel = self.scan();
-
-            if let Ok(c) = channel {
-                wifi_channel = Some(c);
-                break;
-            }
+    /// Configures the station to join the access point of the [candidate] picked by [scan].
+    /// The driver associates and obtains the ip in the background.
+    pub fn associate(&mut self, candidate: &WifiCandidate) -> anyhow::Result<()> {
+        let network = self.networks.network(candidate).ok_or_else(|| {
+            WifiError::Configuration(
+                "E0004a".to_owned(),
+                format!("no network at {}", candidate.network_index),
+            )
+        })?;
 
-            log::warn!("[network] the wifi scanning was unsuccessful. Will try again...");
+        log::debug!(
+            "[network] joining the access point `{}` on channel {} ({} dBm)",
+            network.ssid,
+            candidate.channel,
+            candidate.rssi
+        );
 
-            sleep(Duration::from_millis(
-                DefaultValues::WIFI_RECONNECTION_DELAY_MS,
-            ));
-        }
-
-        let dhcp_conf = DHCPClientSettings {
-            hostname: Some(EnvValues::DEVICE_ID.to_owned()),
+        let ip_conf = match network.static_ipv4 {
+            None => ipv4::ClientConfiguration::DHCP(DHCPClientSettings {
+                hostname: Some(EnvValues::DEVICE_ID.to_owned()),
+            }),
+            Some(s) => ipv4::ClientConfiguration::Fixed(ipv4::ClientSettings {
+                ip: s.address,
+                subnet: ipv4::Subnet {
+                    gateway: s.gateway,
+                    mask: ipv4::Mask(s.prefix_len),
+                },
+                dns: Some(s.dns),
+                secondary_dns: s.secondary_dns,
+            }),
         };
-        let ip_conf = ipv4::ClientConfiguration::DHCP(dhcp_conf);
         let cl = ClientConfiguration {
-            ssid: EnvValues::WIFI_SSID.into(),
-            password: EnvValues::WIFI_PASS.into(),
-            channel: wifi_channel,
-            bssid: None,
-            auth_method: AuthMethod::default(),
+            ssid: network.ssid.clone(),
+            password: network.password.clone(),
+            channel: Some(candidate.channel),
+            bssid: Some(candidate.bssid),
+            auth_method: Self::auth_method(network.auth),
             ip_conf: Some(ip_conf),
         };
 
@@ -54,7 +82,8 @@ impl WifiAdaptor {
         Ok(())
     }
 
-    pub fn scan(&mut self) -> anyhow::Result<u8> {
+    /// The access points around the device.
+    pub fn scan_access_points(&mut self) -> anyhow::Result<Vec<ScannedAccessPoint>> {
         log::debug!("[network] starting wifi access point scanning...");
 
         let ap_infos = self
@@ -62,34 +91,157 @@ impl WifiAdaptor {
             .scan()
             .map_err(|e| WifiError::Scanning("E0002".to_owned(), e.to_string()))?;
 
-        let ap_info = ap_infos
+        let access_points = ap_infos
             .into_iter()
-            .find(|a| a.ssid == EnvValues::WIFI_SSID);
-
-        let channel = if let Some(ap) = ap_info {
-            log::debug!(
-                "[network] found the configured access point {} on channel {}",
-                EnvValues::WIFI_SSID,
-                ap.channel.to_string()
-            );
-            ap.channel
+            .map(|a| ScannedAccessPoint {
+                ssid: a.ssid,
+                bssid: a.bssid,
+                channel: a.channel,
+                // the driver reports the rssi (dBm) as it is, in an unsigned byte
+                rssi: a.signal_strength as i8,
+            })
+            .collect();
+
+        Ok(access_points)
+    }
+
+    /// Scans for the configured networks and picks the access point to join.
+    pub fn scan(&mut self) -> anyhow::Result<WifiCandidate> {
+        let access_points = self.scan_access_points()?;
+
+        match self.networks.select(&access_points) {
+            Some(candidate) => Ok(candidate),
+            None => {
+                let ssids = self
+                    .networks
+                    .networks()
+                    .iter()
+                    .map(|n| n.ssid.as_str())
+                    .collect::<Vec<&str>>()
+                    .join(", ");
+
+                log::error!(
+                    "[network] none of the configured access points `{}` was found \
+                    during the scanning",
+                    ssids
+                );
+
+                Err(WifiError::ApNotFound("E0006".to_owned(), ssids).into())
+            }
+        }
+    }
+
+    /// Opens an access point without a password at [ip], for the provisioning portal.
+    /// The station stays on, so that the networks around can still be scanned.
+    pub fn start_access_point(&mut self, ssid: &str, ip: Ipv4Addr) -> anyhow::Result<()> {
+        let ap = AccessPointConfiguration {
+            ssid: ssid.into(),
+            channel: 1,
+            auth_method: AuthMethod::None,
+            ip_conf: Some(ipv4::RouterConfiguration {
+                subnet: ipv4::Subnet {
+                    gateway: ip,
+                    mask: ipv4::Mask(24),
+                },
+                dhcp_enabled: true,
+                // the clients send every name to the captive portal dns
+                dns: Some(ip),
+                secondary_dns: None,
+            }),
+            ..Default::default()
+        };
+
+        self.esp_wifi
+            .set_configuration(&Configuration::Mixed(ClientConfiguration::default(), ap))
+            .map_err(|e| WifiError::Configuration("E0004b".to_owned(), e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// The station couldn't join the access point of the [candidate], or lost it; the next
+    /// scan prefers the other networks.
+    pub fn mark_failed(&mut self, candidate: &WifiCandidate) {
+        self.networks.mark_failed(candidate);
+    }
+
+    pub fn mark_connected(&mut self) {
+        self.networks.mark_connected();
+    }
+
+    /// Calls [on_event] from the task of the system event loop whenever the station link
+    /// changes, so that it is known before the next poll of the driver status.
+    /// The handler stays registered for as long as the device runs.
+    pub fn subscribe<F>(&self, on_event: F) -> anyhow::Result<()>
+    where
+        F: Fn(WifiDriverEvent) + Send + 'static,
+    {
+        let handler: WifiDriverEventHandler = Box::new(on_event);
+        // never freed, the event loop keeps calling it
+        let handler_arg = Box::into_raw(Box::new(handler)).cast::<c_void>();
+
+        // SAFETY: ESP IDF related sys call; [handler_arg] lives for as long as the device runs
+        unsafe {
+            esp!(esp_idf_sys::esp_event_handler_register(
+                esp_idf_sys::WIFI_EVENT,
+                esp_idf_sys::wifi_event_t_WIFI_EVENT_STA_DISCONNECTED as i32,
+                Some(Self::on_driver_event),
+                handler_arg,
+            ))
+            .map_err(|e| WifiError::Configuration("E0004c".to_owned(), e.to_string()))?;
+
+            esp!(esp_idf_sys::esp_event_handler_register(
+                esp_idf_sys::IP_EVENT,
+                esp_idf_sys::ip_event_t_IP_EVENT_STA_GOT_IP as i32,
+                Some(Self::on_driver_event),
+                handler_arg,
+            ))
+            .map_err(|e| WifiError::Configuration("E0004d".to_owned(), e.to_string()))?;
+        }
+
+        Ok(())
+    }
+
+    /// Called by the system event loop for the events registered in [subscribe].
+    unsafe extern "C" fn on_driver_event(
+        handler_arg: *mut c_void,
+        event_base: esp_event_base_t,
+        _event_id: i32,
+        _event_data: *mut c_void,
+    ) {
+        let event = if event_base == esp_idf_sys::WIFI_EVENT {
+            WifiDriverEvent::StaDisconnected
         } else {
-            log::error!(
-                "[network] the configured access point `{}` was not found during the scanning",
-                EnvValues::WIFI_SSID
-            );
-            return Err(
-                WifiError::ApNotFound("E0006".to_owned(), EnvValues::WIFI_SSID.to_owned()).into(),
-            );
+            WifiDriverEvent::StaGotIp
         };
 
-        Ok(channel)
+        let handler = &*handler_arg.cast::<WifiDriverEventHandler>();
+        handler(event);
+    }
+
+    /// How the station gets its ip address on the network of the [candidate].
+    pub fn ipv4_mode(&self, candidate: &WifiCandidate) -> WifiIpv4Mode {
+        self.networks
+            .network(candidate)
+            .map_or(WifiIpv4Mode::Dhcp, WifiNetwork::ipv4_mode)
+    }
+
+    const fn auth_method(auth: WifiAuth) -> AuthMethod {
+        match auth {
+            WifiAuth::None => AuthMethod::None,
+            WifiAuth::Wep => AuthMethod::WEP,
+            WifiAuth::Wpa => AuthMethod::WPA,
+            WifiAuth::Wpa2 => AuthMethod::WPA2Personal,
+            WifiAuth::Wpa3 => AuthMethod::WPA3Personal,
+            WifiAuth::Wpa2Wpa3 => AuthMethod::WPA2WPA3Personal,
+        }
     }
 
-    pub fn new() -> anyhow::Result<Self> {
+    pub fn new(
+        default_nvs: Arc<EspDefaultNvs>,
+        networks: Vec<WifiNetwork>,
+    ) -> anyhow::Result<Self> {
         let netif_stack = Arc::new(EspNetifStack::new()?);
         let sys_loop_stack = Arc::new(EspSysLoopStack::new()?);
-        let default_nvs = Arc::new(EspDefaultNvs::new()?);
 
         let esp_wifi = EspWifi::new(netif_stack, sys_loop_stack, default_nvs)?;
 
@@ -100,6 +252,9 @@ impl WifiAdaptor {
 
         log::debug!("[network] wifi adaptor created");
 
-        Ok(Self { esp_wifi })
+        Ok(Self {
+            esp_wifi,
+            networks: WifiNetworkSelector::new(networks),
+        })
     }
 }
@@ -0,0 +1,52 @@
+use crate::common::errors::provisioning_errors::ProvisioningError;
+use crate::common::models::device_settings::DeviceSettings;
+use embedded_svc::storage::RawStorage;
+use esp_idf_svc::nvs::EspDefaultNvs;
+use esp_idf_svc::nvs_storage::EspNvsStorage;
+use std::sync::Arc;
+
+const NVS_NAMESPACE: &str = "sirius";
+const NVS_SETTINGS_KEY: &str = "settings";
+
+/// Keeps the [DeviceSettings] in the NVS, as json, so that they survive a reboot or a reflash.
+pub struct DeviceSettingsStore {
+    storage: EspNvsStorage,
+}
+
+impl DeviceSettingsStore {
+    /// The stored settings; [None] if nothing has been stored yet.
+    pub fn load(&self) -> anyhow::Result<Option<DeviceSettings>> {
+        let raw = self
+            .storage
+            .get_raw(NVS_SETTINGS_KEY)
+            .map_err(|e| ProvisioningError::Storage("E0044a".to_owned(), e.to_string()))?;
+
+        match raw {
+            None => Ok(None),
+            Some(r) => {
+                let settings = serde_json::from_slice::<DeviceSettings>(&r)
+                    .map_err(|e| ProvisioningError::Storage("E0044b".to_owned(), e.to_string()))?;
+
+                Ok(Some(settings))
+            }
+        }
+    }
+
+    pub fn save(&mut self, settings: &DeviceSettings) -> anyhow::Result<()> {
+        let raw = serde_json::to_vec(settings)
+            .map_err(|e| ProvisioningError::Storage("E0044c".to_owned(), e.to_string()))?;
+
+        self.storage
+            .put_raw(NVS_SETTINGS_KEY, raw)
+            .map_err(|e| ProvisioningError::Storage("E0044d".to_owned(), e.to_string()))?;
+
+        Ok(())
+    }
+
+    pub fn new(default_nvs: Arc<EspDefaultNvs>) -> anyhow::Result<Self> {
+        let storage = EspNvsStorage::new_default(default_nvs, NVS_NAMESPACE, true)
+            .map_err(|e| ProvisioningError::Storage("E0044".to_owned(), e.to_string()))?;
+
+        Ok(Self { storage })
+    }
+}
@@ -0,0 +1,608 @@
+use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
+use std::net::Ipv4Addr;
+use std::str::FromStr;
+use strum_macros::EnumString;
+
+/// The weakest access point the station joins while a stronger one of the configured networks
+/// is around.
+const WEAK_RSSI_DBM: i8 = -80;
+
+/// The security of a configured network.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumString, Serialize, Deserialize)]
+pub enum WifiAuth {
+    #[strum(serialize = "none")]
+    #[serde(rename = "none")]
+    None,
+
+    #[strum(serialize = "wep")]
+    #[serde(rename = "wep")]
+    Wep,
+
+    #[strum(serialize = "wpa")]
+    #[serde(rename = "wpa")]
+    Wpa,
+
+    #[strum(serialize = "wpa2")]
+    #[serde(rename = "wpa2")]
+    Wpa2,
+
+    #[strum(serialize = "wpa3")]
+    #[serde(rename = "wpa3")]
+    Wpa3,
+
+    #[strum(serialize = "wpa2_wpa3")]
+    #[serde(rename = "wpa2_wpa3")]
+    Wpa2Wpa3,
+}
+
+/// A network the station may join; the networks are tried in the order they are configured.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WifiNetwork {
+    pub ssid: String,
+    pub password: String,
+    pub auth: WifiAuth,
+
+    /// [None] if any access point with the [ssid] will do
+    pub bssid: Option<[u8; 6]>,
+
+    /// [None] if the ip address is taken from the DHCP server
+    #[serde(default)]
+    pub static_ipv4: Option<StaticIpv4>,
+}
+
+impl WifiNetwork {
+    /// Parses the networks separated by `;`, each as `ssid,password,auth[,bssid]`,
+    /// eg: `Home,secret,wpa2;Hotspot,secret,wpa2,aa:bb:cc:dd:ee:ff`.
+    /// A `\` takes the next character as it is, eg: `a\;b` for the password `a;b`.
+    pub fn parse_list(s: &str) -> anyhow::Result<Vec<Self>> {
+        split_list(s)?.into_iter().map(Self::from_fields).collect()
+    }
+
+    /// Gives each of the [networks] its static ip configuration, by the ssid.
+    /// Returns the ssids in [static_ipv4s] which none of the [networks] has.
+    pub fn assign_static_ipv4s(
+        networks: &mut [Self],
+        static_ipv4s: Vec<(String, StaticIpv4)>,
+    ) -> Vec<String> {
+        let mut unknown_ssids = vec![];
+
+        for (ssid, static_ipv4) in static_ipv4s {
+            let mut is_known = false;
+            for n in networks.iter_mut().filter(|n| n.ssid == ssid) {
+                n.static_ipv4 = Some(static_ipv4);
+                is_known = true;
+            }
+
+            if !is_known {
+                unknown_ssids.push(ssid);
+            }
+        }
+
+        unknown_ssids
+    }
+
+    pub const fn ipv4_mode(&self) -> WifiIpv4Mode {
+        match self.static_ipv4 {
+            None => WifiIpv4Mode::Dhcp,
+            Some(_) => WifiIpv4Mode::Static,
+        }
+    }
+
+    fn from_fields(fields: Vec<String>) -> anyhow::Result<Self> {
+        let (ssid, password, auth, bssid) = match fields.as_slice() {
+            [ssid, password, auth] => (ssid, password, auth, None),
+            [ssid, password, auth, bssid] => (ssid, password, auth, Some(bssid)),
+            _ => {
+                return Err(anyhow::anyhow!(
+                    "expected `ssid,password,auth[,bssid]`, got {} fields",
+                    fields.len()
+                ))
+            }
+        };
+
+        if ssid.is_empty() || ssid.len() > 32 {
+            return Err(anyhow::anyhow!(
+                "the ssid {:?} is not 1 to 32 bytes long",
+                ssid
+            ));
+        }
+        if password.len() > 64 {
+            return Err(anyhow::anyhow!(
+                "the password of {:?} is longer than 64 bytes",
+                ssid
+            ));
+        }
+
+        let bssid = match bssid.map(|b| b.trim()) {
+            None | Some("") => None,
+            Some(b) => Some(Self::parse_bssid(b)?),
+        };
+
+        Ok(Self {
+            ssid: ssid.to_owned(),
+            password: password.to_owned(),
+            auth: WifiAuth::from_str(auth.trim())?,
+            bssid,
+            static_ipv4: None,
+        })
+    }
+
+    /// Parses `aa:bb:cc:dd:ee:ff`.
+    fn parse_bssid(s: &str) -> anyhow::Result<[u8; 6]> {
+        let octets = s
+            .split(':')
+            .map(|o| u8::from_str_radix(o, 16))
+            .collect::<Result<Vec<u8>, _>>()?;
+
+        <[u8; 6]>::try_from(octets.as_slice())
+            .map_err(|_| anyhow::anyhow!("expected `aa:bb:cc:dd:ee:ff`, got {:?}", s))
+    }
+}
+
+/// How the station gets its ip address; reported to Sirius Proxima.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WifiIpv4Mode {
+    #[serde(rename = "dhcp")]
+    Dhcp,
+
+    #[serde(rename = "static")]
+    Static,
+}
+
+/// A fixed ip configuration, for the routers with a broken DHCP server or with port forwarding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StaticIpv4 {
+    pub address: Ipv4Addr,
+
+    /// length of the netmask, eg: 24 for `255.255.255.0`
+    pub prefix_len: u8,
+
+    pub gateway: Ipv4Addr,
+    pub dns: Ipv4Addr,
+    pub secondary_dns: Option<Ipv4Addr>,
+}
+
+impl StaticIpv4 {
+    /// Parses the static ip configurations separated by `;`, each with the ssid of its network
+    /// as `ssid,address/prefix_len,gateway,dns[,secondary_dns]`,
+    /// eg: `Home,192.168.1.50/24,192.168.1.1,192.168.1.1,8.8.8.8`.
+    /// `none` if every network uses the DHCP.
+    pub fn parse_list(s: &str) -> anyhow::Result<Vec<(String, Self)>> {
+        if s.trim() == "none" {
+            return Ok(vec![]);
+        }
+
+        split_list(s)?
+            .into_iter()
+            .map(|fields| {
+                let (ssid, address, gateway, dns, secondary_dns) = match fields.as_slice() {
+                    [ssid, address, gateway, dns] => (ssid, address, gateway, dns, None),
+                    [ssid, address, gateway, dns, secondary_dns] => {
+                        (ssid, address, gateway, dns, Some(secondary_dns))
+                    }
+                    _ => {
+                        return Err(anyhow::anyhow!(
+                            "expected `ssid,address/prefix_len,gateway,dns[,secondary_dns]`, \
+                            got {} fields",
+                            fields.len()
+                        ))
+                    }
+                };
+
+                let (address, prefix_len) = address.split_once('/').ok_or_else(|| {
+                    anyhow::anyhow!("expected `address/prefix_len`, got {:?}", address)
+                })?;
+
+                let static_ipv4 = Self {
+                    address: Ipv4Addr::from_str(address.trim())?,
+                    prefix_len: prefix_len.trim().parse::<u8>()?,
+                    gateway: Ipv4Addr::from_str(gateway.trim())?,
+                    dns: Ipv4Addr::from_str(dns.trim())?,
+                    secondary_dns: secondary_dns
+                        .map(|d| Ipv4Addr::from_str(d.trim()))
+                        .transpose()?,
+                };
+                static_ipv4.validate()?;
+
+                Ok((ssid.to_owned(), static_ipv4))
+            })
+            .collect()
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        if !(1..=30).contains(&self.prefix_len) {
+            return Err(anyhow::anyhow!(
+                "the prefix length {} is not 1 to 30",
+                self.prefix_len
+            ));
+        }
+
+        let mask = u32::MAX << (32 - self.prefix_len);
+        let address = u32::from(self.address);
+        if address & mask != u32::from(self.gateway) & mask {
+            return Err(anyhow::anyhow!(
+                "the gateway {} is not in the subnet of {}/{}",
+                self.gateway,
+                self.address,
+                self.prefix_len
+            ));
+        }
+        if address & !mask == 0 || address & !mask == !mask {
+            return Err(anyhow::anyhow!(
+                "{} is the network or the broadcast address of its subnet",
+                self.address
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// Splits the entries separated by `;` into their fields separated by `,`.
+/// A `\` takes the next character as it is, eg: `a\;b` for `a;b`. Empty entries are skipped.
+fn split_list(s: &str) -> anyhow::Result<Vec<Vec<String>>> {
+    let mut entries: Vec<Vec<String>> = vec![];
+    let mut fields: Vec<String> = vec![];
+    let mut field = String::new();
+    let mut chars = s.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(escaped) => field.push(escaped),
+                None => return Err(anyhow::anyhow!("a `\\` at the end of {:?}", s)),
+            },
+            ',' => fields.push(std::mem::take(&mut field)),
+            ';' => {
+                fields.push(std::mem::take(&mut field));
+                entries.push(std::mem::take(&mut fields));
+            }
+            c => field.push(c),
+        }
+    }
+    fields.push(field);
+    entries.push(fields);
+
+    Ok(entries
+        .into_iter()
+        .filter(|f| !matches!(f.as_slice(), [only] if only.trim().is_empty()))
+        .collect())
+}
+
+/// An access point found by the scan.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScannedAccessPoint {
+    pub ssid: String,
+    pub bssid: [u8; 6],
+    pub channel: u8,
+    pub rssi: i8,
+}
+
+/// The access point picked to join, and the configured network it belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WifiCandidate {
+    /// position of the network in the configured list
+    pub network_index: usize,
+    pub bssid: [u8; 6],
+    pub channel: u8,
+    pub rssi: i8,
+}
+
+/// Picks the access point to join from a scan: the first configured network which is visible
+/// wins, and the strongest access point of it is joined. An access point weaker than
+/// [WEAK_RSSI_DBM] is only joined if there is nothing stronger.
+///
+/// A network which has failed is passed over until every visible network has failed, so that
+/// the station fails over to the next one and comes back round to the first.
+#[derive(Debug, Clone)]
+pub struct WifiNetworkSelector {
+    networks: Vec<WifiNetwork>,
+    has_failed: Vec<bool>,
+}
+
+impl WifiNetworkSelector {
+    pub fn new(networks: Vec<WifiNetwork>) -> Self {
+        let has_failed = vec![false; networks.len()];
+
+        Self {
+            networks,
+            has_failed,
+        }
+    }
+
+    pub fn networks(&self) -> &[WifiNetwork] {
+        &self.networks
+    }
+
+    pub fn network(&self, candidate: &WifiCandidate) -> Option<&WifiNetwork> {
+        self.networks.get(candidate.network_index)
+    }
+
+    /// The access point to join; [None] if none of the configured networks is visible.
+    pub fn select(&mut self, access_points: &[ScannedAccessPoint]) -> Option<WifiCandidate> {
+        let candidates: Vec<WifiCandidate> = access_points
+            .iter()
+            .filter_map(|ap| {
+                let network_index = self.networks.iter().position(|n| {
+                    n.ssid == ap.ssid && !matches!(n.bssid, Some(b) if b != ap.bssid)
+                })?;
+
+                Some(WifiCandidate {
+                    network_index,
+                    bssid: ap.bssid,
+                    channel: ap.channel,
+                    rssi: ap.rssi,
+                })
+            })
+            .collect();
+
+        if candidates.iter().all(|c| self.has_failed(c.network_index)) {
+            // every visible network has failed; start over
+            self.has_failed.iter_mut().for_each(|f| *f = false);
+        }
+
+        candidates
+            .into_iter()
+            .filter(|c| !self.has_failed(c.network_index))
+            .min_by_key(|c| (c.rssi < WEAK_RSSI_DBM, c.network_index, -(c.rssi as i16)))
+    }
+
+    fn has_failed(&self, network_index: usize) -> bool {
+        self.has_failed.get(network_index).copied().unwrap_or(false)
+    }
+
+    /// The station couldn't join the network of the [candidate], or it went away.
+    pub fn mark_failed(&mut self, candidate: &WifiCandidate) {
+        if let Some(f) = self.has_failed.get_mut(candidate.network_index) {
+            *f = true;
+        }
+    }
+
+    /// The station is online; every network gets another chance on the next scan.
+    pub fn mark_connected(&mut self) {
+        self.has_failed.iter_mut().for_each(|f| *f = false);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn network(ssid: &str) -> WifiNetwork {
+        WifiNetwork {
+            ssid: ssid.to_owned(),
+            password: "password".to_owned(),
+            auth: WifiAuth::Wpa2,
+            bssid: None,
+            static_ipv4: None,
+        }
+    }
+
+    fn access_point(ssid: &str, last_octet: u8, rssi: i8) -> ScannedAccessPoint {
+        ScannedAccessPoint {
+            ssid: ssid.to_owned(),
+            bssid: [0xAA, 0xBB, 0xCC, 0xDD, 0xEE, last_octet],
+            channel: 6,
+            rssi,
+        }
+    }
+
+    fn selector(ssids: &[&str]) -> WifiNetworkSelector {
+        WifiNetworkSelector::new(ssids.iter().map(|s| network(s)).collect())
+    }
+
+    #[test]
+    fn parse_list_reads_every_network() {
+        let networks = WifiNetwork::parse_list(
+            "Home,secret,wpa2;Cafe,,none;Hotspot,secret,wpa3,aa:bb:cc:dd:ee:0f;",
+        )
+        .unwrap();
+
+        assert_eq!(networks.len(), 3);
+        assert_eq!(
+            networks[0],
+            WifiNetwork {
+                password: "secret".to_owned(),
+                ..network("Home")
+            }
+        );
+        assert_eq!(networks[1].auth, WifiAuth::None);
+        assert_eq!(networks[1].password, "");
+        assert_eq!(networks[2].auth, WifiAuth::Wpa3);
+        assert_eq!(
+            networks[2].bssid,
+            Some([0xAA, 0xBB, 0xCC, 0xDD, 0xEE, 0x0F])
+        );
+    }
+
+    #[test]
+    fn parse_list_takes_escaped_separators() {
+        let networks = WifiNetwork::parse_list(r"My\,Home,pass\;word,wpa2").unwrap();
+
+        assert_eq!(networks[0].ssid, "My,Home");
+        assert_eq!(networks[0].password, "pass;word");
+    }
+
+    #[test]
+    fn parse_list_rejects_broken_networks() {
+        for s in [
+            "Home,secret",
+            "Home,secret,wpa9",
+            ",secret,wpa2",
+            "Home,secret,wpa2,aa:bb:cc",
+            "Home,secret,wpa2,zz:bb:cc:dd:ee:ff",
+            r"Home,secret,wpa2\",
+        ] {
+            assert!(WifiNetwork::parse_list(s).is_err(), "{:?}", s);
+        }
+        assert!(WifiNetwork::parse_list(&format!("{},secret,wpa2", "s".repeat(33))).is_err());
+    }
+
+    #[test]
+    fn static_ipv4_parse_list_reads_every_config() {
+        let static_ipv4s = StaticIpv4::parse_list(
+            "Home,192.168.1.50/24,192.168.1.1,192.168.1.1,8.8.8.8;Office,10.0.0.9/8,10.0.0.1,1.1.1.1",
+        )
+        .unwrap();
+
+        assert_eq!(
+            static_ipv4s,
+            vec![
+                (
+                    "Home".to_owned(),
+                    StaticIpv4 {
+                        address: Ipv4Addr::new(192, 168, 1, 50),
+                        prefix_len: 24,
+                        gateway: Ipv4Addr::new(192, 168, 1, 1),
+                        dns: Ipv4Addr::new(192, 168, 1, 1),
+                        secondary_dns: Some(Ipv4Addr::new(8, 8, 8, 8)),
+                    }
+                ),
+                (
+                    "Office".to_owned(),
+                    StaticIpv4 {
+                        address: Ipv4Addr::new(10, 0, 0, 9),
+                        prefix_len: 8,
+                        gateway: Ipv4Addr::new(10, 0, 0, 1),
+                        dns: Ipv4Addr::new(1, 1, 1, 1),
+                        secondary_dns: None,
+                    }
+                ),
+            ]
+        );
+        assert_eq!(StaticIpv4::parse_list("none").unwrap(), vec![]);
+    }
+
+    #[test]
+    fn static_ipv4_parse_list_rejects_a_bad_prefix_length() {
+        for prefix_len in ["0", "31", "32", "33", "x", ""] {
+            let s = format!("Home,192.168.1.50/{},192.168.1.1,192.168.1.1", prefix_len);
+
+            assert!(StaticIpv4::parse_list(&s).is_err(), "{:?}", s);
+        }
+        assert!(StaticIpv4::parse_list("Home,192.168.1.50,192.168.1.1,192.168.1.1").is_err());
+    }
+
+    #[test]
+    fn static_ipv4_parse_list_rejects_a_gateway_outside_of_the_subnet() {
+        assert!(StaticIpv4::parse_list("Home,192.168.1.50/24,192.168.2.1,192.168.1.1").is_err());
+        assert!(StaticIpv4::parse_list("Home,192.168.1.50/16,192.168.2.1,192.168.1.1").is_ok());
+    }
+
+    #[test]
+    fn static_ipv4_parse_list_rejects_the_network_and_broadcast_addresses() {
+        assert!(StaticIpv4::parse_list("Home,192.168.1.0/24,192.168.1.1,192.168.1.1").is_err());
+        assert!(StaticIpv4::parse_list("Home,192.168.1.255/24,192.168.1.1,192.168.1.1").is_err());
+    }
+
+    #[test]
+    fn assign_static_ipv4s_returns_the_unknown_ssids() {
+        let static_ipv4s = StaticIpv4::parse_list(
+            "Home,192.168.1.50/24,192.168.1.1,192.168.1.1;Cafe,10.0.0.9/8,10.0.0.1,10.0.0.1",
+        )
+        .unwrap();
+        let home = static_ipv4s[0].1;
+        let mut networks = vec![network("Home"), network("Office")];
+
+        let unknown_ssids = WifiNetwork::assign_static_ipv4s(&mut networks, static_ipv4s);
+
+        assert_eq!(unknown_ssids, vec!["Cafe".to_owned()]);
+        assert_eq!(networks[0].static_ipv4, Some(home));
+        assert_eq!(networks[0].ipv4_mode(), WifiIpv4Mode::Static);
+        assert_eq!(networks[1].static_ipv4, None);
+        assert_eq!(networks[1].ipv4_mode(), WifiIpv4Mode::Dhcp);
+    }
+
+    #[test]
+    fn select_prefers_the_configured_order_then_the_strongest() {
+        let mut selector = selector(&["Home", "Office"]);
+        let access_points = [
+            access_point("Office", 1, -40),
+            access_point("Home", 2, -70),
+            access_point("Home", 3, -60),
+            access_point("Neighbour", 4, -30),
+        ];
+
+        let candidate = selector.select(&access_points).unwrap();
+
+        assert_eq!(candidate.network_index, 0);
+        assert_eq!(candidate.rssi, -60);
+        assert_eq!(candidate.bssid, access_points[2].bssid);
+    }
+
+    #[test]
+    fn select_passes_over_a_weak_access_point() {
+        let mut selector = selector(&["Home", "Office"]);
+
+        let candidate = selector
+            .select(&[access_point("Home", 1, -85), access_point("Office", 2, -75)])
+            .unwrap();
+        assert_eq!(candidate.network_index, 1);
+
+        // a weak access point is still joined if there is nothing stronger
+        let candidate = selector
+            .select(&[access_point("Home", 1, -85), access_point("Office", 2, -90)])
+            .unwrap();
+        assert_eq!(candidate.network_index, 0);
+    }
+
+    #[test]
+    fn select_only_takes_the_pinned_bssid() {
+        let mut selector = WifiNetworkSelector::new(vec![WifiNetwork {
+            bssid: Some([0xAA, 0xBB, 0xCC, 0xDD, 0xEE, 2]),
+            ..network("Home")
+        }]);
+
+        let candidate = selector
+            .select(&[access_point("Home", 1, -40), access_point("Home", 2, -70)])
+            .unwrap();
+        assert_eq!(candidate.rssi, -70);
+
+        assert_eq!(selector.select(&[access_point("Home", 1, -40)]), None);
+    }
+
+    #[test]
+    fn select_fails_over_to_the_next_network() {
+        let mut selector = selector(&["Home", "Office"]);
+        let access_points = [access_point("Home", 1, -50), access_point("Office", 2, -50)];
+
+        let home = selector.select(&access_points).unwrap();
+        selector.mark_failed(&home);
+
+        assert_eq!(selector.select(&access_points).unwrap().network_index, 1);
+    }
+
+    #[test]
+    fn select_starts_over_once_every_network_has_failed() {
+        let mut selector = selector(&["Home", "Office"]);
+        let access_points = [access_point("Home", 1, -50), access_point("Office", 2, -50)];
+
+        let home = selector.select(&access_points).unwrap();
+        selector.mark_failed(&home);
+        let office = selector.select(&access_points).unwrap();
+        selector.mark_failed(&office);
+
+        assert_eq!(selector.select(&access_points).unwrap().network_index, 0);
+    }
+
+    #[test]
+    fn mark_connected_gives_every_network_another_chance() {
+        let mut selector = selector(&["Home", "Office"]);
+        let access_points = [access_point("Home", 1, -50), access_point("Office", 2, -50)];
+
+        let home = selector.select(&access_points).unwrap();
+        selector.mark_failed(&home);
+        selector.mark_connected();
+
+        assert_eq!(selector.select(&access_points).unwrap().network_index, 0);
+    }
+
+    #[test]
+    fn select_finds_nothing_without_a_configured_network() {
+        let mut selector = selector(&["Home"]);
+
+        assert_eq!(selector.select(&[access_point("Neighbour", 1, -30)]), None);
+        assert_eq!(selector.select(&[]), None);
+    }
+}
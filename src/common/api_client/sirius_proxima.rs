@@ -5,8 +5,10 @@ use crate::common::models::sirius_proxima_api::{
 };
 use crate::constants::default_values::DefaultValues;
 use crate::constants::environment::APP_ENV;
+use crate::helpers::wall_clock::WALL_CLOCK;
+use crate::libs::segment_display::bargraph::BargraphStyle;
 use crate::EnvValues;
-use attohttpc::header::{HeaderValue, IntoHeaderName};
+use attohttpc::header::{HeaderValue, IntoHeaderName, DATE};
 use attohttpc::{Error, ErrorKind, Response, StatusCode};
 use lazy_static::lazy_static;
 use log::{debug, error, info, warn};
@@ -42,6 +44,13 @@ impl SiriusProximaClient {
                 let status_code = server_response.status();
                 let headers = server_response.headers().clone();
 
+                // the device has no clock of its own; take the time of the day from the server
+                if let Some(date) = headers.get(DATE).and_then(|d| d.to_str().ok()) {
+                    if !WALL_CLOCK.sync_from_http_date(date) {
+                        warn!("[SiriusProximaClient] invalid date header: {}", date);
+                    }
+                }
+
                 let resp_text = server_response.text();
                 let resp_text_ok = match resp_text {
                     Ok(s) => {
@@ -230,4 +239,35 @@ pub struct PingResponse {
     pub short_period_buzzer_beep_duration_ms: usize,
     pub is_continuous_period_buzzer_beep_active: bool,
     pub is_first_ping_after_device_turned_on_registered: bool,
+
+    /// asks the device to run the segment display self test
+    #[serde(default)]
+    pub is_segment_display_self_test_requested: bool,
+
+    /// text for the secondary segment display, eg: a live value; [None] keeps the current text,
+    /// which is cleared once it hasn't been sent for
+    /// [DefaultValues::SECONDARY_SEGMENT_DISPLAY_TEXT_TTL_MS]
+    #[serde(default)]
+    pub secondary_segment_display_text: Option<String>,
+
+    /// texts of the segment display by their name, eg: `err_no_wifi`, used in place of the
+    /// compiled-in catalog; an empty map goes back to it, [None] keeps the current texts
+    #[serde(default)]
+    pub segment_display_texts: Option<HashMap<String, String>>,
+
+    /// level to show as a bargraph on the segment display, eg: the water in the tank;
+    /// [None] shows nothing
+    #[serde(default)]
+    pub segment_display_level: Option<SegmentDisplayLevel>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SegmentDisplayLevel {
+    /// 0 to 100
+    pub percent: u8,
+    pub style: BargraphStyle,
+
+    /// percents which are marked on the scale, eg: the pump cut-off level
+    #[serde(default)]
+    pub thresholds: Vec<u8>,
 }
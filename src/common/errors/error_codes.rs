@@ -0,0 +1,341 @@
+use crate::common::errors::api_errors::{ApiClientError, ApiResponseError};
+use crate::common::errors::device_errors::DeviceError;
+use crate::common::errors::provisioning_errors::ProvisioningError;
+use crate::common::errors::wifi_errors::WifiError;
+use crate::libs::max7219::errors::Max7219Error;
+use crate::libs::tm1637::errors::TmError;
+use crate::libs::tm1638::errors::Tm1638Error;
+use esp_idf_sys::EspError;
+use lazy_static::lazy_static;
+use std::sync::atomic::{AtomicU16, Ordering};
+
+lazy_static! {
+    pub static ref ERROR_CODES: ErrorCodeLog = ErrorCodeLog::new();
+}
+
+/// How much an error matters to the people using the device.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ErrorSeverity {
+    /// The device goes on as usual, eg: a text which can't be shown.
+    Info,
+
+    /// The device is expected to recover by itself, eg: a dropped Wi-Fi connection.
+    Warning,
+
+    /// The device needs someone to look at it, eg: the access point isn't there or the display
+    /// is missing.
+    Critical,
+}
+
+/// The subsystems of the [ErrorCode] registry, by the hundreds of the numbers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCodeGroup {
+    Wifi = 1,
+    ApiClient = 2,
+    ApiResponse = 3,
+    Display = 4,
+    Device = 5,
+    Provisioning = 6,
+}
+
+/// The error codes which are shown to the users, so that they can read them out to the support.
+/// The numbers are grouped by hundreds:
+/// * 1xx: Wi-Fi ([WifiError])
+/// * 2xx: API client ([ApiClientError])
+/// * 3xx: API response ([ApiResponseError])
+/// * 4xx: segment display ([TmError], [Tm1638Error], [Max7219Error])
+/// * 5xx: device ([DeviceError])
+/// * 6xx: provisioning ([ProvisioningError])
+///
+/// The numbers are part of the support documentation; never reuse or change them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCode {
+    WifiScanning,
+    WifiApNotFound,
+    WifiConfiguration,
+    WifiNotConnected,
+    WifiUnresolvedIp,
+
+    ApiClientResponse,
+    ApiClientJsonParsing,
+
+    ApiSiteNotFound,
+    ApiInternalServerError,
+    ApiBadRequest,
+    ApiNotFound,
+
+    DisplayAck,
+    DisplayIo,
+    DisplayAutoScroll,
+    DisplaySize,
+    DisplayDigitOrder,
+    DisplayGlyph,
+
+    DevicePeripheralPin,
+
+    ProvisioningStorage,
+    ProvisioningPortal,
+}
+
+impl ErrorCode {
+    const ALL: [ErrorCode; 20] = [
+        ErrorCode::WifiScanning,
+        ErrorCode::WifiApNotFound,
+        ErrorCode::WifiConfiguration,
+        ErrorCode::WifiNotConnected,
+        ErrorCode::WifiUnresolvedIp,
+        ErrorCode::ApiClientResponse,
+        ErrorCode::ApiClientJsonParsing,
+        ErrorCode::ApiSiteNotFound,
+        ErrorCode::ApiInternalServerError,
+        ErrorCode::ApiBadRequest,
+        ErrorCode::ApiNotFound,
+        ErrorCode::DisplayAck,
+        ErrorCode::DisplayIo,
+        ErrorCode::DisplayAutoScroll,
+        ErrorCode::DisplaySize,
+        ErrorCode::DisplayDigitOrder,
+        ErrorCode::DisplayGlyph,
+        ErrorCode::DevicePeripheralPin,
+        ErrorCode::ProvisioningStorage,
+        ErrorCode::ProvisioningPortal,
+    ];
+
+    pub const fn number(self) -> u16 {
+        match self {
+            ErrorCode::WifiScanning => 101,
+            ErrorCode::WifiApNotFound => 102,
+            ErrorCode::WifiConfiguration => 103,
+            ErrorCode::WifiNotConnected => 104,
+            ErrorCode::WifiUnresolvedIp => 105,
+
+            ErrorCode::ApiClientResponse => 201,
+            ErrorCode::ApiClientJsonParsing => 202,
+
+            ErrorCode::ApiSiteNotFound => 301,
+            ErrorCode::ApiInternalServerError => 302,
+            ErrorCode::ApiBadRequest => 303,
+            ErrorCode::ApiNotFound => 304,
+
+            ErrorCode::DisplayAck => 401,
+            ErrorCode::DisplayIo => 402,
+            ErrorCode::DisplayAutoScroll => 403,
+            ErrorCode::DisplaySize => 404,
+            ErrorCode::DisplayDigitOrder => 405,
+            ErrorCode::DisplayGlyph => 406,
+
+            ErrorCode::DevicePeripheralPin => 501,
+
+            ErrorCode::ProvisioningStorage => 601,
+            ErrorCode::ProvisioningPortal => 602,
+        }
+    }
+
+    pub const fn severity(self) -> ErrorSeverity {
+        match self {
+            ErrorCode::WifiScanning
+            | ErrorCode::WifiNotConnected
+            | ErrorCode::WifiUnresolvedIp
+            | ErrorCode::ApiClientResponse
+            | ErrorCode::ApiSiteNotFound
+            | ErrorCode::ApiInternalServerError => ErrorSeverity::Warning,
+
+            ErrorCode::DisplayAutoScroll | ErrorCode::DisplayGlyph => ErrorSeverity::Info,
+
+            ErrorCode::WifiApNotFound
+            | ErrorCode::WifiConfiguration
+            | ErrorCode::ApiClientJsonParsing
+            | ErrorCode::ApiBadRequest
+            | ErrorCode::ApiNotFound
+            | ErrorCode::DisplayAck
+            | ErrorCode::DisplayIo
+            | ErrorCode::DisplaySize
+            | ErrorCode::DisplayDigitOrder
+            | ErrorCode::DevicePeripheralPin
+            | ErrorCode::ProvisioningStorage
+            | ErrorCode::ProvisioningPortal => ErrorSeverity::Critical,
+        }
+    }
+
+    /// The code as it is shown on the display, eg: `E102`; it fits on 4 digits.
+    pub fn short(self) -> String {
+        format!("E{}", self.number())
+    }
+
+    pub const fn group(self) -> ErrorCodeGroup {
+        match self.number() / 100 {
+            1 => ErrorCodeGroup::Wifi,
+            2 => ErrorCodeGroup::ApiClient,
+            3 => ErrorCodeGroup::ApiResponse,
+            4 => ErrorCodeGroup::Display,
+            5 => ErrorCodeGroup::Device,
+            _ => ErrorCodeGroup::Provisioning,
+        }
+    }
+
+    pub fn from_number(number: u16) -> Option<Self> {
+        Self::ALL.iter().copied().find(|c| c.number() == number)
+    }
+
+    /// The code of an error which has been passed on as an [anyhow::Error]; [None] if the error
+    /// is not in the registry.
+    pub fn of(err: &anyhow::Error) -> Option<Self> {
+        if let Some(e) = err.downcast_ref::<WifiError>() {
+            return Some(e.error_code());
+        }
+        if let Some(e) = err.downcast_ref::<ApiClientError>() {
+            return Some(e.error_code());
+        }
+        if let Some(e) = err.downcast_ref::<ApiResponseError>() {
+            return Some(e.error_code());
+        }
+        if let Some(e) = err.downcast_ref::<TmError<EspError>>() {
+            return Some(e.error_code());
+        }
+        if let Some(e) = err.downcast_ref::<Tm1638Error<EspError>>() {
+            return Some(e.error_code());
+        }
+        if let Some(e) = err.downcast_ref::<Max7219Error<EspError>>() {
+            return Some(e.error_code());
+        }
+        if let Some(e) = err.downcast_ref::<DeviceError<'static>>() {
+            return Some(e.error_code());
+        }
+        if let Some(e) = err.downcast_ref::<ProvisioningError>() {
+            return Some(e.error_code());
+        }
+
+        None
+    }
+}
+
+/// An error which has a code in the [ErrorCode] registry.
+pub trait ToErrorCode {
+    fn error_code(&self) -> ErrorCode;
+}
+
+impl ToErrorCode for WifiError {
+    fn error_code(&self) -> ErrorCode {
+        match self {
+            WifiError::Scanning(_, _) => ErrorCode::WifiScanning,
+            WifiError::ApNotFound(_, _) => ErrorCode::WifiApNotFound,
+            WifiError::Configuration(_, _) => ErrorCode::WifiConfiguration,
+            WifiError::NotConnected(_) => ErrorCode::WifiNotConnected,
+            WifiError::UnresolvedIp(_) => ErrorCode::WifiUnresolvedIp,
+        }
+    }
+}
+
+impl ToErrorCode for ApiClientError {
+    fn error_code(&self) -> ErrorCode {
+        match self {
+            ApiClientError::Response(_, _) => ErrorCode::ApiClientResponse,
+            ApiClientError::JsonParsing(_, _) => ErrorCode::ApiClientJsonParsing,
+        }
+    }
+}
+
+impl ToErrorCode for ApiResponseError {
+    fn error_code(&self) -> ErrorCode {
+        match self {
+            ApiResponseError::SiteNotFound(_, _) => ErrorCode::ApiSiteNotFound,
+            ApiResponseError::InternalServerError(_, _, _) => ErrorCode::ApiInternalServerError,
+            ApiResponseError::BadRequest(_, _, _) => ErrorCode::ApiBadRequest,
+            ApiResponseError::NotFound(_, _, _) => ErrorCode::ApiNotFound,
+        }
+    }
+}
+
+impl<E> ToErrorCode for TmError<E> {
+    fn error_code(&self) -> ErrorCode {
+        match self {
+            TmError::Ack(_, _) => ErrorCode::DisplayAck,
+            TmError::IO(_) => ErrorCode::DisplayIo,
+            TmError::AutoScroll(_, _) => ErrorCode::DisplayAutoScroll,
+            TmError::DisplaySize(_, _) => ErrorCode::DisplaySize,
+            TmError::DigitOrder(_, _) => ErrorCode::DisplayDigitOrder,
+            TmError::Glyph(_, _) => ErrorCode::DisplayGlyph,
+        }
+    }
+}
+
+impl<E> ToErrorCode for Tm1638Error<E> {
+    fn error_code(&self) -> ErrorCode {
+        match self {
+            Tm1638Error::IO(_) => ErrorCode::DisplayIo,
+            Tm1638Error::AutoScroll(_, _) => ErrorCode::DisplayAutoScroll,
+        }
+    }
+}
+
+impl<E> ToErrorCode for Max7219Error<E> {
+    fn error_code(&self) -> ErrorCode {
+        match self {
+            Max7219Error::Spi(_) => ErrorCode::DisplayIo,
+            Max7219Error::AutoScroll(_, _) => ErrorCode::DisplayAutoScroll,
+            Max7219Error::DisplaySize(_, _) => ErrorCode::DisplaySize,
+        }
+    }
+}
+
+impl<'a> ToErrorCode for DeviceError<'a> {
+    fn error_code(&self) -> ErrorCode {
+        match self {
+            DeviceError::PeripheralPin(_, _) => ErrorCode::DevicePeripheralPin,
+        }
+    }
+}
+
+impl ToErrorCode for ProvisioningError {
+    fn error_code(&self) -> ErrorCode {
+        match self {
+            ProvisioningError::Storage(_, _) => ErrorCode::ProvisioningStorage,
+            ProvisioningError::Portal(_, _) => ErrorCode::ProvisioningPortal,
+        }
+    }
+}
+
+/// Keeps the most recent critical error, so that it can be shown on the display.
+/// The error is cleared once its subsystem has recovered, eg: the Wi-Fi is online again.
+#[derive(Debug, Default)]
+pub struct ErrorCodeLog {
+    /// number of the [ErrorCode]; 0 if there was none
+    latest_critical: AtomicU16,
+}
+
+impl ErrorCodeLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keeps the [code] if it is critical.
+    pub fn record(&self, code: ErrorCode) {
+        if code.severity() == ErrorSeverity::Critical {
+            self.latest_critical.store(code.number(), Ordering::Relaxed);
+        }
+    }
+
+    /// Keeps the code of the [err] if it is in the registry and critical.
+    pub fn record_anyhow(&self, err: &anyhow::Error) {
+        if let Some(code) = ErrorCode::of(err) {
+            self.record(code);
+        }
+    }
+
+    /// Clears the latest critical error if it belongs to the [group] which has recovered.
+    /// A newer error of another subsystem is kept.
+    pub fn clear(&self, group: ErrorCodeGroup) {
+        let _ = self
+            .latest_critical
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |number| {
+                match ErrorCode::from_number(number) {
+                    Some(code) if code.group() == group => Some(0),
+                    _ => None,
+                }
+            });
+    }
+
+    pub fn latest_critical(&self) -> Option<ErrorCode> {
+        ErrorCode::from_number(self.latest_critical.load(Ordering::Relaxed))
+    }
+}
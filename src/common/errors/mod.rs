@@ -1,4 +1,6 @@
 pub mod api_errors;
-pub mod device_errors;
 pub mod common_errors;
+pub mod device_errors;
+pub mod error_codes;
+pub mod provisioning_errors;
 pub mod wifi_errors;
@@ -0,0 +1,10 @@
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ProvisioningError {
+    #[error("{0:?} a settings storage error occurred: {1:?}")]
+    Storage(String, String),
+
+    #[error("{0:?} a provisioning portal error occurred: {1:?}")]
+    Portal(String, String),
+}
@@ -0,0 +1,26 @@
+use crate::common::adaptors::wifi_networks::WifiNetwork;
+use serde::{Deserialize, Serialize};
+
+/// The settings entered in the provisioning portal; they take the place of the compiled-in
+/// values.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DeviceSettings {
+    /// the networks the station may join, by priority
+    #[serde(default)]
+    pub wifi_networks: Vec<WifiNetwork>,
+
+    /// [None] if the compiled-in `DEVICE_NAME` is used
+    #[serde(default)]
+    pub device_name: Option<String>,
+
+    /// [None] if the compiled-in `DEVICE_LOCATION` is used
+    #[serde(default)]
+    pub device_location: Option<String>,
+}
+
+impl DeviceSettings {
+    /// True if a network the station may join has been saved in the provisioning portal.
+    pub fn has_wifi_networks(&self) -> bool {
+        self.wifi_networks.iter().any(|n| !n.ssid.is_empty())
+    }
+}
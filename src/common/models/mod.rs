@@ -1,2 +1,3 @@
 pub mod sirius_proxima_api;
 pub mod device;
+pub mod device_settings;
@@ -1,4 +1,7 @@
+use crate::common::adaptors::wifi_networks::WifiIpv4Mode;
 use crate::helpers::chip_info::{ChipInfo, Model};
+use crate::helpers::device_identity::DEVICE_IDENTITY;
+use crate::libs::segment_display::SegmentDisplaySelfTestResult;
 use crate::EnvValues;
 use serde::{Deserialize, Serialize};
 use std::str::FromStr;
@@ -32,10 +35,20 @@ pub struct SiriusProximaPing {
 }
 
 impl SiriusProximaPing {
-    pub fn new(is_first_ping_after_device_turned_on: bool) -> anyhow::Result<Self> {
+    pub fn new(
+        is_first_ping_after_device_turned_on: bool,
+        is_segment_display_missing: bool,
+        segment_display_self_test_result: SegmentDisplaySelfTestResult,
+        wifi_ipv4_mode: WifiIpv4Mode,
+    ) -> anyhow::Result<Self> {
         Ok(Self {
             device_type: DeviceType::from_str(EnvValues::DEVICE_TYPE)?,
-            device: Device::new(is_first_ping_after_device_turned_on)?,
+            device: Device::new(
+                is_first_ping_after_device_turned_on,
+                is_segment_display_missing,
+                segment_display_self_test_result,
+                wifi_ipv4_mode,
+            )?,
         })
     }
 }
@@ -47,10 +60,20 @@ pub struct Device {
 }
 
 impl Device {
-    pub fn new(is_first_ping_after_device_turned_on: bool) -> anyhow::Result<Self> {
+    pub fn new(
+        is_first_ping_after_device_turned_on: bool,
+        is_segment_display_missing: bool,
+        segment_display_self_test_result: SegmentDisplaySelfTestResult,
+        wifi_ipv4_mode: WifiIpv4Mode,
+    ) -> anyhow::Result<Self> {
         Ok(Self {
             device_type: DeviceType::from_str(EnvValues::DEVICE_TYPE)?,
-            details: DeviceDetails::new(is_first_ping_after_device_turned_on),
+            details: DeviceDetails::new(
+                is_first_ping_after_device_turned_on,
+                is_segment_display_missing,
+                segment_display_self_test_result,
+                wifi_ipv4_mode,
+            ),
         })
     }
 }
@@ -79,20 +102,31 @@ pub struct DeviceDetails {
     pub revision: u8,
     pub app_version: String,
     pub is_first_ping_after_device_turned_on: bool,
+    pub is_segment_display_missing: bool,
+    pub segment_display_self_test_result: SegmentDisplaySelfTestResult,
+    pub wifi_ipv4_mode: WifiIpv4Mode,
 }
 
 impl DeviceDetails {
-    pub fn new(is_first_ping_after_device_turned_on: bool) -> Self {
+    pub fn new(
+        is_first_ping_after_device_turned_on: bool,
+        is_segment_display_missing: bool,
+        segment_display_self_test_result: SegmentDisplaySelfTestResult,
+        wifi_ipv4_mode: WifiIpv4Mode,
+    ) -> Self {
         let chip = ChipInfo::new();
 
         Self {
-            device_name: EnvValues::DEVICE_NAME.to_owned(),
+            device_name: DEVICE_IDENTITY.device_name(),
             device_id: EnvValues::DEVICE_ID.to_owned(),
-            device_location: EnvValues::DEVICE_LOCATION.to_owned(),
+            device_location: DEVICE_IDENTITY.device_location(),
             app_version: EnvValues::APP_VERSION.to_owned(),
             model: chip.model.unwrap_or(Model::Unknown),
             revision: chip.revision,
             is_first_ping_after_device_turned_on,
+            is_segment_display_missing,
+            segment_display_self_test_result,
+            wifi_ipv4_mode,
         }
     }
 }
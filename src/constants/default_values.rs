@@ -10,9 +10,28 @@ pub struct DefaultValues<'a> {
 
 impl DefaultValues<'static> {
     pub const API_TIMEOUT_MS: u64 = 10000;
-    pub const WIFI_RECONNECTION_DELAY_MS: u64 = 4000;
+    pub const WIFI_BACKOFF_INITIAL_MS: u64 = 4000;
+    pub const WIFI_BACKOFF_MULTIPLIER: f32 = 2.0;
+    pub const WIFI_BACKOFF_MAX_MS: u64 = 120_000; // 2 min
+    pub const WIFI_BACKOFF_JITTER_PERCENT: u8 = 30;
+    pub const WIFI_BACKOFF_RESET_AFTER_MS: u64 = 60_000; // 1 min
+    pub const WIFI_ASSOCIATING_TIMEOUT_MS: u64 = 20_000; // 20 sec
+    pub const WIFI_OBTAINING_IP_TIMEOUT_MS: u64 = 20_000; // 20 sec
     pub const NET_CONNECTION_MANAGER_THREAD_DELAY_MS: u64 = 4000;
     pub const APIS_THREAD_DELAY_MS: u64 = 30_000; // 30 sec
     pub const INITIAL_APIS_THREAD_DELAY_MS: u64 = 3_000; // 30 sec
     pub const BUZZER_THREAD_DELAY_MS: u64 = 500;
+    pub const SEGMENT_DISPLAY_TICK_MS: u64 = 50;
+    pub const SEGMENT_DISPLAY_FULL_REFRESH_MS: u64 = 10_000; // 10 sec
+    pub const SEGMENT_DISPLAY_SELF_TEST_STEP_MS: u16 = 150;
+    pub const SEGMENT_DISPLAY_STATUS_MESSAGE_TTL_MS: u64 = 10_000; // 10 sec
+    pub const SEGMENT_DISPLAY_ALERT_MESSAGE_TTL_MS: u64 = 2_000; // 2 sec
+    pub const SEGMENT_DISPLAY_BARGRAPH_MS: u16 = 3_000; // 3 sec
+    pub const SECONDARY_SEGMENT_DISPLAY_TEXT_TTL_MS: u64 = 300_000; // 5 min
+    pub const AMBIENT_LIGHT_SAMPLE_MS: u64 = 500;
+    pub const PROVISIONING_BUTTON_WINDOW_MS: u64 = 3_000; // 3 sec
+    pub const PROVISIONING_BUTTON_HOLD_MS: u64 = 2_000; // 2 sec
+    pub const PROVISIONING_PORTAL_IP: [u8; 4] = [192, 168, 71, 1];
+    pub const PROVISIONING_RESTART_DELAY_MS: u64 = 2_000; // 2 sec
+    pub const PROVISIONING_AFTER_FAILED_WIFI_CYCLES: u32 = 5;
 }
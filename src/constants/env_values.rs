@@ -1,6 +1,13 @@
+use crate::common::adaptors::wifi_networks::{StaticIpv4, WifiNetwork};
+use crate::constants::segment_display_text::SegmentDisplayLocale;
+use crate::features::segment_display::auto_brightness::{DisplayBrightness, NightSchedule};
+use crate::features::segment_display::status_pages::StatusPageConfig;
+use crate::libs::segment_display::{SecondarySegmentDisplayWiring, SegmentDisplayKind};
 use serde_value::Value::U64;
 use std::marker::PhantomData;
 use std::num::ParseIntError;
+use std::str::FromStr;
+use std::str::ParseBoolError;
 
 pub struct EnvValues<'a> {
     /// https://stackoverflow.com/questions/40484154/parameter-a-is-never-used-error-when-a-is-used-in-type-parameter-bound
@@ -12,9 +19,7 @@ pub struct EnvValues<'a> {
 impl EnvValues<'static> {
     pub const APP_VERSION: &'static str = env!("CARGO_PKG_VERSION");
 
-    pub const WIFI_SSID: &'static str = dotenv!("WIFI_SSID");
-
-    pub const WIFI_PASS: &'static str = dotenv!("WIFI_PASS");
+    pub const WIFI_NETWORKS: &'static str = dotenv!("WIFI_NETWORKS");
 
     pub const API_TOKEN_KEY: &'static str = dotenv!("API_TOKEN_KEY");
 
@@ -30,10 +35,175 @@ impl EnvValues<'static> {
 
     pub const DEVICE_LOCATION: &'static str = dotenv!("DEVICE_LOCATION");
 
+    pub const WIFI_STATIC_IPV4: &'static str = dotenv!("WIFI_STATIC_IPV4");
+
     pub const FAILSAFE_TRIGGER_CONTINUOUS_PERIOD_BUZZER_BEEP_AFTER_MS: &'static str =
         dotenv!("FAILSAFE_TRIGGER_CONTINUOUS_PERIOD_BUZZER_BEEP_AFTER_MS");
 
+    pub const SEGMENT_DISPLAY_KIND: &'static str = dotenv!("SEGMENT_DISPLAY_KIND");
+
+    pub const SEGMENT_DISPLAY_SIZE: &'static str = dotenv!("SEGMENT_DISPLAY_SIZE");
+
+    pub const SEGMENT_DISPLAY_DIGIT_ORDER: &'static str = dotenv!("SEGMENT_DISPLAY_DIGIT_ORDER");
+
+    pub const SEGMENT_DISPLAY_UPSIDE_DOWN: &'static str = dotenv!("SEGMENT_DISPLAY_UPSIDE_DOWN");
+
+    pub const SEGMENT_DISPLAY_SELF_TEST_ON_BOOT: &'static str =
+        dotenv!("SEGMENT_DISPLAY_SELF_TEST_ON_BOOT");
+
+    pub const SEGMENT_DISPLAY_BIT_DELAY_US: &'static str = dotenv!("SEGMENT_DISPLAY_BIT_DELAY_US");
+
+    pub const SEGMENT_DISPLAY_MAX_BIT_DELAY_US: &'static str =
+        dotenv!("SEGMENT_DISPLAY_MAX_BIT_DELAY_US");
+
+    pub const SECONDARY_SEGMENT_DISPLAY_WIRING: &'static str =
+        dotenv!("SECONDARY_SEGMENT_DISPLAY_WIRING");
+
+    pub const SECONDARY_SEGMENT_DISPLAY_SIZE: &'static str =
+        dotenv!("SECONDARY_SEGMENT_DISPLAY_SIZE");
+
+    pub const SEGMENT_DISPLAY_LOCALE: &'static str = dotenv!("SEGMENT_DISPLAY_LOCALE");
+
+    pub const SEGMENT_DISPLAY_STATUS_PAGES: &'static str = dotenv!("SEGMENT_DISPLAY_STATUS_PAGES");
+
+    pub const WALL_CLOCK_UTC_OFFSET_MINUTES: &'static str =
+        dotenv!("WALL_CLOCK_UTC_OFFSET_MINUTES");
+
+    pub const AMBIENT_LIGHT_SENSOR_ENABLED: &'static str = dotenv!("AMBIENT_LIGHT_SENSOR_ENABLED");
+
+    pub const AMBIENT_LIGHT_DARK_MV: &'static str = dotenv!("AMBIENT_LIGHT_DARK_MV");
+
+    pub const AMBIENT_LIGHT_BRIGHT_MV: &'static str = dotenv!("AMBIENT_LIGHT_BRIGHT_MV");
+
+    pub const SEGMENT_DISPLAY_DAY_BRIGHTNESS: &'static str =
+        dotenv!("SEGMENT_DISPLAY_DAY_BRIGHTNESS");
+
+    pub const SEGMENT_DISPLAY_NIGHT_BRIGHTNESS: &'static str =
+        dotenv!("SEGMENT_DISPLAY_NIGHT_BRIGHTNESS");
+
+    pub const SEGMENT_DISPLAY_NIGHT_SCHEDULE: &'static str =
+        dotenv!("SEGMENT_DISPLAY_NIGHT_SCHEDULE");
+
+    pub const SEGMENT_DISPLAY_IDLE_TIMEOUT_MS: &'static str =
+        dotenv!("SEGMENT_DISPLAY_IDLE_TIMEOUT_MS");
+
+    pub const SEGMENT_DISPLAY_IDLE_BRIGHTNESS: &'static str =
+        dotenv!("SEGMENT_DISPLAY_IDLE_BRIGHTNESS");
+
+    /// the networks the station may join, by priority.
+    /// eg: `Home,secret,wpa2;Hotspot,secret,wpa2,aa:bb:cc:dd:ee:ff`
+    pub fn wifi_networks() -> anyhow::Result<Vec<WifiNetwork>> {
+        WifiNetwork::parse_list(Self::WIFI_NETWORKS)
+    }
+
+    /// the static ip configurations by the ssid of their network; empty if every network
+    /// uses the DHCP. eg: `Home,192.168.1.50/24,192.168.1.1,192.168.1.1`
+    pub fn wifi_static_ipv4() -> anyhow::Result<Vec<(String, StaticIpv4)>> {
+        StaticIpv4::parse_list(Self::WIFI_STATIC_IPV4)
+    }
+
     pub fn failsafe_trigger_continuous_period_buzzer_beep_after_ms() -> Result<u64, ParseIntError> {
         Self::FAILSAFE_TRIGGER_CONTINUOUS_PERIOD_BUZZER_BEEP_AFTER_MS.parse::<u64>()
     }
+
+    pub fn segment_display_kind() -> Result<SegmentDisplayKind, strum::ParseError> {
+        SegmentDisplayKind::from_str(Self::SEGMENT_DISPLAY_KIND)
+    }
+
+    pub fn segment_display_size() -> Result<u8, ParseIntError> {
+        Self::SEGMENT_DISPLAY_SIZE.parse::<u8>()
+    }
+
+    /// comma separated display registers of each digit, from left to right. eg: `2,1,0,5,4,3`
+    pub fn segment_display_digit_order() -> Result<Vec<u8>, ParseIntError> {
+        Self::SEGMENT_DISPLAY_DIGIT_ORDER
+            .split(',')
+            .map(|r| r.trim().parse::<u8>())
+            .collect()
+    }
+
+    pub fn segment_display_upside_down() -> Result<bool, ParseBoolError> {
+        Self::SEGMENT_DISPLAY_UPSIDE_DOWN.parse::<bool>()
+    }
+
+    pub fn segment_display_self_test_on_boot() -> Result<bool, ParseBoolError> {
+        Self::SEGMENT_DISPLAY_SELF_TEST_ON_BOOT.parse::<bool>()
+    }
+
+    pub fn segment_display_bit_delay_us() -> Result<u16, ParseIntError> {
+        Self::SEGMENT_DISPLAY_BIT_DELAY_US.parse::<u16>()
+    }
+
+    pub fn segment_display_max_bit_delay_us() -> Result<u16, ParseIntError> {
+        Self::SEGMENT_DISPLAY_MAX_BIT_DELAY_US.parse::<u16>()
+    }
+
+    pub fn secondary_segment_display_wiring(
+    ) -> Result<SecondarySegmentDisplayWiring, strum::ParseError> {
+        SecondarySegmentDisplayWiring::from_str(Self::SECONDARY_SEGMENT_DISPLAY_WIRING)
+    }
+
+    pub fn secondary_segment_display_size() -> Result<u8, ParseIntError> {
+        Self::SECONDARY_SEGMENT_DISPLAY_SIZE.parse::<u8>()
+    }
+
+    pub fn segment_display_locale() -> Result<SegmentDisplayLocale, strum::ParseError> {
+        SegmentDisplayLocale::from_str(Self::SEGMENT_DISPLAY_LOCALE)
+    }
+
+    /// comma separated `page:dwell_ms` of the status pages, in the order they are shown.
+    /// eg: `uptime:10000,clock:5000,ip:4000`
+    pub fn segment_display_status_pages() -> anyhow::Result<Vec<StatusPageConfig>> {
+        Self::SEGMENT_DISPLAY_STATUS_PAGES
+            .split(',')
+            .filter(|p| !p.trim().is_empty())
+            .map(StatusPageConfig::from_str)
+            .collect()
+    }
+
+    pub fn wall_clock_utc_offset_minutes() -> Result<i16, ParseIntError> {
+        Self::WALL_CLOCK_UTC_OFFSET_MINUTES.parse::<i16>()
+    }
+
+    pub fn ambient_light_sensor_enabled() -> Result<bool, ParseBoolError> {
+        Self::AMBIENT_LIGHT_SENSOR_ENABLED.parse::<bool>()
+    }
+
+    pub fn ambient_light_dark_mv() -> Result<u16, ParseIntError> {
+        Self::AMBIENT_LIGHT_DARK_MV.parse::<u16>()
+    }
+
+    pub fn ambient_light_bright_mv() -> Result<u16, ParseIntError> {
+        Self::AMBIENT_LIGHT_BRIGHT_MV.parse::<u16>()
+    }
+
+    /// `off` or a brightness level from `0` to `7`
+    pub fn segment_display_day_brightness() -> anyhow::Result<DisplayBrightness> {
+        DisplayBrightness::from_str(Self::SEGMENT_DISPLAY_DAY_BRIGHTNESS)
+    }
+
+    /// `off` or a brightness level from `0` to `7`
+    pub fn segment_display_night_brightness() -> anyhow::Result<DisplayBrightness> {
+        DisplayBrightness::from_str(Self::SEGMENT_DISPLAY_NIGHT_BRIGHTNESS)
+    }
+
+    /// `HH:MM-HH:MM` in the local time, or `none`
+    pub fn segment_display_night_schedule() -> anyhow::Result<Option<NightSchedule>> {
+        match Self::SEGMENT_DISPLAY_NIGHT_SCHEDULE.trim() {
+            "none" => Ok(None),
+            s => NightSchedule::from_str(s).map(Some),
+        }
+    }
+
+    /// [None] if it is `0`
+    pub fn segment_display_idle_timeout_ms() -> Result<Option<u64>, ParseIntError> {
+        Self::SEGMENT_DISPLAY_IDLE_TIMEOUT_MS
+            .parse::<u64>()
+            .map(|ms| (ms > 0).then(|| ms))
+    }
+
+    /// `off` or a brightness level from `0` to `7`
+    pub fn segment_display_idle_brightness() -> anyhow::Result<DisplayBrightness> {
+        DisplayBrightness::from_str(Self::SEGMENT_DISPLAY_IDLE_BRIGHTNESS)
+    }
 }
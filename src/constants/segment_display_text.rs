@@ -1,4 +1,40 @@
 use std::marker::PhantomData;
+use strum_macros::EnumString;
+
+/// The texts shown on the segment display; the server refers to them by these names.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, EnumString)]
+pub enum SegmentDisplayTextKey {
+    #[strum(serialize = "err_404")]
+    Err404,
+
+    #[strum(serialize = "err_400")]
+    Err400,
+
+    #[strum(serialize = "err_503")]
+    Err503,
+
+    #[strum(serialize = "err_api")]
+    ErrApi,
+
+    #[strum(serialize = "err_json")]
+    ErrJson,
+
+    #[strum(serialize = "err_no_wifi")]
+    ErrNoWifi,
+
+    #[strum(serialize = "switch_off")]
+    SwitchOff,
+}
+
+/// The languages of the compiled-in message catalogs.
+#[derive(Debug, Clone, Copy, PartialEq, EnumString)]
+pub enum SegmentDisplayLocale {
+    #[strum(serialize = "en")]
+    En,
+
+    #[strum(serialize = "de")]
+    De,
+}
 
 #[derive(Debug)]
 pub struct SegmentDisplayText<'a> {
@@ -14,6 +50,39 @@ impl SegmentDisplayText<'static> {
     pub const ERR_503: &'static str = "Err 503";
     pub const ERR_API: &'static str = "Err API";
     pub const ERR_JSON: &'static str = "Err JSON";
-    pub const ERR_NO_WIFI: &'static str = "Err no yify";
-    pub const SWITCH_OFF: &'static str = "Suuitch Off";
+    pub const ERR_NO_WIFI: &'static str = "Err no WiFi";
+    pub const SWITCH_OFF: &'static str = "Switch Off";
+
+    /// The English text of the [key]; every other catalog falls back to it.
+    pub const fn fallback(key: SegmentDisplayTextKey) -> &'static str {
+        match key {
+            SegmentDisplayTextKey::Err404 => Self::ERR_404,
+            SegmentDisplayTextKey::Err400 => Self::ERR_400,
+            SegmentDisplayTextKey::Err503 => Self::ERR_503,
+            SegmentDisplayTextKey::ErrApi => Self::ERR_API,
+            SegmentDisplayTextKey::ErrJson => Self::ERR_JSON,
+            SegmentDisplayTextKey::ErrNoWifi => Self::ERR_NO_WIFI,
+            SegmentDisplayTextKey::SwitchOff => Self::SWITCH_OFF,
+        }
+    }
+
+    /// The compiled-in text of the [key] in the [locale]; [None] if the catalog of the
+    /// [locale] doesn't have it.
+    pub const fn compiled(
+        locale: SegmentDisplayLocale,
+        key: SegmentDisplayTextKey,
+    ) -> Option<&'static str> {
+        match locale {
+            SegmentDisplayLocale::En => Some(Self::fallback(key)),
+            SegmentDisplayLocale::De => match key {
+                SegmentDisplayTextKey::Err404 => Some("Fehler 404"),
+                SegmentDisplayTextKey::Err400 => Some("Fehler 400"),
+                SegmentDisplayTextKey::Err503 => Some("Fehler 503"),
+                SegmentDisplayTextKey::ErrApi => Some("Fehler API"),
+                SegmentDisplayTextKey::ErrJson => Some("Fehler JSON"),
+                SegmentDisplayTextKey::ErrNoWifi => Some("Kein WLAN"),
+                SegmentDisplayTextKey::SwitchOff => Some("Ausschalten"),
+            },
+        }
+    }
 }
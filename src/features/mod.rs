@@ -1,2 +1,4 @@
 pub mod network;
 pub mod peripheral;
+pub mod provisioning;
+pub mod segment_display;
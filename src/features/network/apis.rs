@@ -1,6 +1,8 @@
+use crate::common::adaptors::wifi_networks::WifiIpv4Mode;
 use crate::common::api_client::sirius_proxima::{ApiResponse, PingResponse, SIRIUS_PROXIMA_CLIENT};
 use crate::common::models::sirius_proxima_api::SiriusProximaPing;
 use crate::constants::headers::{HeaderKeys, HeaderValues};
+use crate::libs::segment_display::SegmentDisplaySelfTestResult;
 use crate::EnvValues;
 use lazy_static::lazy_static;
 use std::collections::HashMap;
@@ -13,8 +15,19 @@ lazy_static! {
 pub struct Apis;
 
 impl Apis {
-    pub fn ping(self, is_first_ping_after_device_turned_on: bool) -> ApiResponse<PingResponse> {
-        let json_data = SiriusProximaPing::new(is_first_ping_after_device_turned_on)?;
+    pub fn ping(
+        self,
+        is_first_ping_after_device_turned_on: bool,
+        is_segment_display_missing: bool,
+        segment_display_self_test_result: SegmentDisplaySelfTestResult,
+        wifi_ipv4_mode: WifiIpv4Mode,
+    ) -> ApiResponse<PingResponse> {
+        let json_data = SiriusProximaPing::new(
+            is_first_ping_after_device_turned_on,
+            is_segment_display_missing,
+            segment_display_self_test_result,
+            wifi_ipv4_mode,
+        )?;
         let mut headers = HashMap::new();
         headers.insert(
             HeaderKeys::DEVICE_ID,
@@ -0,0 +1,487 @@
+use std::time::{Duration, Instant};
+
+/// The states of the Wi-Fi connection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum WifiConnectionState {
+    /// The connection hasn't been started, or has been stopped.
+    Idle = 0,
+
+    /// Looking for the configured access point.
+    Scanning = 1,
+
+    /// The station has been configured and is joining the access point.
+    Associating = 2,
+
+    /// Joined the access point, waiting for an ip address.
+    ObtainingIp = 3,
+
+    Online = 4,
+
+    /// Waiting before the next scan, after a failure.
+    Backoff = 5,
+}
+
+impl WifiConnectionState {
+    pub const fn from_u8(state: u8) -> Self {
+        match state {
+            1 => WifiConnectionState::Scanning,
+            2 => WifiConnectionState::Associating,
+            3 => WifiConnectionState::ObtainingIp,
+            4 => WifiConnectionState::Online,
+            5 => WifiConnectionState::Backoff,
+            _ => WifiConnectionState::Idle,
+        }
+    }
+}
+
+/// The state of the link as reported by the Wi-Fi driver.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WifiLink {
+    /// Not associated with an access point.
+    Down,
+
+    /// Associated with the access point, without an ip address.
+    Associated,
+
+    /// Associated with the access point, with an ip address.
+    Up,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WifiConnectionEvent {
+    Start,
+    Stop,
+    ScanSucceeded,
+    ScanFailed,
+
+    /// The station couldn't be configured to join the access point.
+    AssociationFailed,
+
+    /// The link as it was polled from the Wi-Fi driver, or as the driver has posted it on the
+    /// system event loop.
+    Link(WifiLink),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WifiConnectionTransition {
+    pub from: WifiConnectionState,
+    pub to: WifiConnectionState,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WifiConnectionTimeouts {
+    /// the longest wait for the association before backing off
+    pub associating: Duration,
+
+    /// the longest wait for an ip address before backing off
+    pub obtaining_ip: Duration,
+
+    /// the wait before scanning again after a failure
+    pub backoff: Duration,
+}
+
+/// The Wi-Fi connection cycle: Idle → Scanning → Associating → ObtainingIp → Online.
+/// A failure or a timeout on the way goes to Backoff, which goes back to Scanning once the
+/// backoff time has passed. A dropped link while Online goes back to Scanning, and a lost ip
+/// address goes back to ObtainingIp.
+///
+/// The machine only keeps the state; the caller does the work of each state (scanning,
+/// configuring the station) and reports the outcome as a [WifiConnectionEvent].
+/// The time is passed in by the caller, so that it doesn't depend on the clock.
+#[derive(Debug, Clone, Copy)]
+pub struct WifiConnectionStateMachine {
+    state: WifiConnectionState,
+    entered_at: Instant,
+    timeouts: WifiConnectionTimeouts,
+}
+
+impl WifiConnectionStateMachine {
+    pub fn new(timeouts: WifiConnectionTimeouts, now: Instant) -> Self {
+        Self {
+            state: WifiConnectionState::Idle,
+            entered_at: now,
+            timeouts,
+        }
+    }
+
+    pub const fn state(&self) -> WifiConnectionState {
+        self.state
+    }
+
+    /// Changes the backoff time, eg: from a backoff policy which grows it after each failure.
+    pub fn set_backoff(&mut self, backoff: Duration) {
+        self.timeouts.backoff = backoff;
+    }
+
+    /// Applies the [event]; returns the transition if the state has changed.
+    pub fn handle(
+        &mut self,
+        event: WifiConnectionEvent,
+        now: Instant,
+    ) -> Option<WifiConnectionTransition> {
+        let next = match (self.state, event) {
+            (_, WifiConnectionEvent::Stop) => WifiConnectionState::Idle,
+            (WifiConnectionState::Idle, WifiConnectionEvent::Start) => {
+                WifiConnectionState::Scanning
+            }
+
+            (WifiConnectionState::Scanning, WifiConnectionEvent::ScanSucceeded) => {
+                WifiConnectionState::Associating
+            }
+            (WifiConnectionState::Scanning, WifiConnectionEvent::ScanFailed) => {
+                WifiConnectionState::Backoff
+            }
+
+            (WifiConnectionState::Associating, WifiConnectionEvent::AssociationFailed) => {
+                WifiConnectionState::Backoff
+            }
+            (WifiConnectionState::Associating, WifiConnectionEvent::Link(WifiLink::Associated)) => {
+                WifiConnectionState::ObtainingIp
+            }
+            (
+                WifiConnectionState::Associating | WifiConnectionState::ObtainingIp,
+                WifiConnectionEvent::Link(WifiLink::Up),
+            ) => WifiConnectionState::Online,
+
+            // dropped while it was joining; try again after the backoff
+            (WifiConnectionState::ObtainingIp, WifiConnectionEvent::Link(WifiLink::Down)) => {
+                WifiConnectionState::Backoff
+            }
+
+            (WifiConnectionState::Online, WifiConnectionEvent::Link(WifiLink::Down)) => {
+                WifiConnectionState::Scanning
+            }
+            (WifiConnectionState::Online, WifiConnectionEvent::Link(WifiLink::Associated)) => {
+                WifiConnectionState::ObtainingIp
+            }
+
+            (state, _) => state,
+        };
+
+        self.transition_to(next, now)
+    }
+
+    /// Moves on once the time of the current state has run out: Associating and ObtainingIp
+    /// back off, and Backoff scans again.
+    pub fn poll(&mut self, now: Instant) -> Option<WifiConnectionTransition> {
+        let elapsed = now.saturating_duration_since(self.entered_at);
+
+        let next = match self.state {
+            WifiConnectionState::Associating if elapsed >= self.timeouts.associating => {
+                WifiConnectionState::Backoff
+            }
+            WifiConnectionState::ObtainingIp if elapsed >= self.timeouts.obtaining_ip => {
+                WifiConnectionState::Backoff
+            }
+            WifiConnectionState::Backoff if elapsed >= self.timeouts.backoff => {
+                WifiConnectionState::Scanning
+            }
+            state => state,
+        };
+
+        self.transition_to(next, now)
+    }
+
+    fn transition_to(
+        &mut self,
+        next: WifiConnectionState,
+        now: Instant,
+    ) -> Option<WifiConnectionTransition> {
+        if next == self.state {
+            return None;
+        }
+
+        let transition = WifiConnectionTransition {
+            from: self.state,
+            to: next,
+        };
+
+        self.state = next;
+        self.entered_at = now;
+
+        Some(transition)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TIMEOUTS: WifiConnectionTimeouts = WifiConnectionTimeouts {
+        associating: Duration::from_secs(10),
+        obtaining_ip: Duration::from_secs(20),
+        backoff: Duration::from_secs(5),
+    };
+
+    fn transition(from: WifiConnectionState, to: WifiConnectionState) -> WifiConnectionTransition {
+        WifiConnectionTransition { from, to }
+    }
+
+    /// A machine which has been driven to the [state] at [now].
+    fn machine_in(state: WifiConnectionState, now: Instant) -> WifiConnectionStateMachine {
+        let mut machine = WifiConnectionStateMachine::new(TIMEOUTS, now);
+        let events: &[WifiConnectionEvent] = match state {
+            WifiConnectionState::Idle => &[],
+            WifiConnectionState::Scanning => &[WifiConnectionEvent::Start],
+            WifiConnectionState::Associating => &[
+                WifiConnectionEvent::Start,
+                WifiConnectionEvent::ScanSucceeded,
+            ],
+            WifiConnectionState::ObtainingIp => &[
+                WifiConnectionEvent::Start,
+                WifiConnectionEvent::ScanSucceeded,
+                WifiConnectionEvent::Link(WifiLink::Associated),
+            ],
+            WifiConnectionState::Online => &[
+                WifiConnectionEvent::Start,
+                WifiConnectionEvent::ScanSucceeded,
+                WifiConnectionEvent::Link(WifiLink::Up),
+            ],
+            WifiConnectionState::Backoff => {
+                &[WifiConnectionEvent::Start, WifiConnectionEvent::ScanFailed]
+            }
+        };
+        for event in events {
+            machine.handle(*event, now);
+        }
+        assert_eq!(machine.state(), state);
+
+        machine
+    }
+
+    #[test]
+    fn a_connection_goes_from_idle_to_online() {
+        let now = Instant::now();
+        let mut machine = WifiConnectionStateMachine::new(TIMEOUTS, now);
+
+        assert_eq!(
+            machine.handle(WifiConnectionEvent::Start, now),
+            Some(transition(
+                WifiConnectionState::Idle,
+                WifiConnectionState::Scanning
+            ))
+        );
+        assert_eq!(
+            machine.handle(WifiConnectionEvent::ScanSucceeded, now),
+            Some(transition(
+                WifiConnectionState::Scanning,
+                WifiConnectionState::Associating
+            ))
+        );
+        assert_eq!(
+            machine.handle(WifiConnectionEvent::Link(WifiLink::Associated), now),
+            Some(transition(
+                WifiConnectionState::Associating,
+                WifiConnectionState::ObtainingIp
+            ))
+        );
+        assert_eq!(
+            machine.handle(WifiConnectionEvent::Link(WifiLink::Up), now),
+            Some(transition(
+                WifiConnectionState::ObtainingIp,
+                WifiConnectionState::Online
+            ))
+        );
+    }
+
+    #[test]
+    fn an_ip_address_while_associating_goes_online() {
+        let now = Instant::now();
+        let mut machine = machine_in(WifiConnectionState::Associating, now);
+
+        assert_eq!(
+            machine.handle(WifiConnectionEvent::Link(WifiLink::Up), now),
+            Some(transition(
+                WifiConnectionState::Associating,
+                WifiConnectionState::Online
+            ))
+        );
+    }
+
+    #[test]
+    fn the_failures_back_off() {
+        let now = Instant::now();
+
+        let mut machine = machine_in(WifiConnectionState::Scanning, now);
+        assert_eq!(
+            machine.handle(WifiConnectionEvent::ScanFailed, now),
+            Some(transition(
+                WifiConnectionState::Scanning,
+                WifiConnectionState::Backoff
+            ))
+        );
+
+        let mut machine = machine_in(WifiConnectionState::Associating, now);
+        assert_eq!(
+            machine.handle(WifiConnectionEvent::AssociationFailed, now),
+            Some(transition(
+                WifiConnectionState::Associating,
+                WifiConnectionState::Backoff
+            ))
+        );
+
+        let mut machine = machine_in(WifiConnectionState::ObtainingIp, now);
+        assert_eq!(
+            machine.handle(WifiConnectionEvent::Link(WifiLink::Down), now),
+            Some(transition(
+                WifiConnectionState::ObtainingIp,
+                WifiConnectionState::Backoff
+            ))
+        );
+    }
+
+    #[test]
+    fn a_dropped_link_while_online_scans_again() {
+        let now = Instant::now();
+        let mut machine = machine_in(WifiConnectionState::Online, now);
+
+        assert_eq!(
+            machine.handle(WifiConnectionEvent::Link(WifiLink::Down), now),
+            Some(transition(
+                WifiConnectionState::Online,
+                WifiConnectionState::Scanning
+            ))
+        );
+    }
+
+    #[test]
+    fn a_lost_ip_address_while_online_obtains_it_again() {
+        let now = Instant::now();
+        let mut machine = machine_in(WifiConnectionState::Online, now);
+
+        assert_eq!(
+            machine.handle(WifiConnectionEvent::Link(WifiLink::Associated), now),
+            Some(transition(
+                WifiConnectionState::Online,
+                WifiConnectionState::ObtainingIp
+            ))
+        );
+    }
+
+    #[test]
+    fn the_association_times_out() {
+        let now = Instant::now();
+        let mut machine = machine_in(WifiConnectionState::Associating, now);
+
+        assert_eq!(machine.poll(now + TIMEOUTS.associating / 2), None);
+        assert_eq!(
+            machine.poll(now + TIMEOUTS.associating),
+            Some(transition(
+                WifiConnectionState::Associating,
+                WifiConnectionState::Backoff
+            ))
+        );
+    }
+
+    #[test]
+    fn obtaining_an_ip_address_times_out() {
+        let now = Instant::now();
+        let mut machine = machine_in(WifiConnectionState::ObtainingIp, now);
+
+        // the timeout of the association doesn't apply
+        assert_eq!(machine.poll(now + TIMEOUTS.associating), None);
+        assert_eq!(
+            machine.poll(now + TIMEOUTS.obtaining_ip),
+            Some(transition(
+                WifiConnectionState::ObtainingIp,
+                WifiConnectionState::Backoff
+            ))
+        );
+    }
+
+    #[test]
+    fn the_backoff_scans_again_once_its_time_has_passed() {
+        let now = Instant::now();
+        let mut machine = machine_in(WifiConnectionState::Backoff, now);
+        machine.set_backoff(Duration::from_secs(30));
+
+        assert_eq!(machine.poll(now + TIMEOUTS.backoff), None);
+        assert_eq!(
+            machine.poll(now + Duration::from_secs(30)),
+            Some(transition(
+                WifiConnectionState::Backoff,
+                WifiConnectionState::Scanning
+            ))
+        );
+    }
+
+    #[test]
+    fn the_timeouts_are_counted_from_the_last_transition() {
+        let now = Instant::now();
+        let mut machine = machine_in(WifiConnectionState::Scanning, now);
+
+        let associating_at = now + Duration::from_secs(60);
+        machine.handle(WifiConnectionEvent::ScanSucceeded, associating_at);
+
+        assert_eq!(
+            machine.poll(associating_at + TIMEOUTS.associating / 2),
+            None
+        );
+        assert_eq!(machine.state(), WifiConnectionState::Associating);
+    }
+
+    #[test]
+    fn the_online_and_idle_states_dont_time_out() {
+        let now = Instant::now();
+        let later = now + Duration::from_secs(3600);
+
+        assert_eq!(
+            machine_in(WifiConnectionState::Online, now).poll(later),
+            None
+        );
+        assert_eq!(machine_in(WifiConnectionState::Idle, now).poll(later), None);
+    }
+
+    #[test]
+    fn the_unexpected_events_are_ignored() {
+        let now = Instant::now();
+
+        let mut machine = machine_in(WifiConnectionState::Idle, now);
+        assert_eq!(
+            machine.handle(WifiConnectionEvent::Link(WifiLink::Up), now),
+            None
+        );
+
+        let mut machine = machine_in(WifiConnectionState::Backoff, now);
+        assert_eq!(
+            machine.handle(WifiConnectionEvent::Link(WifiLink::Down), now),
+            None
+        );
+        assert_eq!(machine.handle(WifiConnectionEvent::Start, now), None);
+    }
+
+    #[test]
+    fn stop_goes_back_to_idle_from_any_state() {
+        let now = Instant::now();
+
+        for state in [
+            WifiConnectionState::Scanning,
+            WifiConnectionState::Associating,
+            WifiConnectionState::ObtainingIp,
+            WifiConnectionState::Online,
+            WifiConnectionState::Backoff,
+        ] {
+            let mut machine = machine_in(state, now);
+
+            assert_eq!(
+                machine.handle(WifiConnectionEvent::Stop, now),
+                Some(transition(state, WifiConnectionState::Idle))
+            );
+        }
+    }
+
+    #[test]
+    fn the_state_survives_the_round_trip_through_u8() {
+        for state in [
+            WifiConnectionState::Idle,
+            WifiConnectionState::Scanning,
+            WifiConnectionState::Associating,
+            WifiConnectionState::ObtainingIp,
+            WifiConnectionState::Online,
+            WifiConnectionState::Backoff,
+        ] {
+            assert_eq!(WifiConnectionState::from_u8(state as u8), state);
+        }
+    }
+}
@@ -1,11 +1,27 @@
+use crate::common::adaptors::network::WifiDriverEvent;
+use crate::common::adaptors::wifi_networks::WifiCandidate;
 use crate::common::api_client::sirius_proxima::{ApiResponse, PingResponse};
 use crate::common::errors::api_errors::{ApiClientError, ApiResponseError};
+use crate::common::errors::error_codes::{ErrorCode, ErrorCodeGroup, ERROR_CODES};
 use crate::common::errors::wifi_errors::WifiError;
 use crate::constants::default_values::DefaultValues;
-use crate::constants::segment_display_text::SegmentDisplayText;
+use crate::constants::segment_display_text::SegmentDisplayTextKey;
 use crate::features::network::apis::NETWORK_APIS;
+use crate::features::network::connection_state::{
+    WifiConnectionEvent, WifiConnectionState, WifiConnectionStateMachine, WifiConnectionTimeouts,
+    WifiConnectionTransition, WifiLink,
+};
+use crate::features::network::status::NetworkStatus;
 use crate::features::peripheral::{Peripheral, PeripheralKind, PeripheralTx};
+use crate::features::segment_display::message_queue::{
+    SegmentDisplayMessage, SegmentDisplayMessagePriority, SegmentDisplayMessageRepeat,
+    SegmentDisplayMessageSource,
+};
+use crate::features::segment_display::text_catalog::SEGMENT_DISPLAY_TEXTS;
+use crate::features::segment_display::SegmentDisplayTx;
 use crate::helpers::atomic_esp_system_time::{AtomicSystemTime, Diff};
+use crate::helpers::backoff::{Backoff, BackoffConfig};
+use crate::libs::segment_display::SegmentDisplaySelfTestResult;
 use crate::GpioPinValue::{High, Low};
 use crate::{CommonError, EnvValues, WifiAdaptor};
 use either::Either;
@@ -13,80 +29,141 @@ use embedded_svc::wifi::{ClientConnectionStatus, ClientIpStatus, ClientStatus, S
 use esp_idf_sys::c_types::c_uint;
 use log::error;
 use serde::de::DeserializeOwned;
+use std::net::Ipv4Addr;
 use std::ptr::null_mut;
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::mpsc::Sender;
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+use std::sync::mpsc::{channel, Receiver};
 use std::sync::{Arc, Condvar, Mutex};
 use std::thread;
 use std::thread::JoinHandle;
 use std::time::{Duration, Instant};
 
 pub mod apis;
+pub mod connection_state;
+pub mod status;
 
 #[derive(Clone, Copy)]
 pub struct Network {
-    is_network_connected: bool,
-    is_wifi_ip_resolved: bool,
+    connection: WifiConnectionStateMachine,
+
+    /// the access point picked by the last scan
+    wifi_candidate: Option<WifiCandidate>,
+
+    /// the wait before the next try, after the wifi connection has failed
+    wifi_backoff: Backoff,
+
+    /// the provisioning portal is requested once the wifi connection has failed this many
+    /// times in a row since the boot; [None] when networks have been saved in the portal
+    provisioning_after_failed_wifi_cycles: Option<u32>,
+
+    /// false until the wifi connection has been online since the boot
+    has_wifi_been_online: bool,
+
     is_first_ping_after_device_turned_on: bool,
 }
 
 pub const STACK_SIZE: usize = 32768_u32 as usize;
 
 impl Network {
-    fn process_network_response<T>(self, response: &ApiResponse<T>) -> Either<&T, Option<&str>>
+    /// The response data, or the text to show on the segment display for the error along with
+    /// its code, eg: `Err 404 E304`.
+    fn process_network_response<T>(self, response: &ApiResponse<T>) -> Either<&T, Option<String>>
     where
         T: DeserializeOwned,
     {
         return match &response {
             Ok(d) => Either::Left(d),
             Err(e) => {
-                let matched_api_res_err: Option<&str> = match e.downcast_ref::<ApiResponseError>() {
-                    None => None,
-                    Some(ApiResponseError::InternalServerError(_, _, _)) => {
-                        Some(SegmentDisplayText::ERR_503)
-                    }
-                    Some(
-                        ApiResponseError::SiteNotFound(_, _) | ApiResponseError::NotFound(_, _, _),
-                    ) => Some(SegmentDisplayText::ERR_404),
-                    Some(ApiResponseError::BadRequest(_, _, _)) => {
-                        Some(SegmentDisplayText::ERR_400)
-                    }
-                };
-
-                if matched_api_res_err.is_some() {
-                    return Either::Right(matched_api_res_err);
+                let error_code = ErrorCode::of(e);
+                if let Some(code) = error_code {
+                    ERROR_CODES.record(code);
                 }
 
-                let matched_api_client_err: Option<&str> = match e.downcast_ref::<ApiClientError>()
-                {
-                    None => None,
-                    Some(ApiClientError::Response(_, _)) => Some(SegmentDisplayText::ERR_API),
-                    Some(ApiClientError::JsonParsing(_, _)) => Some(SegmentDisplayText::ERR_JSON),
-                };
+                let text = self.error_segment_display_text_key(e).map(|key| {
+                    let text = SEGMENT_DISPLAY_TEXTS.text(key);
 
-                if matched_api_client_err.is_some() {
-                    return Either::Right(matched_api_client_err);
-                }
+                    match error_code {
+                        Some(code) => format!("{} {}", text, code.short()),
+                        None => text,
+                    }
+                });
 
-                Either::Right(None)
+                Either::Right(text)
             }
         };
     }
 
-    fn connect(self, wifi_adaptor: &mut WifiAdaptor) -> anyhow::Result<()> {
-        wifi_adaptor.connect()
+    fn error_segment_display_text_key(self, e: &anyhow::Error) -> Option<SegmentDisplayTextKey> {
+        let matched_api_res_err: Option<SegmentDisplayTextKey> =
+            match e.downcast_ref::<ApiResponseError>() {
+                None => None,
+                Some(ApiResponseError::InternalServerError(_, _, _)) => {
+                    Some(SegmentDisplayTextKey::Err503)
+                }
+                Some(
+                    ApiResponseError::SiteNotFound(_, _) | ApiResponseError::NotFound(_, _, _),
+                ) => Some(SegmentDisplayTextKey::Err404),
+                Some(ApiResponseError::BadRequest(_, _, _)) => Some(SegmentDisplayTextKey::Err400),
+            };
+
+        if matched_api_res_err.is_some() {
+            return matched_api_res_err;
+        }
+
+        let matched_api_client_err: Option<SegmentDisplayTextKey> =
+            match e.downcast_ref::<ApiClientError>() {
+                None => None,
+                Some(ApiClientError::Response(_, _)) => Some(SegmentDisplayTextKey::ErrApi),
+                Some(ApiClientError::JsonParsing(_, _)) => Some(SegmentDisplayTextKey::ErrJson),
+            };
+
+        if matched_api_client_err.is_some() {
+            return matched_api_client_err;
+        }
+
+        None
     }
 
-    const fn check_wifi_ip_resolved(self, status: &Status) -> bool {
+    /// The state of the link, as the Wi-Fi driver reports it.
+    const fn wifi_link(self, status: &Status) -> WifiLink {
+        match status {
+            Status(
+                ClientStatus::Started(ClientConnectionStatus::Connected(ClientIpStatus::Done(_))),
+                _,
+            ) => WifiLink::Up,
+            Status(ClientStatus::Started(ClientConnectionStatus::Connected(_)), _) => {
+                WifiLink::Associated
+            }
+            _ => WifiLink::Down,
+        }
+    }
+
+    /// The ip address of the station, if it has been resolved.
+    fn wifi_ip(self, status: &Status) -> Option<Ipv4Addr> {
         if let Status(
-            ClientStatus::Started(ClientConnectionStatus::Connected(ClientIpStatus::Done(_))),
+            ClientStatus::Started(ClientConnectionStatus::Connected(ClientIpStatus::Done(
+                settings,
+            ))),
             _,
-        ) = *status
+        ) = status
         {
-            return true;
+            return Some(settings.ip);
         }
 
-        false
+        None
+    }
+
+    /// Signal strength of the access point the station is connected to.
+    fn wifi_rssi(self) -> Option<i8> {
+        let mut ap_info = esp_idf_sys::wifi_ap_record_t::default();
+
+        // SAFETY: ESP IDF related sys call
+        let res = unsafe { esp_idf_sys::esp_wifi_sta_get_ap_info(&mut ap_info) };
+        if res != esp_idf_sys::ESP_OK as esp_idf_sys::esp_err_t {
+            return None;
+        }
+
+        Some(ap_info.rssi)
     }
 
     pub fn set_buzzer(
@@ -110,70 +187,193 @@ impl Network {
         }
     }
 
-    fn run_net_connection_worker(&mut self, wifi_adaptor: &mut WifiAdaptor) {
-        ////////////////////////////////////////////////////////////////////////////////////////////////
-        ////////
-        // <---if the network connectivity hasnt been established yet then connect to the network--->
-        if !self.is_network_connected {
-            let c = self.connect(wifi_adaptor);
-
-            if let Err(e) = c {
-                // connectivity issue, try again
-                log::warn!(
-                    "[E0008] [network feature] an error occured: '{}'.\n\
+    /// Does the work of the current state of the Wi-Fi connection and feeds the outcome and
+    /// the link status into the state machine. The state is published via [network_status].
+    fn run_net_connection_worker(
+        &mut self,
+        wifi_adaptor: &mut WifiAdaptor,
+        network_status: &NetworkStatus,
+    ) {
+        let now = Instant::now();
+        let status = wifi_adaptor.esp_wifi.get_status();
+        let link = self.wifi_link(&status);
+
+        let transition = match self.connection.state() {
+            WifiConnectionState::Idle => self.connection.handle(WifiConnectionEvent::Start, now),
+            WifiConnectionState::Scanning => match wifi_adaptor.scan() {
+                Ok(candidate) => {
+                    self.wifi_candidate = Some(candidate);
+
+                    self.connection
+                        .handle(WifiConnectionEvent::ScanSucceeded, now)
+                }
+                Err(e) => {
+                    ERROR_CODES.record_anyhow(&e);
+
+                    log::warn!(
+                        "[E0008] [network feature] an error occured: '{}'.\n\
                                     Will try to connect again...",
-                    e
-                );
+                        e
+                    );
 
-                thread::sleep(Duration::from_millis(
-                    DefaultValues::WIFI_RECONNECTION_DELAY_MS,
-                ));
+                    self.connection.handle(WifiConnectionEvent::ScanFailed, now)
+                }
+            },
+            WifiConnectionState::Associating
+            | WifiConnectionState::ObtainingIp
+            | WifiConnectionState::Online => self
+                .connection
+                .handle(WifiConnectionEvent::Link(link), now)
+                .or_else(|| self.connection.poll(now)),
+            WifiConnectionState::Backoff => self.connection.poll(now),
+        };
 
-                return;
-            }
+        self.apply_wifi_transitions(transition, wifi_adaptor, network_status, now);
+        self.publish_wifi_status(&status, network_status);
+    }
 
-            // marking [is_network_connected] as true since a wifi connection has been established
-            self.is_network_connected = true;
-        }
+    /// Feeds a link change which the Wi-Fi driver has posted on the system event loop into the
+    /// state machine, without waiting for the next poll of the driver status.
+    fn handle_wifi_link_event(
+        &mut self,
+        link: WifiLink,
+        wifi_adaptor: &mut WifiAdaptor,
+        network_status: &NetworkStatus,
+    ) {
+        let now = Instant::now();
+        let transition = self.connection.handle(WifiConnectionEvent::Link(link), now);
 
-        // </---if the network connectivity hasnt been established yet then connect to the network--->
-        ////////
+        self.apply_wifi_transitions(transition, wifi_adaptor, network_status, now);
+        self.publish_wifi_status(&wifi_adaptor.esp_wifi.get_status(), network_status);
+    }
 
-        ////////////////////////////////////////////////////////////////////////////////////////////////
-        ////////
-        // <--- check if the wifi status has turned into the connected state and the ip address has been resolved. --->
+    /// Does the work of each state the connection goes through, until it settles.
+    fn apply_wifi_transitions(
+        &mut self,
+        mut transition: Option<WifiConnectionTransition>,
+        wifi_adaptor: &mut WifiAdaptor,
+        network_status: &NetworkStatus,
+        now: Instant,
+    ) {
+        while let Some(t) = transition {
+            log::info!(
+                "[network feature] the wifi connection went from {:?} to {:?}",
+                t.from,
+                t.to
+            );
+            network_status.set_connection_state(t.to);
 
-        let esp_wifi = &wifi_adaptor.esp_wifi;
-        let status = esp_wifi.get_status();
-        if !self.check_wifi_ip_resolved(&status) {
-            // mark [is_wifi_ip_resolved] as false if the ip hasnt been resolved yet
-            self.is_wifi_ip_resolved = false;
+            if t.from == WifiConnectionState::Online {
+                self.wifi_backoff.mark_down(now);
+            }
+            if t.to == WifiConnectionState::Backoff {
+                let delay = self.wifi_backoff.next_delay();
+                self.connection.set_backoff(delay);
+
+                log::info!(
+                    "[network feature] retrying the wifi connection in {} ms (attempt {})",
+                    delay.as_millis(),
+                    self.wifi_backoff.attempt()
+                );
 
-            thread::sleep(Duration::from_millis(500));
+                if let Some(max_cycles) = self.provisioning_after_failed_wifi_cycles {
+                    if !self.has_wifi_been_online
+                        && self.wifi_backoff.attempt() >= max_cycles
+                        && !network_status.is_provisioning_requested()
+                    {
+                        log::warn!(
+                            "[network feature] none of the wifi networks could be joined after \
+                            {} tries, requesting the provisioning portal",
+                            max_cycles
+                        );
+                        network_status.request_provisioning();
+                    }
+                }
+            }
 
-            return;
+            // the network couldn't be joined or went away; fail over to the next one
+            if let (
+                WifiConnectionState::Associating
+                | WifiConnectionState::ObtainingIp
+                | WifiConnectionState::Online,
+                WifiConnectionState::Backoff | WifiConnectionState::Scanning,
+                Some(candidate),
+            ) = (t.from, t.to, &self.wifi_candidate)
+            {
+                wifi_adaptor.mark_failed(candidate);
+            }
+
+            transition = match (t.to, self.wifi_candidate) {
+                (WifiConnectionState::Associating, Some(candidate)) => {
+                    match wifi_adaptor.associate(&candidate) {
+                        Ok(_) => None,
+                        Err(e) => {
+                            ERROR_CODES.record_anyhow(&e);
+                            error!("[E0008b][network feature] {}", e.to_string());
+
+                            self.connection
+                                .handle(WifiConnectionEvent::AssociationFailed, now)
+                        }
+                    }
+                }
+                (WifiConnectionState::Associating, None) => self
+                    .connection
+                    .handle(WifiConnectionEvent::AssociationFailed, now),
+                (WifiConnectionState::Online, candidate) => {
+                    wifi_adaptor.mark_connected();
+                    self.wifi_backoff.mark_up(now);
+                    self.has_wifi_been_online = true;
+                    ERROR_CODES.clear(ErrorCodeGroup::Wifi);
+                    if let Some(c) = candidate {
+                        network_status.set_ipv4_mode(wifi_adaptor.ipv4_mode(&c));
+                    }
+
+                    None
+                }
+                _ => None,
+            };
         }
+    }
 
-        // marking [is_wifi_ip_resolved] as true since an ip has been resolved
-        self.is_wifi_ip_resolved = true;
-        // </--- check if the wifi status has turned into the connected state and the ip address has been resolved. --->
-        ////////
+    fn publish_wifi_status(&self, status: &Status, network_status: &NetworkStatus) {
+        if self.connection.state() == WifiConnectionState::Online {
+            network_status.set_ipv4(self.wifi_ip(status));
+            network_status.set_rssi(self.wifi_rssi());
+        } else {
+            network_status.set_ipv4(None);
+            network_status.set_rssi(None);
+        }
     }
 
     fn run_ping_api_worker(
         &mut self,
-        display_tx: &Sender<Option<String>>,
+        display_tx: &SegmentDisplayTx,
+        secondary_display_tx: &SegmentDisplayTx,
         peripheral_tx: &PeripheralTx,
         play_short_period_buzzer_beep_until_time: &Arc<AtomicSystemTime>,
         is_continuous_period_buzzer_beep_active: &Arc<AtomicBool>,
+        is_segment_display_healthy: &Arc<AtomicBool>,
+        is_segment_display_self_test_requested: &Arc<AtomicBool>,
+        segment_display_self_test_result: &Arc<AtomicU8>,
+        network_status: &NetworkStatus,
     ) -> anyhow::Result<()> {
-        if !self.is_network_connected {
+        let connection_state = self.connection.state();
+
+        if !matches!(
+            connection_state,
+            WifiConnectionState::ObtainingIp | WifiConnectionState::Online
+        ) {
             log::debug!(
                 "[network feature] [run_apis] waiting for the wifi \
                      connection to get established..."
             );
 
-            let display_tx_res = display_tx.send(Some(SegmentDisplayText::ERR_NO_WIFI.to_owned()));
+            let display_tx_res = display_tx.send(SegmentDisplayMessage::new(
+                &SEGMENT_DISPLAY_TEXTS.text(SegmentDisplayTextKey::ErrNoWifi),
+                SegmentDisplayMessagePriority::Warning,
+                SegmentDisplayMessageSource::Wifi,
+                Duration::from_millis(DefaultValues::SEGMENT_DISPLAY_STATUS_MESSAGE_TTL_MS),
+            ));
             if let Err(err) = display_tx_res {
                 error!(
                     "[E0027a][run_ping_api_worker display_tx_res] {}",
@@ -189,13 +389,18 @@ impl Network {
             return Err(WifiError::NotConnected("E0017".to_owned()).into());
         }
 
-        if !self.is_wifi_ip_resolved {
+        if connection_state != WifiConnectionState::Online {
             log::debug!(
                 "[network feature] [run_apis] waiting for the \
             ip to get resolved..."
             );
 
-            let display_tx_res = display_tx.send(Some(SegmentDisplayText::ERR_NO_WIFI.to_owned()));
+            let display_tx_res = display_tx.send(SegmentDisplayMessage::new(
+                &SEGMENT_DISPLAY_TEXTS.text(SegmentDisplayTextKey::ErrNoWifi),
+                SegmentDisplayMessagePriority::Warning,
+                SegmentDisplayMessageSource::Wifi,
+                Duration::from_millis(DefaultValues::SEGMENT_DISPLAY_STATUS_MESSAGE_TTL_MS),
+            ));
             if let Err(err) = display_tx_res {
                 error!(
                     "[E0027b][run_ping_api_worker display_tx] {}",
@@ -234,11 +439,22 @@ impl Network {
         Peripheral::set_peripheral(peripheral_tx, PeripheralKind::ProximaApiRequestLed(Low));
 
         // network request starts here
-        let ping_resp = NETWORK_APIS.ping(self.is_first_ping_after_device_turned_on);
+        let ping_resp = NETWORK_APIS.ping(
+            self.is_first_ping_after_device_turned_on,
+            !is_segment_display_healthy.load(Ordering::Relaxed),
+            SegmentDisplaySelfTestResult::from_u8(
+                segment_display_self_test_result.load(Ordering::Relaxed),
+            ),
+            network_status.ipv4_mode(),
+        );
         let processed_network_response = self.process_network_response(&ping_resp);
         match processed_network_response {
             // successful api request
             Either::Left(ping_response) => {
+                network_status.set_ping_succeeded();
+                ERROR_CODES.clear(ErrorCodeGroup::ApiClient);
+                ERROR_CODES.clear(ErrorCodeGroup::ApiResponse);
+
                 // set [is_first_ping_after_device_turned_on] as false if we get [is_first_ping_after_device_turned_on_registered] as true
                 // this means we wouldnt be sending the [is_first_ping_after_device_turned_on] flag
                 // to the sirius proxima api once the 'device turned on' notification is sent to the user
@@ -251,11 +467,62 @@ impl Network {
                     play_short_period_buzzer_beep_until_time,
                     is_continuous_period_buzzer_beep_active,
                 );
+
+                // the segment display thread clears the flag once the self test has started
+                if ping_response.is_segment_display_self_test_requested {
+                    is_segment_display_self_test_requested.store(true, Ordering::Relaxed);
+                }
+
+                if let Some(texts) = &ping_response.segment_display_texts {
+                    for name in SEGMENT_DISPLAY_TEXTS.set_pushed_texts(texts) {
+                        log::warn!(
+                            "[network feature] [run_apis] unknown segment display text: {}",
+                            name
+                        );
+                    }
+                }
+
+                if let Some(level) = &ping_response.segment_display_level {
+                    let res = display_tx.send(SegmentDisplayMessage::bargraph(
+                        level.percent,
+                        level.style,
+                        &level.thresholds,
+                        SegmentDisplayMessagePriority::Info,
+                        SegmentDisplayMessageSource::Level,
+                        Duration::from_millis(DefaultValues::SEGMENT_DISPLAY_STATUS_MESSAGE_TTL_MS),
+                    ));
+                    if let Err(err) = res {
+                        error!("[E0027g][run_ping_api_worker] {}", err.to_string());
+                    }
+                }
+
+                if let Some(text) = &ping_response.secondary_segment_display_text {
+                    // shown until it is replaced, or until the pings stop sending it
+                    let res = secondary_display_tx.send(
+                        SegmentDisplayMessage::new(
+                            text,
+                            SegmentDisplayMessagePriority::Info,
+                            SegmentDisplayMessageSource::Api,
+                            Duration::from_millis(
+                                DefaultValues::SECONDARY_SEGMENT_DISPLAY_TEXT_TTL_MS,
+                            ),
+                        )
+                        .with_repeat(SegmentDisplayMessageRepeat::UntilExpired),
+                    );
+                    if let Err(err) = res {
+                        error!("[E0027f][run_ping_api_worker] {}", err.to_string());
+                    }
+                }
             }
             // api request failed
             Either::Right(segment_display_text) => {
                 if let Some(text) = segment_display_text {
-                    let res = display_tx.send(Some(text.to_owned()));
+                    let res = display_tx.send(SegmentDisplayMessage::new(
+                        &text,
+                        SegmentDisplayMessagePriority::Warning,
+                        SegmentDisplayMessageSource::Api,
+                        Duration::from_millis(DefaultValues::SEGMENT_DISPLAY_STATUS_MESSAGE_TTL_MS),
+                    ));
                     if let Err(err) = res {
                         error!("[E0027c][run_ping_api_worker] {}", err.to_string());
                     }
@@ -270,6 +537,8 @@ impl Network {
         this: Arc<Mutex<Self>>,
         wifi_adaptor: Arc<Mutex<WifiAdaptor>>,
         netmanager_condvar: Arc<Condvar>,
+        wifi_link_rx: Receiver<WifiLink>,
+        network_status: Arc<NetworkStatus>,
     ) -> std::io::Result<JoinHandle<anyhow::Result<()>>> {
         thread::Builder::new()
             .stack_size(STACK_SIZE)
@@ -286,19 +555,41 @@ impl Network {
                     .lock()
                     .map_err(|e| CommonError::MutexGuard("E0014".to_owned(), e.to_string()))?;
 
-                let mut wifi_adaptor = wifi_adaptor
-                    .lock()
-                    .map_err(|e| CommonError::MutexGuard("E0012".to_owned(), e.to_string()))?;
-
                 loop {
                     log::debug!("[start_netmanager_thread] entering into the next iteration...");
 
-                    if Instant::now() - last_exec_time
+                    // the wifi is left to the provisioning portal once it has been requested
+                    let is_wifi_released = network_status.is_provisioning_requested();
+
+                    // the link changes posted by the driver are handled as soon as they arrive
+                    let wifi_links: Vec<WifiLink> = wifi_link_rx.try_iter().collect();
+                    if !is_wifi_released && !wifi_links.is_empty() {
+                        let mut wifi_adaptor = wifi_adaptor.lock().map_err(|e| {
+                            CommonError::MutexGuard("E0012b".to_owned(), e.to_string())
+                        })?;
+
+                        for link in wifi_links {
+                            this.handle_wifi_link_event(link, &mut wifi_adaptor, &network_status);
+                        }
+                    }
+
+                    if is_wifi_released {
+                        log::debug!(
+                            "[start_netmanager_thread] the provisioning portal has been \
+                            requested, skipping this iteration..."
+                        );
+                    } else if Instant::now() - last_exec_time
                         >= Duration::from_millis(
                             DefaultValues::NET_CONNECTION_MANAGER_THREAD_DELAY_MS,
                         )
                     {
-                        this.run_net_connection_worker(&mut wifi_adaptor);
+                        // the adaptor is only held for a single step of the connection, so
+                        // that it is never held by a retry for long
+                        let mut wifi_adaptor = wifi_adaptor.lock().map_err(|e| {
+                            CommonError::MutexGuard("E0012".to_owned(), e.to_string())
+                        })?;
+
+                        this.run_net_connection_worker(&mut wifi_adaptor, &network_status);
 
                         last_exec_time = Instant::now();
                     } else {
@@ -321,10 +612,15 @@ impl Network {
     pub fn start_workers_thread(
         this: Arc<Mutex<Self>>,
         worker_condvar: Arc<Condvar>,
-        display_tx: Sender<Option<String>>,
+        display_tx: SegmentDisplayTx,
+        secondary_display_tx: SegmentDisplayTx,
         peripheral_tx: PeripheralTx,
         play_short_period_buzzer_beep_until_time: Arc<AtomicSystemTime>,
         is_continuous_period_buzzer_beep_active: Arc<AtomicBool>,
+        is_segment_display_healthy: Arc<AtomicBool>,
+        is_segment_display_self_test_requested: Arc<AtomicBool>,
+        segment_display_self_test_result: Arc<AtomicU8>,
+        network_status: Arc<NetworkStatus>,
     ) -> std::io::Result<JoinHandle<anyhow::Result<()>>> {
         thread::Builder::new()
             .stack_size(STACK_SIZE)
@@ -344,9 +640,14 @@ impl Network {
                     if Instant::now() - last_exec_time >= delayed_exec_time_duration {
                         let res = this.run_ping_api_worker(
                             &display_tx,
+                            &secondary_display_tx,
                             &peripheral_tx,
                             &play_short_period_buzzer_beep_until_time,
                             &is_continuous_period_buzzer_beep_active,
+                            &is_segment_display_healthy,
+                            &is_segment_display_self_test_requested,
+                            &segment_display_self_test_result,
+                            &network_status,
                         );
 
                         // skip setting the [last_exec_time] if there were any errors in the API call
@@ -379,11 +680,22 @@ impl Network {
             })
     }
 
+    /// Shown for as long as the buzzer thread keeps sending it.
+    fn switch_off_message() -> SegmentDisplayMessage {
+        SegmentDisplayMessage::new(
+            &SEGMENT_DISPLAY_TEXTS.text(SegmentDisplayTextKey::SwitchOff),
+            SegmentDisplayMessagePriority::Critical,
+            SegmentDisplayMessageSource::Buzzer,
+            Duration::from_millis(DefaultValues::SEGMENT_DISPLAY_ALERT_MESSAGE_TTL_MS),
+        )
+        .with_repeat(SegmentDisplayMessageRepeat::UntilExpired)
+    }
+
     pub fn start_buzzer_thread(
         peripheral_tx: PeripheralTx,
         play_short_period_buzzer_beep_until_time: Arc<AtomicSystemTime>,
         is_continuous_period_buzzer_beep_active: Arc<AtomicBool>,
-        display_tx: Sender<Option<String>>,
+        display_tx: SegmentDisplayTx,
     ) -> std::io::Result<JoinHandle<anyhow::Result<()>>> {
         thread::Builder::new().spawn(move || -> anyhow::Result<()> {
             let mut last_exec_time: Instant = Instant::now();
@@ -408,8 +720,7 @@ impl Network {
                             PeripheralKind::AlertBuzzer(High),
                         );
 
-                        let display_tx_res =
-                            display_tx.send(Some(SegmentDisplayText::SWITCH_OFF.to_owned()));
+                        let display_tx_res = display_tx.send(Self::switch_off_message());
                         if let Err(err) = display_tx_res {
                             error!(
                                 "[E0027e][is_continuous_period_buzzer_beep_active \
@@ -436,8 +747,7 @@ impl Network {
                             PeripheralKind::AlertBuzzer(High),
                         );
 
-                        let display_tx_res =
-                            display_tx.send(Some(SegmentDisplayText::SWITCH_OFF.to_owned()));
+                        let display_tx_res = display_tx.send(Self::switch_off_message());
                         if let Err(err) = display_tx_res {
                             error!(
                                 "[E0027d][is_continuous_period_buzzer_beep_active \
@@ -475,8 +785,13 @@ impl Network {
     pub fn start(
         this: &Arc<Mutex<Self>>,
         wifi_adaptor: &Arc<Mutex<WifiAdaptor>>,
-        seg_display_tx: Sender<Option<String>>,
+        seg_display_tx: SegmentDisplayTx,
+        secondary_seg_display_tx: SegmentDisplayTx,
         peripheral_tx: PeripheralTx,
+        is_segment_display_healthy: &Arc<AtomicBool>,
+        is_segment_display_self_test_requested: &Arc<AtomicBool>,
+        segment_display_self_test_result: &Arc<AtomicU8>,
+        network_status: &Arc<NetworkStatus>,
     ) -> anyhow::Result<()> {
         let peripheral_tx_cloned1 = peripheral_tx.clone();
         let self_cloned1 = Arc::clone(this);
@@ -496,19 +811,43 @@ impl Network {
 
         let seg_display_tx_clone = seg_display_tx.clone();
 
+        // wakes the netmanager thread up as soon as the driver reports a link change
+        let (wifi_link_tx, wifi_link_rx) = channel::<WifiLink>();
+        let netmanager_thread_condvar_cloned1 = Arc::clone(&netmanager_thread_condvar);
+        wifi_adaptor
+            .lock()
+            .map_err(|e| CommonError::MutexGuard("E0012c".to_owned(), e.to_string()))?
+            .subscribe(move |event| {
+                let link = match event {
+                    WifiDriverEvent::StaDisconnected => WifiLink::Down,
+                    WifiDriverEvent::StaGotIp => WifiLink::Up,
+                };
+
+                if wifi_link_tx.send(link).is_ok() {
+                    netmanager_thread_condvar_cloned1.notify_one();
+                }
+            })?;
+
         Self::start_netmanager_thread(
             self_cloned1,
             wifi_adaptor_cloned1,
             netmanager_thread_condvar,
+            wifi_link_rx,
+            Arc::<NetworkStatus>::clone(network_status),
         )?;
 
         Self::start_workers_thread(
             self_cloned2,
             workers_thread_condvar,
             seg_display_tx,
+            secondary_seg_display_tx,
             peripheral_tx,
             play_short_period_buzzer_beep_until_time,
             is_continuous_period_buzzer_beep_active,
+            Arc::<AtomicBool>::clone(is_segment_display_healthy),
+            Arc::<AtomicBool>::clone(is_segment_display_self_test_requested),
+            Arc::<AtomicU8>::clone(segment_display_self_test_result),
+            Arc::<NetworkStatus>::clone(network_status),
         )?;
 
         Self::start_buzzer_thread(
@@ -521,10 +860,35 @@ impl Network {
         Ok(())
     }
 
-    pub const fn new() -> Self {
+    /// [has_saved_wifi_networks]: false while only the compiled-in networks are known, in which
+    /// case the provisioning portal is requested if none of them can be joined after the boot.
+    pub fn new(has_saved_wifi_networks: bool) -> Self {
+        let timeouts = WifiConnectionTimeouts {
+            associating: Duration::from_millis(DefaultValues::WIFI_ASSOCIATING_TIMEOUT_MS),
+            obtaining_ip: Duration::from_millis(DefaultValues::WIFI_OBTAINING_IP_TIMEOUT_MS),
+            backoff: Duration::from_millis(DefaultValues::WIFI_BACKOFF_INITIAL_MS),
+        };
+        let backoff_config = BackoffConfig {
+            initial: Duration::from_millis(DefaultValues::WIFI_BACKOFF_INITIAL_MS),
+            multiplier: DefaultValues::WIFI_BACKOFF_MULTIPLIER,
+            max: Duration::from_millis(DefaultValues::WIFI_BACKOFF_MAX_MS),
+            jitter_percent: DefaultValues::WIFI_BACKOFF_JITTER_PERCENT,
+            reset_after: Duration::from_millis(DefaultValues::WIFI_BACKOFF_RESET_AFTER_MS),
+        };
+
+        // SAFETY: ESP IDF related sys call
+        let seed = unsafe { esp_idf_sys::esp_random() };
+
         Self {
-            is_network_connected: false,
-            is_wifi_ip_resolved: false,
+            connection: WifiConnectionStateMachine::new(timeouts, Instant::now()),
+            wifi_candidate: None,
+            wifi_backoff: Backoff::new(backoff_config, seed),
+            provisioning_after_failed_wifi_cycles: if has_saved_wifi_networks {
+                None
+            } else {
+                Some(DefaultValues::PROVISIONING_AFTER_FAILED_WIFI_CYCLES)
+            },
+            has_wifi_been_online: false,
             is_first_ping_after_device_turned_on: true,
         }
     }
@@ -0,0 +1,101 @@
+use crate::common::adaptors::wifi_networks::WifiIpv4Mode;
+use crate::features::network::connection_state::WifiConnectionState;
+use crate::helpers::atomic_esp_system_time::AtomicSystemTime;
+use std::net::Ipv4Addr;
+use std::sync::atomic::{AtomicBool, AtomicI8, AtomicU32, AtomicU64, AtomicU8, Ordering};
+use std::time::Duration;
+
+/// The state of the network, published by the network threads for the status pages.
+#[derive(Debug, Default)]
+pub struct NetworkStatus {
+    /// [WifiConnectionState] as u8
+    connection_state: AtomicU8,
+
+    /// 0 while there is no ip
+    ipv4: AtomicU32,
+
+    /// signal strength of the access point in dBm; 0 while it is unknown
+    rssi: AtomicI8,
+
+    /// false while the ip address is taken from the DHCP server
+    is_static_ipv4: AtomicBool,
+
+    /// system time in ms of the last successful ping; 0 if there was none
+    last_ping_at_ms: AtomicU64,
+
+    /// set once the network gives up on the known wifi networks, see [Network::new]
+    is_provisioning_requested: AtomicBool,
+}
+
+impl NetworkStatus {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_connection_state(&self, state: WifiConnectionState) {
+        self.connection_state.store(state as u8, Ordering::Relaxed);
+    }
+
+    pub fn connection_state(&self) -> WifiConnectionState {
+        WifiConnectionState::from_u8(self.connection_state.load(Ordering::Relaxed))
+    }
+
+    pub fn set_ipv4(&self, ip: Option<Ipv4Addr>) {
+        self.ipv4.store(ip.map_or(0, u32::from), Ordering::Relaxed);
+    }
+
+    pub fn ipv4(&self) -> Option<Ipv4Addr> {
+        match self.ipv4.load(Ordering::Relaxed) {
+            0 => None,
+            ip => Some(Ipv4Addr::from(ip)),
+        }
+    }
+
+    pub fn set_rssi(&self, rssi: Option<i8>) {
+        self.rssi.store(rssi.unwrap_or(0), Ordering::Relaxed);
+    }
+
+    pub fn rssi(&self) -> Option<i8> {
+        match self.rssi.load(Ordering::Relaxed) {
+            0 => None,
+            rssi => Some(rssi),
+        }
+    }
+
+    pub fn set_ipv4_mode(&self, mode: WifiIpv4Mode) {
+        self.is_static_ipv4
+            .store(mode == WifiIpv4Mode::Static, Ordering::Relaxed);
+    }
+
+    pub fn ipv4_mode(&self) -> WifiIpv4Mode {
+        if self.is_static_ipv4.load(Ordering::Relaxed) {
+            WifiIpv4Mode::Static
+        } else {
+            WifiIpv4Mode::Dhcp
+        }
+    }
+
+    pub fn set_ping_succeeded(&self) {
+        self.last_ping_at_ms
+            .store(AtomicSystemTime::now_millis().max(1), Ordering::Relaxed);
+    }
+
+    /// Time since the last successful ping; [None] if there was none.
+    pub fn last_ping_age(&self) -> Option<Duration> {
+        match self.last_ping_at_ms.load(Ordering::Relaxed) {
+            0 => None,
+            at_ms => Some(Duration::from_millis(
+                AtomicSystemTime::now_millis().saturating_sub(at_ms),
+            )),
+        }
+    }
+
+    pub fn request_provisioning(&self) {
+        self.is_provisioning_requested
+            .store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_provisioning_requested(&self) -> bool {
+        self.is_provisioning_requested.load(Ordering::Relaxed)
+    }
+}
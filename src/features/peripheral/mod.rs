@@ -1,7 +1,12 @@
 use crate::{DeviceError, GpioPinValue};
 use embedded_hal::digital::v2::OutputPin;
-use esp_idf_hal::gpio::{Gpio13, Gpio14, Gpio25, Gpio26, Gpio27, Gpio32, InputOutput, Output};
+use esp_idf_hal::adc;
+use esp_idf_hal::gpio::{
+    Gpio0, Gpio13, Gpio14, Gpio15, Gpio25, Gpio26, Gpio27, Gpio32, Gpio33, Gpio34, Gpio4, Input,
+    InputOutput, Output,
+};
 use esp_idf_hal::peripherals::Peripherals;
+use esp_idf_hal::spi;
 use esp_idf_sys::EspError;
 use log::error;
 use std::sync::mpsc::{Receiver, Sender};
@@ -19,12 +24,31 @@ pub type PeripheralTx = Sender<PeripheralKind>;
 pub type PeripheralRx = Receiver<PeripheralKind>;
 
 pub struct Peripheral {
-    /// clk pin for TM1637
+    /// clk pin for the segment display
     pub inout_g27: Gpio27<InputOutput>,
 
-    /// data io pin for TM1637
+    /// data io pin for the segment display
+    /// open drain, so that the TM1637 can pull it low for the acknowledge and the key scan data
     pub inout_g13: Gpio13<InputOutput>,
 
+    /// strobe pin for TM1638, chip select pin for MAX7219
+    pub inout_g15: Gpio15<InputOutput>,
+
+    /// SPI bus for MAX7219
+    pub spi2: spi::SPI2,
+
+    /// data io pin for the secondary segment display, open drain
+    pub inout_g33: Gpio33<InputOutput>,
+
+    /// clk pin for the secondary segment display when it doesn't share the clk
+    pub inout_g4: Gpio4<InputOutput>,
+
+    /// ADC for the ambient light sensor; ADC2 can't be used while the Wi-Fi is on
+    pub adc1: adc::ADC1,
+
+    /// ambient light sensor (LDR) pin
+    pub analog_g34: Gpio34<adc::Atten11dB<adc::ADC1>>,
+
     /// Power on led
     pub out_g32: Gpio32<Output>,
 
@@ -36,6 +60,10 @@ pub struct Peripheral {
 
     // Alert buzzer
     pub out_g14: Gpio14<Output>,
+
+    /// BOOT button, pressed after the reset to start the provisioning portal; GPIO0 is a
+    /// strapping pin, holding it during the reset starts the serial bootloader
+    pub in_g0: Gpio0<Input>,
 }
 
 pub struct PeripheralFeatureStartPins {
@@ -141,19 +169,31 @@ impl Peripheral {
             None => Err(DeviceError::PeripheralPin("E0030b", "'peripherals' is empty").into()),
             Some(per) => {
                 let inout_g27 = per.pins.gpio27.into_input_output()?;
-                let inout_g13 = per.pins.gpio13.into_input_output()?;
+                let inout_g13 = per.pins.gpio13.into_input_output_od()?;
+                let inout_g15 = per.pins.gpio15.into_input_output()?;
+                let inout_g33 = per.pins.gpio33.into_input_output_od()?;
+                let inout_g4 = per.pins.gpio4.into_input_output()?;
+                let analog_g34 = per.pins.gpio34.into_analog_atten_11db()?;
                 let out_g32 = per.pins.gpio32.into_output()?;
                 let out_g25 = per.pins.gpio25.into_output()?;
                 let out_g26 = per.pins.gpio26.into_output()?;
                 let out_g14 = per.pins.gpio14.into_output()?;
+                let in_g0 = per.pins.gpio0.into_input()?;
 
                 let s = Self {
                     inout_g27,
                     inout_g13,
+                    inout_g15,
+                    spi2: per.spi2,
+                    inout_g33,
+                    inout_g4,
+                    adc1: per.adc1,
+                    analog_g34,
                     out_g32,
                     out_g25,
                     out_g26,
                     out_g14,
+                    in_g0,
                 };
 
                 Ok(s)
@@ -0,0 +1,162 @@
+use std::net::Ipv4Addr;
+
+const DNS_HEADER_LEN: usize = 12;
+const DNS_TYPE_A: u16 = 1;
+const DNS_CLASS_IN: u16 = 1;
+const DNS_ANSWER_TTL_SECS: u32 = 60;
+
+/// The reply to a DNS [query] which points every name at [ip], so that the phones and the
+/// laptops joining the access point open the provisioning portal; [None] if the [query] is
+/// not a standard query with a single question.
+pub fn dns_answer(query: &[u8], ip: Ipv4Addr) -> Option<Vec<u8>> {
+    if query.len() < DNS_HEADER_LEN {
+        return None;
+    }
+
+    let flags = u16::from_be_bytes([query[2], query[3]]);
+    let question_count = u16::from_be_bytes([query[4], query[5]]);
+    // a response, or not a standard query
+    if flags & 0x8000 != 0 || flags & 0x7800 != 0 || question_count != 1 {
+        return None;
+    }
+
+    // the name is a list of labels, each prefixed with its length, ending with a zero length
+    let mut question_end = DNS_HEADER_LEN;
+    loop {
+        let label_len = *query.get(question_end)? as usize;
+        if label_len & 0xC0 != 0 {
+            // compressed names are not expected in a question
+            return None;
+        }
+
+        question_end += 1 + label_len;
+        if label_len == 0 {
+            break;
+        }
+    }
+
+    let qtype = u16::from_be_bytes([*query.get(question_end)?, *query.get(question_end + 1)?]);
+    let qclass = u16::from_be_bytes([*query.get(question_end + 2)?, *query.get(question_end + 3)?]);
+    question_end += 4;
+
+    let is_answered = qtype == DNS_TYPE_A && qclass == DNS_CLASS_IN;
+
+    let mut reply = Vec::with_capacity(question_end + 16);
+    // id
+    reply.extend_from_slice(&query[0..2]);
+    // a response, authoritative, recursion desired as asked, recursion available
+    reply.extend_from_slice(&(0x8480 | (flags & 0x0100)).to_be_bytes());
+    // one question, one answer or none, no authority or additional records
+    reply.extend_from_slice(&1_u16.to_be_bytes());
+    reply.extend_from_slice(&(is_answered as u16).to_be_bytes());
+    reply.extend_from_slice(&[0, 0, 0, 0]);
+    reply.extend_from_slice(&query[DNS_HEADER_LEN..question_end]);
+
+    if is_answered {
+        // the name is a pointer to the one in the question
+        reply.extend_from_slice(&(0xC000 | DNS_HEADER_LEN as u16).to_be_bytes());
+        reply.extend_from_slice(&DNS_TYPE_A.to_be_bytes());
+        reply.extend_from_slice(&DNS_CLASS_IN.to_be_bytes());
+        reply.extend_from_slice(&DNS_ANSWER_TTL_SECS.to_be_bytes());
+        reply.extend_from_slice(&4_u16.to_be_bytes());
+        reply.extend_from_slice(&ip.octets());
+    }
+
+    Some(reply)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PORTAL_IP: Ipv4Addr = Ipv4Addr::new(192, 168, 71, 1);
+
+    /// A standard query for `example.com`, recursion desired.
+    fn query(qtype: u16) -> Vec<u8> {
+        let mut query = vec![0x12, 0x34, 0x01, 0x00, 0, 1, 0, 0, 0, 0, 0, 0];
+        query.extend_from_slice(b"\x07example\x03com\x00");
+        query.extend_from_slice(&qtype.to_be_bytes());
+        query.extend_from_slice(&DNS_CLASS_IN.to_be_bytes());
+
+        query
+    }
+
+    #[test]
+    fn an_a_query_is_answered_with_the_portal_ip() {
+        let query = query(DNS_TYPE_A);
+
+        let reply = dns_answer(&query, PORTAL_IP).unwrap();
+
+        // the id, a response with recursion desired and available, 1 question and 1 answer
+        assert_eq!(
+            reply[..DNS_HEADER_LEN],
+            [0x12, 0x34, 0x85, 0x80, 0, 1, 0, 1, 0, 0, 0, 0]
+        );
+        // the question is sent back as it is
+        assert_eq!(reply[DNS_HEADER_LEN..query.len()], query[DNS_HEADER_LEN..]);
+        assert_eq!(
+            reply[query.len()..],
+            [0xC0, 0x0C, 0, 1, 0, 1, 0, 0, 0, 60, 0, 4, 192, 168, 71, 1]
+        );
+    }
+
+    #[test]
+    fn another_qtype_gets_no_answer() {
+        // AAAA
+        let query = query(28);
+
+        let reply = dns_answer(&query, PORTAL_IP).unwrap();
+
+        assert_eq!(reply.len(), query.len());
+        assert_eq!(reply[6..8], [0, 0]);
+    }
+
+    #[test]
+    fn a_truncated_header_is_ignored() {
+        let query = query(DNS_TYPE_A);
+
+        assert_eq!(dns_answer(&query[..DNS_HEADER_LEN - 1], PORTAL_IP), None);
+        assert_eq!(dns_answer(&[], PORTAL_IP), None);
+    }
+
+    #[test]
+    fn a_truncated_question_is_ignored() {
+        let query = query(DNS_TYPE_A);
+
+        for len in DNS_HEADER_LEN..query.len() {
+            assert_eq!(dns_answer(&query[..len], PORTAL_IP), None, "{}", len);
+        }
+    }
+
+    #[test]
+    fn a_label_running_past_the_query_is_ignored() {
+        let mut query = vec![0x12, 0x34, 0x01, 0x00, 0, 1, 0, 0, 0, 0, 0, 0];
+        query.extend_from_slice(b"\x3Fexample");
+
+        assert_eq!(dns_answer(&query, PORTAL_IP), None);
+    }
+
+    #[test]
+    fn a_compressed_name_is_ignored() {
+        let mut query = vec![0x12, 0x34, 0x01, 0x00, 0, 1, 0, 0, 0, 0, 0, 0];
+        // a pointer back to the header
+        query.extend_from_slice(&[0xC0, 0x00, 0, 1, 0, 1]);
+
+        assert_eq!(dns_answer(&query, PORTAL_IP), None);
+    }
+
+    #[test]
+    fn a_response_or_a_query_with_other_questions_is_ignored() {
+        let mut response = query(DNS_TYPE_A);
+        response[2] |= 0x80;
+        assert_eq!(dns_answer(&response, PORTAL_IP), None);
+
+        let mut inverse_query = query(DNS_TYPE_A);
+        inverse_query[2] |= 0x08;
+        assert_eq!(dns_answer(&inverse_query, PORTAL_IP), None);
+
+        let mut two_questions = query(DNS_TYPE_A);
+        two_questions[5] = 2;
+        assert_eq!(dns_answer(&two_questions, PORTAL_IP), None);
+    }
+}
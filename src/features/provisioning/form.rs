@@ -0,0 +1,446 @@
+use crate::common::adaptors::wifi_networks::{ScannedAccessPoint, WifiAuth, WifiNetwork};
+use crate::common::models::device_settings::DeviceSettings;
+use std::str::FromStr;
+
+/// Longest device name or location, in bytes.
+const MAX_DEVICE_TEXT_LEN: usize = 64;
+
+/// The settings posted by the provisioning page.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProvisioningForm {
+    pub network: WifiNetwork,
+
+    /// [None] if it was left empty, so that the current name is kept
+    pub device_name: Option<String>,
+
+    /// [None] if it was left empty, so that the current location is kept
+    pub device_location: Option<String>,
+}
+
+impl ProvisioningForm {
+    /// Puts the network of the form first, in place of a network with the same ssid, and
+    /// takes over the device name and location if they were filled in.
+    pub fn apply(self, settings: &mut DeviceSettings) {
+        settings
+            .wifi_networks
+            .retain(|n| n.ssid != self.network.ssid);
+        settings.wifi_networks.insert(0, self.network);
+
+        if self.device_name.is_some() {
+            settings.device_name = self.device_name;
+        }
+        if self.device_location.is_some() {
+            settings.device_location = self.device_location;
+        }
+    }
+
+    fn validate_password(auth: WifiAuth, password: &str) -> anyhow::Result<()> {
+        let is_valid = match auth {
+            WifiAuth::None => password.is_empty(),
+            WifiAuth::Wep => matches!(password.len(), 5 | 13),
+            WifiAuth::Wpa | WifiAuth::Wpa2 | WifiAuth::Wpa3 | WifiAuth::Wpa2Wpa3 => {
+                (8..=63).contains(&password.len())
+                    || (password.len() == 64 && password.chars().all(|c| c.is_ascii_hexdigit()))
+            }
+        };
+
+        if !is_valid {
+            return Err(anyhow::anyhow!(
+                "the password doesn't fit the security of the network"
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn device_text(name: &str, value: Option<String>) -> anyhow::Result<Option<String>> {
+        match value.as_deref().map(str::trim) {
+            None | Some("") => Ok(None),
+            Some(v) if v.len() > MAX_DEVICE_TEXT_LEN => Err(anyhow::anyhow!(
+                "the {} is longer than {} bytes",
+                name,
+                MAX_DEVICE_TEXT_LEN
+            )),
+            Some(v) => Ok(Some(v.to_owned())),
+        }
+    }
+}
+
+impl FromStr for ProvisioningForm {
+    type Err = anyhow::Error;
+
+    /// Parses an `application/x-www-form-urlencoded` body with the fields `ssid`, `password`,
+    /// `auth`, `device_name` and `device_location`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut ssid = None;
+        let mut password = None;
+        let mut auth = None;
+        let mut device_name = None;
+        let mut device_location = None;
+
+        for (name, value) in parse_urlencoded(s)? {
+            match name.as_str() {
+                "ssid" => ssid = Some(value),
+                "password" => password = Some(value),
+                "auth" => auth = Some(value),
+                "device_name" => device_name = Some(value),
+                "device_location" => device_location = Some(value),
+                _ => {}
+            }
+        }
+
+        let ssid = ssid.unwrap_or_default();
+        if ssid.is_empty() || ssid.len() > 32 {
+            return Err(anyhow::anyhow!("the ssid is not 1 to 32 bytes long"));
+        }
+
+        let password = password.unwrap_or_default();
+        let auth = match auth.as_deref().map(str::trim) {
+            None | Some("") if password.is_empty() => WifiAuth::None,
+            None | Some("") => WifiAuth::Wpa2,
+            Some(a) => WifiAuth::from_str(a)
+                .map_err(|_| anyhow::anyhow!("the security {:?} is not known", a))?,
+        };
+        Self::validate_password(auth, &password)?;
+
+        Ok(Self {
+            network: WifiNetwork {
+                ssid,
+                password,
+                auth,
+                bssid: None,
+                static_ipv4: None,
+            },
+            device_name: Self::device_text("device name", device_name)?,
+            device_location: Self::device_text("device location", device_location)?,
+        })
+    }
+}
+
+/// The name and value pairs of an `application/x-www-form-urlencoded` body, in their order.
+pub fn parse_urlencoded(body: &str) -> anyhow::Result<Vec<(String, String)>> {
+    body.split('&')
+        .filter(|p| !p.is_empty())
+        .map(|p| {
+            let (name, value) = p.split_once('=').unwrap_or((p, ""));
+
+            Ok((percent_decode(name)?, percent_decode(value)?))
+        })
+        .collect()
+}
+
+/// Decodes the `%XX` escapes and the `+` for a space.
+fn percent_decode(s: &str) -> anyhow::Result<String> {
+    let mut bytes = Vec::with_capacity(s.len());
+    let mut iter = s.bytes();
+
+    while let Some(b) = iter.next() {
+        match b {
+            b'+' => bytes.push(b' '),
+            b'%' => {
+                let hex = [
+                    iter.next().unwrap_or_default(),
+                    iter.next().unwrap_or_default(),
+                ];
+                // from_str_radix alone would take a sign, eg: `%+1`
+                if !hex.iter().all(u8::is_ascii_hexdigit) {
+                    return Err(anyhow::anyhow!("a broken `%` escape in {:?}", s));
+                }
+                let byte = std::str::from_utf8(&hex)
+                    .ok()
+                    .and_then(|h| u8::from_str_radix(h, 16).ok())
+                    .ok_or_else(|| anyhow::anyhow!("a broken `%` escape in {:?}", s))?;
+
+                bytes.push(byte);
+            }
+            b => bytes.push(b),
+        }
+    }
+
+    Ok(String::from_utf8(bytes)?)
+}
+
+/// Escapes the text to be put in the html, as the content or as an attribute value.
+fn html_escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+/// The page of the provisioning portal.
+pub struct ProvisioningPage<'a> {
+    /// the networks around the device, from the last scan
+    pub access_points: &'a [ScannedAccessPoint],
+
+    pub device_name: &'a str,
+    pub device_location: &'a str,
+
+    /// shown above the form, eg: why the settings couldn't be saved
+    pub notice: Option<&'a str>,
+}
+
+impl<'a> ProvisioningPage<'a> {
+    const HEAD: &'static str = "<!DOCTYPE html><html><head><meta charset=\"utf-8\">\
+        <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\
+        <title>Sirius Alpha setup</title></head><body><h1>Sirius Alpha setup</h1>";
+
+    const AUTHS: [(&'static str, &'static str); 6] = [
+        ("wpa2", "WPA2"),
+        ("wpa2_wpa3", "WPA2/WPA3"),
+        ("wpa3", "WPA3"),
+        ("wpa", "WPA"),
+        ("wep", "WEP"),
+        ("none", "Open"),
+    ];
+
+    pub fn render(&self) -> String {
+        let mut html = String::from(Self::HEAD);
+
+        if let Some(notice) = self.notice {
+            html.push_str(&format!("<p><strong>{}</strong></p>", html_escape(notice)));
+        }
+
+        // the strongest access point of each network, strongest first
+        let mut access_points: Vec<&ScannedAccessPoint> = self.access_points.iter().collect();
+        access_points.sort_by_key(|a| -(a.rssi as i16));
+        let mut seen: Vec<&str> = vec![];
+        access_points.retain(|a| {
+            let is_new = !a.ssid.is_empty() && !seen.contains(&a.ssid.as_str());
+            seen.push(&a.ssid);
+
+            is_new
+        });
+
+        html.push_str("<form method=\"post\" action=\"/save\"><h2>Wi-Fi</h2><ul>");
+        for ap in &access_points {
+            html.push_str(&format!(
+                "<li>{} ({} dBm)</li>",
+                html_escape(&ap.ssid),
+                ap.rssi
+            ));
+        }
+        html.push_str("</ul><p><a href=\"/\">Scan again</a></p>");
+
+        html.push_str(
+            "<p><label>Network<br><input name=\"ssid\" list=\"ssids\" maxlength=\"32\" \
+            required></label><datalist id=\"ssids\">",
+        );
+        for ap in &access_points {
+            html.push_str(&format!("<option value=\"{}\">", html_escape(&ap.ssid)));
+        }
+        html.push_str(
+            "</datalist></p><p><label>Password<br><input name=\"password\" \
+            type=\"password\" maxlength=\"64\"></label></p>\
+            <p><label>Security<br><select name=\"auth\">",
+        );
+        for (value, label) in &Self::AUTHS {
+            html.push_str(&format!("<option value=\"{}\">{}</option>", value, label));
+        }
+        html.push_str("</select></label></p>");
+
+        html.push_str(&format!(
+            "<h2>Device</h2><p><label>Name<br><input name=\"device_name\" value=\"{}\" \
+            maxlength=\"{}\"></label></p><p><label>Location<br>\
+            <input name=\"device_location\" value=\"{}\" maxlength=\"{}\"></label></p>",
+            html_escape(self.device_name),
+            MAX_DEVICE_TEXT_LEN,
+            html_escape(self.device_location),
+            MAX_DEVICE_TEXT_LEN
+        ));
+
+        html.push_str("<p><button type=\"submit\">Save and restart</button></p></form>");
+        html.push_str("</body></html>");
+
+        html
+    }
+
+    /// Shown once the settings have been saved, while the device restarts.
+    pub fn render_saved(ssid: &str) -> String {
+        format!(
+            "{}<p>Saved. The device restarts and joins <strong>{}</strong>.</p></body></html>",
+            Self::HEAD,
+            html_escape(ssid)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn network(ssid: &str) -> WifiNetwork {
+        WifiNetwork {
+            ssid: ssid.to_owned(),
+            password: "password".to_owned(),
+            auth: WifiAuth::Wpa2,
+            bssid: None,
+            static_ipv4: None,
+        }
+    }
+
+    fn form(ssid: &str, device_name: Option<&str>) -> ProvisioningForm {
+        ProvisioningForm {
+            network: network(ssid),
+            device_name: device_name.map(str::to_owned),
+            device_location: None,
+        }
+    }
+
+    #[test]
+    fn percent_decode_takes_escapes_and_plus() {
+        assert_eq!(percent_decode("a+b%20c").unwrap(), "a b c");
+        assert_eq!(percent_decode("%2B%26%3d").unwrap(), "+&=");
+        assert_eq!(percent_decode("caf%C3%A9").unwrap(), "caf\u{e9}");
+    }
+
+    #[test]
+    fn percent_decode_fails_on_broken_escapes() {
+        for s in ["%", "%4", "a%zz", "%+1", "%-1"] {
+            assert!(percent_decode(s).is_err(), "{:?}", s);
+        }
+    }
+
+    #[test]
+    fn percent_decode_fails_on_invalid_utf8() {
+        assert!(percent_decode("%C3").is_err());
+    }
+
+    #[test]
+    fn parse_urlencoded_keeps_order_and_empty_values() {
+        assert_eq!(
+            parse_urlencoded("b=1&&a&c=x%3Dy").unwrap(),
+            vec![
+                ("b".to_owned(), "1".to_owned()),
+                ("a".to_owned(), "".to_owned()),
+                ("c".to_owned(), "x=y".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn from_str_decodes_the_fields() {
+        let form = ProvisioningForm::from_str(
+            "ssid=My+Home%21&password=pass+word&auth=wpa3&device_name=+Kitchen+&device_location=",
+        )
+        .unwrap();
+
+        assert_eq!(form.network.ssid, "My Home!");
+        assert_eq!(form.network.password, "pass word");
+        assert_eq!(form.network.auth, WifiAuth::Wpa3);
+        assert_eq!(form.device_name.as_deref(), Some("Kitchen"));
+        assert_eq!(form.device_location, None);
+    }
+
+    #[test]
+    fn from_str_fails_on_a_broken_escape() {
+        assert!(ProvisioningForm::from_str("ssid=Home%2&password=").is_err());
+    }
+
+    #[test]
+    fn from_str_checks_the_ssid_length() {
+        assert!(ProvisioningForm::from_str("ssid=&password=").is_err());
+        assert!(ProvisioningForm::from_str("password=").is_err());
+
+        let ssid = "s".repeat(32);
+        assert!(ProvisioningForm::from_str(&format!("ssid={}", ssid)).is_ok());
+        assert!(ProvisioningForm::from_str(&format!("ssid={}s", ssid)).is_err());
+    }
+
+    #[test]
+    fn from_str_picks_the_auth_from_the_password() {
+        let open = ProvisioningForm::from_str("ssid=Cafe&password=").unwrap();
+        assert_eq!(open.network.auth, WifiAuth::None);
+
+        let secured = ProvisioningForm::from_str("ssid=Home&password=password").unwrap();
+        assert_eq!(secured.network.auth, WifiAuth::Wpa2);
+
+        assert!(ProvisioningForm::from_str("ssid=Home&password=password&auth=wpa9").is_err());
+    }
+
+    #[test]
+    fn validate_password_follows_the_auth() {
+        let hex_key = "0123456789abcdef".repeat(4);
+        let cases = [
+            (WifiAuth::None, "", true),
+            (WifiAuth::None, "password", false),
+            (WifiAuth::Wep, "12345", true),
+            (WifiAuth::Wep, "1234567890123", true),
+            (WifiAuth::Wep, "123456", false),
+            (WifiAuth::Wpa, "1234567", false),
+            (WifiAuth::Wpa2, "12345678", true),
+            (WifiAuth::Wpa3, &hex_key[..63], true),
+            (WifiAuth::Wpa2Wpa3, &hex_key, true),
+            (WifiAuth::Wpa2, &format!("{}g", &hex_key[..63]), false),
+            (WifiAuth::Wpa2, &format!("{}0", hex_key), false),
+        ];
+
+        for (auth, password, is_valid) in cases {
+            assert_eq!(
+                ProvisioningForm::validate_password(auth, password).is_ok(),
+                is_valid,
+                "{:?} {:?}",
+                auth,
+                password
+            );
+        }
+    }
+
+    #[test]
+    fn device_text_takes_empty_as_none_and_limits_the_length() {
+        assert_eq!(ProvisioningForm::device_text("name", None).unwrap(), None);
+        assert_eq!(
+            ProvisioningForm::device_text("name", Some("   ".to_owned())).unwrap(),
+            None
+        );
+        assert_eq!(
+            ProvisioningForm::device_text("name", Some("d".repeat(64))).unwrap(),
+            Some("d".repeat(64))
+        );
+        assert!(ProvisioningForm::device_text("name", Some("d".repeat(65))).is_err());
+    }
+
+    #[test]
+    fn apply_puts_the_network_first_and_drops_its_duplicate() {
+        let mut settings = DeviceSettings {
+            wifi_networks: vec![network("Office"), network("Home"), network("Cafe")],
+            ..DeviceSettings::default()
+        };
+        let mut form = form("Home", None);
+        form.network.password = "new password".to_owned();
+
+        form.apply(&mut settings);
+
+        let ssids: Vec<&str> = settings
+            .wifi_networks
+            .iter()
+            .map(|n| n.ssid.as_str())
+            .collect();
+        assert_eq!(ssids, vec!["Home", "Office", "Cafe"]);
+        assert_eq!(settings.wifi_networks[0].password, "new password");
+    }
+
+    #[test]
+    fn apply_keeps_the_device_texts_left_empty() {
+        let mut settings = DeviceSettings {
+            device_name: Some("Hall".to_owned()),
+            device_location: Some("Floor 1".to_owned()),
+            ..DeviceSettings::default()
+        };
+
+        form("Home", Some("Kitchen")).apply(&mut settings);
+
+        assert_eq!(settings.device_name.as_deref(), Some("Kitchen"));
+        assert_eq!(settings.device_location.as_deref(), Some("Floor 1"));
+    }
+}
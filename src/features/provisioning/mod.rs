@@ -0,0 +1,251 @@
+use crate::common::adaptors::settings_store::DeviceSettingsStore;
+use crate::common::errors::error_codes::ERROR_CODES;
+use crate::common::errors::provisioning_errors::ProvisioningError;
+use crate::common::models::device_settings::DeviceSettings;
+use crate::constants::default_values::DefaultValues;
+use crate::constants::strings::Strings;
+use crate::features::provisioning::captive_dns::dns_answer;
+use crate::features::provisioning::form::{ProvisioningForm, ProvisioningPage};
+use crate::helpers::device_identity::DEVICE_IDENTITY;
+use crate::{CommonError, EnvValues, WifiAdaptor};
+use embedded_hal::digital::v2::InputPin;
+use embedded_svc::httpd::registry::Registry;
+use embedded_svc::httpd::{Request, Response};
+use esp_idf_svc::httpd::ServerRegistry;
+use esp_idf_sys::EspError;
+use log::error;
+use std::net::{Ipv4Addr, UdpSocket};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+pub mod captive_dns;
+pub mod form;
+
+/// The paths which the phones and the laptops try after joining a network, to find out
+/// whether they are behind a captive portal.
+const CAPTIVE_PORTAL_PROBE_PATHS: [&str; 6] = [
+    "/generate_204",
+    "/gen_204",
+    "/hotspot-detect.html",
+    "/connecttest.txt",
+    "/ncsi.txt",
+    "/redirect",
+];
+
+pub struct ProvisioningFeature;
+
+impl ProvisioningFeature {
+    /// True if the [button] is held down for [DefaultValues::PROVISIONING_BUTTON_HOLD_MS],
+    /// starting within [DefaultValues::PROVISIONING_BUTTON_WINDOW_MS] of the boot.
+    /// The BOOT button is on GPIO0, a strapping pin: it has to be pressed after the reset, as
+    /// holding it down during the reset starts the serial bootloader.
+    pub fn is_button_held<P>(button: &P) -> bool
+    where
+        P: InputPin<Error = EspError>,
+    {
+        let window = Duration::from_millis(DefaultValues::PROVISIONING_BUTTON_WINDOW_MS);
+        let hold = Duration::from_millis(DefaultValues::PROVISIONING_BUTTON_HOLD_MS);
+
+        let started_at = Instant::now();
+        let mut pressed_since: Option<Instant> = None;
+
+        while started_at.elapsed() < window || pressed_since.is_some() {
+            match button.is_low() {
+                Ok(true) => {
+                    let since = *pressed_since.get_or_insert_with(Instant::now);
+                    if since.elapsed() >= hold {
+                        return true;
+                    }
+                }
+                Ok(false) => pressed_since = None,
+                Err(e) => {
+                    error!("[E0045a][ProvisioningFeature] {}", e.to_string());
+
+                    return false;
+                }
+            }
+
+            thread::sleep(Duration::from_millis(50));
+        }
+
+        false
+    }
+
+    /// Opens the access point and the portal, and restarts the device once the settings have
+    /// been saved; returns only on an error.
+    pub fn run(
+        wifi_adaptor: &Arc<Mutex<WifiAdaptor>>,
+        settings_store: DeviceSettingsStore,
+        settings: DeviceSettings,
+    ) -> anyhow::Result<()> {
+        let ip = Ipv4Addr::from(DefaultValues::PROVISIONING_PORTAL_IP);
+        let mut ap_ssid = format!("{} {}", Strings::APP_NAME, EnvValues::DEVICE_ID);
+        ap_ssid.truncate(32);
+
+        wifi_adaptor
+            .lock()
+            .map_err(|e| CommonError::MutexGuard("E0045b".to_owned(), e.to_string()))?
+            .start_access_point(&ap_ssid, ip)?;
+
+        Self::start_dns_thread(ip)?;
+
+        let is_saved = Arc::new(AtomicBool::from(false));
+        let settings_store = Arc::new(Mutex::new(settings_store));
+        let settings = Arc::new(Mutex::new(settings));
+
+        let mut registry = ServerRegistry::new();
+        for path in CAPTIVE_PORTAL_PROBE_PATHS {
+            registry = registry
+                .at(path)
+                .get(|_| Ok(Response::new(302).header("Location", "/")))?;
+        }
+
+        let wifi_adaptor_cloned1 = Arc::clone(wifi_adaptor);
+        let wifi_adaptor_cloned2 = Arc::clone(wifi_adaptor);
+        let is_saved_cloned1 = Arc::clone(&is_saved);
+
+        let _server = registry
+            .at("/")
+            .get(move |_| Self::page_response(&wifi_adaptor_cloned1, None))?
+            .at("/save")
+            .post(move |req| {
+                Self::save_response(
+                    req,
+                    &wifi_adaptor_cloned2,
+                    &settings_store,
+                    &settings,
+                    &is_saved_cloned1,
+                )
+            })?
+            .start(&Default::default())
+            .map_err(|e| ProvisioningError::Portal("E0045c".to_owned(), e.to_string()))?;
+
+        log::info!(
+            "[ProvisioningFeature] join the wifi `{}` and open http://{} to set up the device",
+            ap_ssid,
+            ip
+        );
+
+        loop {
+            thread::sleep(Duration::from_millis(500));
+
+            if is_saved.load(Ordering::Relaxed) {
+                // let the browser get the page before the access point goes away
+                thread::sleep(Duration::from_millis(
+                    DefaultValues::PROVISIONING_RESTART_DELAY_MS,
+                ));
+
+                log::info!("[ProvisioningFeature] the settings are saved, restarting...");
+
+                // SAFETY: ESP IDF related sys call
+                unsafe {
+                    esp_idf_sys::esp_restart();
+                }
+            }
+        }
+    }
+
+    /// The portal page with the networks around the device.
+    fn page_response(
+        wifi_adaptor: &Arc<Mutex<WifiAdaptor>>,
+        notice: Option<&str>,
+    ) -> anyhow::Result<Response> {
+        let access_points = match wifi_adaptor
+            .lock()
+            .map_err(|e| CommonError::MutexGuard("E0045d".to_owned(), e.to_string()))?
+            .scan_access_points()
+        {
+            Ok(a) => a,
+            Err(e) => {
+                error!("[E0045e][ProvisioningFeature] {}", e.to_string());
+
+                vec![]
+            }
+        };
+
+        let device_name = DEVICE_IDENTITY.device_name();
+        let device_location = DEVICE_IDENTITY.device_location();
+        let page = ProvisioningPage {
+            access_points: &access_points,
+            device_name: &device_name,
+            device_location: &device_location,
+            notice,
+        };
+
+        Ok(Self::html_response(page.render()))
+    }
+
+    fn save_response(
+        mut req: Request,
+        wifi_adaptor: &Arc<Mutex<WifiAdaptor>>,
+        settings_store: &Arc<Mutex<DeviceSettingsStore>>,
+        settings: &Arc<Mutex<DeviceSettings>>,
+        is_saved: &Arc<AtomicBool>,
+    ) -> anyhow::Result<Response> {
+        let form = match ProvisioningForm::from_str(&req.as_string()?) {
+            Ok(f) => f,
+            Err(e) => return Self::page_response(wifi_adaptor, Some(&e.to_string())),
+        };
+        let ssid = form.network.ssid.clone();
+
+        let mut settings = settings
+            .lock()
+            .map_err(|e| CommonError::MutexGuard("E0045f".to_owned(), e.to_string()))?;
+        let mut new_settings = settings.clone();
+        form.apply(&mut new_settings);
+
+        let res = settings_store
+            .lock()
+            .map_err(|e| CommonError::MutexGuard("E0045g".to_owned(), e.to_string()))?
+            .save(&new_settings);
+        if let Err(e) = res {
+            ERROR_CODES.record_anyhow(&e);
+            error!("[E0045h][ProvisioningFeature] {}", e.to_string());
+
+            return Self::page_response(wifi_adaptor, Some("The settings couldn't be saved"));
+        }
+
+        *settings = new_settings;
+        is_saved.store(true, Ordering::Relaxed);
+
+        Ok(Self::html_response(ProvisioningPage::render_saved(&ssid)))
+    }
+
+    fn html_response(html: String) -> Response {
+        Response::new(200)
+            .header("Content-Type", "text/html; charset=utf-8")
+            .body(html.into())
+    }
+
+    /// Answers every name with the [ip] of the portal.
+    fn start_dns_thread(ip: Ipv4Addr) -> anyhow::Result<()> {
+        let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 53))
+            .map_err(|e| ProvisioningError::Portal("E0045i".to_owned(), e.to_string()))?;
+
+        thread::Builder::new().spawn(move || {
+            let mut buf = [0_u8; 512];
+
+            loop {
+                match socket.recv_from(&mut buf) {
+                    Ok((len, from)) => {
+                        if let Some(reply) = dns_answer(&buf[..len], ip) {
+                            if let Err(e) = socket.send_to(&reply, from) {
+                                error!("[E0045j][ProvisioningFeature] {}", e.to_string());
+                            }
+                        }
+                    }
+                    Err(e) => {
+                        error!("[E0045k][ProvisioningFeature] {}", e.to_string());
+
+                        thread::sleep(Duration::from_millis(500));
+                    }
+                }
+            }
+        })?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,212 @@
+use crate::libs::tm1637::mappings::Brightness;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+/// Share of a new ambient light reading in the smoothed value, in percent.
+const AMBIENT_LIGHT_SMOOTHING_PERCENT: i32 = 20;
+
+/// The smoothed ambient light has to move this far past the edge of a brightness level
+/// before the level changes, so that the display doesn't flicker between two levels.
+const AMBIENT_LIGHT_HYSTERESIS_MV: i32 = 60;
+
+const MINUTES_PER_DAY: u16 = 24 * 60;
+
+/// Whether the display is lit and how bright; [`DisplayBrightness::Off`] is the dimmest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DisplayBrightness {
+    Off,
+    On(Brightness),
+}
+
+impl FromStr for DisplayBrightness {
+    type Err = anyhow::Error;
+
+    /// Parses `off` or a brightness level from `0` to `7`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "off" => Ok(DisplayBrightness::Off),
+            level => match level.parse::<u8>()? {
+                l if l <= Brightness::L7 as u8 => {
+                    Ok(DisplayBrightness::On(Brightness::from_level(l)))
+                }
+                l => Err(anyhow::anyhow!("brightness level {} is out of 0 to 7", l)),
+            },
+        }
+    }
+}
+
+/// The local hours of the night, eg: `22:00-07:00`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NightSchedule {
+    /// minutes since the midnight, when the night starts
+    pub start_minute: u16,
+
+    /// minutes since the midnight, when the day starts
+    pub end_minute: u16,
+}
+
+impl NightSchedule {
+    pub fn contains(&self, minute_of_day: u16) -> bool {
+        if self.start_minute <= self.end_minute {
+            (self.start_minute..self.end_minute).contains(&minute_of_day)
+        } else {
+            // the night goes past the midnight
+            minute_of_day >= self.start_minute || minute_of_day < self.end_minute
+        }
+    }
+
+    /// Parses `HH:MM`.
+    fn parse_minute_of_day(s: &str) -> anyhow::Result<u16> {
+        let (hours, minutes) = s
+            .trim()
+            .split_once(':')
+            .ok_or_else(|| anyhow::anyhow!("expected `HH:MM`, got {:?}", s))?;
+
+        let minute_of_day = hours.parse::<u16>()? * 60 + minutes.parse::<u16>()?;
+        if minute_of_day >= MINUTES_PER_DAY {
+            return Err(anyhow::anyhow!("{:?} is not a time of the day", s));
+        }
+
+        Ok(minute_of_day)
+    }
+}
+
+impl FromStr for NightSchedule {
+    type Err = anyhow::Error;
+
+    /// Parses `HH:MM-HH:MM`, eg: `22:00-07:00`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (start, end) = s
+            .split_once('-')
+            .ok_or_else(|| anyhow::anyhow!("expected `HH:MM-HH:MM`, got {:?}", s))?;
+
+        Ok(Self {
+            start_minute: Self::parse_minute_of_day(start)?,
+            end_minute: Self::parse_minute_of_day(end)?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AutoBrightnessConfig {
+    /// ambient light reading at which the display is at its lowest brightness
+    pub dark_mv: u16,
+
+    /// ambient light reading at which the display is at its highest brightness.
+    /// May be lower than [dark_mv] if the LDR pulls the ADC pin down in the light
+    pub bright_mv: u16,
+
+    /// used while there is no ambient light reading
+    pub day_brightness: DisplayBrightness,
+    pub night_brightness: DisplayBrightness,
+
+    /// [None] if the day brightness is used all the time
+    pub night_schedule: Option<NightSchedule>,
+
+    /// [None] if the display never dims
+    pub idle_timeout: Option<Duration>,
+
+    /// how the display looks after [idle_timeout] without any activity; never brighter than
+    /// it would be otherwise
+    pub idle_brightness: DisplayBrightness,
+}
+
+/// Picks the display brightness from the ambient light sensor, or from the night schedule
+/// when there is no sensor reading. The time is passed in by the caller, so that it doesn't
+/// depend on the clock.
+#[derive(Debug, Clone)]
+pub struct AutoBrightness {
+    config: AutoBrightnessConfig,
+
+    /// exponential moving average of the ambient light readings
+    smoothed_mv: Option<i32>,
+
+    /// brightness level picked from [smoothed_mv]
+    ambient_light_level: Option<u8>,
+
+    last_activity_at: Instant,
+}
+
+impl AutoBrightness {
+    pub fn new(config: AutoBrightnessConfig, now: Instant) -> Self {
+        Self {
+            config,
+            smoothed_mv: None,
+            ambient_light_level: None,
+            last_activity_at: now,
+        }
+    }
+
+    pub fn add_ambient_light_reading(&mut self, mv: u16) {
+        let mv = mv as i32;
+        let smoothed_mv = match self.smoothed_mv {
+            None => mv,
+            Some(s) => s + (mv - s) * AMBIENT_LIGHT_SMOOTHING_PERCENT / 100,
+        };
+        self.smoothed_mv = Some(smoothed_mv);
+
+        // keep the level as long as it is within the hysteresis band of the reading
+        let lowest_level = self.level_of(smoothed_mv - AMBIENT_LIGHT_HYSTERESIS_MV);
+        let highest_level = self.level_of(smoothed_mv + AMBIENT_LIGHT_HYSTERESIS_MV);
+        let (lowest_level, highest_level) = if lowest_level <= highest_level {
+            (lowest_level, highest_level)
+        } else {
+            (highest_level, lowest_level)
+        };
+
+        self.ambient_light_level = match self.ambient_light_level {
+            Some(l) if (lowest_level..=highest_level).contains(&l) => Some(l),
+            _ => Some(self.level_of(smoothed_mv)),
+        };
+    }
+
+    /// Forgets the ambient light readings, eg: when the sensor has failed, so that the night
+    /// schedule is used.
+    pub fn clear_ambient_light(&mut self) {
+        self.smoothed_mv = None;
+        self.ambient_light_level = None;
+    }
+
+    /// Something has been shown on the display, eg: a message or an alert; wakes the display.
+    pub fn register_activity(&mut self, now: Instant) {
+        self.last_activity_at = now;
+    }
+
+    /// The brightness at [now]; [minute_of_day] is the local time, [None] while it is unknown.
+    pub fn brightness(&self, now: Instant, minute_of_day: Option<u16>) -> DisplayBrightness {
+        let brightness = match (
+            self.ambient_light_level,
+            self.config.night_schedule,
+            minute_of_day,
+        ) {
+            (Some(level), _, _) => DisplayBrightness::On(Brightness::from_level(level)),
+            (None, Some(night), Some(m)) if night.contains(m) => self.config.night_brightness,
+            (None, _, _) => self.config.day_brightness,
+        };
+
+        let is_idle = self.config.idle_timeout.map_or(false, |t| {
+            now.saturating_duration_since(self.last_activity_at) >= t
+        });
+
+        if is_idle {
+            self.config.idle_brightness.min(brightness)
+        } else {
+            brightness
+        }
+    }
+
+    /// The brightness level (0 to 7) of an ambient light reading.
+    fn level_of(&self, mv: i32) -> u8 {
+        let dark_mv = self.config.dark_mv as i32;
+        let bright_mv = self.config.bright_mv as i32;
+        let max_level = Brightness::L7 as i32;
+
+        if dark_mv == bright_mv {
+            return max_level as u8;
+        }
+
+        let level = (mv - dark_mv) * max_level / (bright_mv - dark_mv);
+
+        level.clamp(0, max_level) as u8
+    }
+}
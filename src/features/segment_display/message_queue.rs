@@ -0,0 +1,355 @@
+use crate::libs::segment_display::bargraph::BargraphStyle;
+use std::cmp::Reverse;
+use std::time::{Duration, Instant};
+
+/// Higher priorities are shown first; a [`SegmentDisplayMessagePriority::Critical`] message
+/// preempts whatever is on the display.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SegmentDisplayMessagePriority {
+    Info,
+    Warning,
+    Critical,
+}
+
+/// How many times a message is shown before it leaves the queue.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SegmentDisplayMessageRepeat {
+    Once,
+    Times(u8),
+
+    /// The message is shown again and again until its time to live has passed.
+    UntilExpired,
+}
+
+/// Who sent the message. A source has at most one message in the queue; a new message
+/// replaces the one which has been sent before by the same source.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SegmentDisplayMessageSource {
+    Wifi,
+    Api,
+    Buzzer,
+
+    /// The level reported by the API, eg: the water in the tank.
+    Level,
+}
+
+/// What a message shows on the display.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SegmentDisplayMessageContent {
+    /// A text, scrolled if it doesn't fit on the display.
+    Text(String),
+
+    /// A level (0 to 100 %) drawn as a bargraph; [thresholds] are marked on the scale.
+    Bargraph {
+        percent: u8,
+        style: BargraphStyle,
+        thresholds: Vec<u8>,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct SegmentDisplayMessage {
+    pub content: SegmentDisplayMessageContent,
+    pub priority: SegmentDisplayMessagePriority,
+    pub source: SegmentDisplayMessageSource,
+
+    /// The message drops out of the queue once it has been queued for this long,
+    /// whether it has been shown or not.
+    pub ttl: Duration,
+
+    pub repeat: SegmentDisplayMessageRepeat,
+}
+
+impl SegmentDisplayMessage {
+    /// A message which is shown once within [ttl].
+    pub fn new(
+        text: &str,
+        priority: SegmentDisplayMessagePriority,
+        source: SegmentDisplayMessageSource,
+        ttl: Duration,
+    ) -> Self {
+        Self {
+            content: SegmentDisplayMessageContent::Text(text.to_owned()),
+            priority,
+            source,
+            ttl,
+            repeat: SegmentDisplayMessageRepeat::Once,
+        }
+    }
+
+    /// A bargraph of [percent] (0 to 100) which is shown once within [ttl].
+    pub fn bargraph(
+        percent: u8,
+        style: BargraphStyle,
+        thresholds: &[u8],
+        priority: SegmentDisplayMessagePriority,
+        source: SegmentDisplayMessageSource,
+        ttl: Duration,
+    ) -> Self {
+        Self {
+            content: SegmentDisplayMessageContent::Bargraph {
+                percent,
+                style,
+                thresholds: thresholds.to_vec(),
+            },
+            priority,
+            source,
+            ttl,
+            repeat: SegmentDisplayMessageRepeat::Once,
+        }
+    }
+
+    pub const fn with_repeat(mut self, repeat: SegmentDisplayMessageRepeat) -> Self {
+        self.repeat = repeat;
+
+        self
+    }
+}
+
+#[derive(Debug, Clone)]
+struct QueuedMessage {
+    message: SegmentDisplayMessage,
+    expires_at: Instant,
+    shown_count: u8,
+}
+
+impl QueuedMessage {
+    fn is_done(&self, now: Instant) -> bool {
+        if now >= self.expires_at {
+            return true;
+        }
+
+        match self.message.repeat {
+            SegmentDisplayMessageRepeat::Once => self.shown_count >= 1,
+            SegmentDisplayMessageRepeat::Times(n) => self.shown_count >= n,
+            SegmentDisplayMessageRepeat::UntilExpired => false,
+        }
+    }
+}
+
+/// The messages waiting to be shown on the segment display.
+/// The time is passed in by the caller, so that the queue doesn't depend on the clock.
+#[derive(Debug, Clone, Default)]
+pub struct SegmentDisplayMessageQueue {
+    /// oldest first
+    messages: Vec<QueuedMessage>,
+}
+
+impl SegmentDisplayMessageQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queues the [message], replacing the message of the same source.
+    pub fn push(&mut self, message: SegmentDisplayMessage, now: Instant) {
+        self.messages.retain(|m| m.message.source != message.source);
+
+        self.messages.push(QueuedMessage {
+            expires_at: now + message.ttl,
+            message,
+            shown_count: 0,
+        });
+    }
+
+    /// Drops the messages which have expired or have been shown as often as they should be.
+    pub fn remove_expired(&mut self, now: Instant) {
+        self.messages.retain(|m| !m.is_done(now));
+    }
+
+    /// The priority of the message which would be shown next.
+    pub fn top_priority(&mut self, now: Instant) -> Option<SegmentDisplayMessagePriority> {
+        self.remove_expired(now);
+
+        self.messages.iter().map(|m| m.message.priority).max()
+    }
+
+    /// Takes the message to show next: the one with the highest priority, and among those
+    /// the one which has been shown the least, then the oldest one.
+    /// A message which should be shown again stays in the queue.
+    pub fn next(&mut self, now: Instant) -> Option<SegmentDisplayMessage> {
+        self.remove_expired(now);
+
+        let next = self
+            .messages
+            .iter_mut()
+            .enumerate()
+            .min_by_key(|(index, m)| (Reverse(m.message.priority), m.shown_count, *index))
+            .map(|(_, m)| {
+                m.shown_count = m.shown_count.saturating_add(1);
+
+                m.message.clone()
+            });
+
+        self.remove_expired(now);
+
+        next
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TTL: Duration = Duration::from_secs(10);
+
+    fn message(
+        text: &str,
+        priority: SegmentDisplayMessagePriority,
+        source: SegmentDisplayMessageSource,
+    ) -> SegmentDisplayMessage {
+        SegmentDisplayMessage::new(text, priority, source, TTL)
+    }
+
+    fn text(message: Option<SegmentDisplayMessage>) -> Option<String> {
+        match message?.content {
+            SegmentDisplayMessageContent::Text(text) => Some(text),
+            SegmentDisplayMessageContent::Bargraph { .. } => None,
+        }
+    }
+
+    #[test]
+    fn the_highest_priority_comes_first_then_the_oldest() {
+        let now = Instant::now();
+        let mut queue = SegmentDisplayMessageQueue::new();
+        queue.push(
+            message(
+                "info",
+                SegmentDisplayMessagePriority::Info,
+                SegmentDisplayMessageSource::Wifi,
+            ),
+            now,
+        );
+        queue.push(
+            message(
+                "warn api",
+                SegmentDisplayMessagePriority::Warning,
+                SegmentDisplayMessageSource::Api,
+            ),
+            now,
+        );
+        queue.push(
+            message(
+                "warn buzzer",
+                SegmentDisplayMessagePriority::Warning,
+                SegmentDisplayMessageSource::Buzzer,
+            ),
+            now,
+        );
+        queue.push(
+            message(
+                "critical",
+                SegmentDisplayMessagePriority::Critical,
+                SegmentDisplayMessageSource::Level,
+            ),
+            now,
+        );
+
+        assert_eq!(
+            queue.top_priority(now),
+            Some(SegmentDisplayMessagePriority::Critical)
+        );
+        assert_eq!(text(queue.next(now)).as_deref(), Some("critical"));
+        assert_eq!(text(queue.next(now)).as_deref(), Some("warn api"));
+        assert_eq!(text(queue.next(now)).as_deref(), Some("warn buzzer"));
+        assert_eq!(text(queue.next(now)).as_deref(), Some("info"));
+        assert_eq!(text(queue.next(now)), None);
+    }
+
+    #[test]
+    fn a_new_message_replaces_the_one_of_the_same_source() {
+        let now = Instant::now();
+        let mut queue = SegmentDisplayMessageQueue::new();
+        queue.push(
+            message(
+                "first",
+                SegmentDisplayMessagePriority::Critical,
+                SegmentDisplayMessageSource::Api,
+            ),
+            now,
+        );
+        queue.push(
+            message(
+                "second",
+                SegmentDisplayMessagePriority::Info,
+                SegmentDisplayMessageSource::Api,
+            ),
+            now,
+        );
+
+        assert_eq!(
+            queue.top_priority(now),
+            Some(SegmentDisplayMessagePriority::Info)
+        );
+        assert_eq!(text(queue.next(now)).as_deref(), Some("second"));
+        assert_eq!(text(queue.next(now)), None);
+    }
+
+    #[test]
+    fn a_message_drops_out_once_its_ttl_has_passed() {
+        let now = Instant::now();
+        let mut queue = SegmentDisplayMessageQueue::new();
+        queue.push(
+            message(
+                "late",
+                SegmentDisplayMessagePriority::Warning,
+                SegmentDisplayMessageSource::Wifi,
+            ),
+            now,
+        );
+
+        assert!(queue
+            .top_priority(now + TTL - Duration::from_millis(1))
+            .is_some());
+        assert_eq!(queue.top_priority(now + TTL), None);
+        assert_eq!(text(queue.next(now + TTL)), None);
+    }
+
+    #[test]
+    fn a_repeated_message_takes_turns_until_it_is_done() {
+        let now = Instant::now();
+        let mut queue = SegmentDisplayMessageQueue::new();
+        queue.push(
+            message(
+                "twice",
+                SegmentDisplayMessagePriority::Info,
+                SegmentDisplayMessageSource::Wifi,
+            )
+            .with_repeat(SegmentDisplayMessageRepeat::Times(2)),
+            now,
+        );
+        queue.push(
+            message(
+                "once",
+                SegmentDisplayMessagePriority::Info,
+                SegmentDisplayMessageSource::Api,
+            ),
+            now,
+        );
+
+        // the message which has been shown the least goes first
+        assert_eq!(text(queue.next(now)).as_deref(), Some("twice"));
+        assert_eq!(text(queue.next(now)).as_deref(), Some("once"));
+        assert_eq!(text(queue.next(now)).as_deref(), Some("twice"));
+        assert_eq!(text(queue.next(now)), None);
+    }
+
+    #[test]
+    fn a_message_repeated_until_expired_stays_until_its_ttl() {
+        let now = Instant::now();
+        let mut queue = SegmentDisplayMessageQueue::new();
+        queue.push(
+            message(
+                "live",
+                SegmentDisplayMessagePriority::Info,
+                SegmentDisplayMessageSource::Api,
+            )
+            .with_repeat(SegmentDisplayMessageRepeat::UntilExpired),
+            now,
+        );
+
+        for _ in 0..5 {
+            assert_eq!(text(queue.next(now)).as_deref(), Some("live"));
+        }
+        assert_eq!(text(queue.next(now + TTL)), None);
+    }
+}
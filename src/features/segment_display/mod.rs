@@ -0,0 +1,810 @@
+use crate::common::errors::error_codes::{ErrorCodeGroup, ToErrorCode, ERROR_CODES};
+use crate::constants::default_values::DefaultValues;
+use crate::constants::env_values::EnvValues;
+use crate::features::network::status::NetworkStatus;
+use crate::features::segment_display::auto_brightness::{
+    AutoBrightness, AutoBrightnessConfig, DisplayBrightness,
+};
+use crate::features::segment_display::message_queue::{
+    SegmentDisplayMessage, SegmentDisplayMessageContent, SegmentDisplayMessagePriority,
+    SegmentDisplayMessageQueue,
+};
+use crate::features::segment_display::status_pages::{
+    StatusPage, StatusPageCarousel, StatusPageSources,
+};
+use crate::helpers::wall_clock::WALL_CLOCK;
+use crate::libs::max7219::Max7219;
+use crate::libs::segment_display::animation::{SegmentAnimation, SegmentRenderer};
+use crate::libs::segment_display::{
+    BannerAutoScrollConfig, SecondarySegmentDisplayWiring, SegmentDisplay, SegmentDisplayKind,
+    SegmentDisplaySelfTestResult,
+};
+use crate::libs::tm1637::errors::TmError;
+use crate::libs::tm1637::mappings::DisplayState;
+use crate::libs::tm1637::shared_pin::SharedOutputPin;
+use crate::libs::tm1637::{DisplayRotation, Tm1637};
+use crate::libs::tm1638::Tm1638;
+use crate::DeviceError;
+use embedded_hal::adc::OneShot;
+use embedded_hal::digital::v2::OutputPin;
+use embedded_svc::sys_time::SystemTime;
+use esp_idf_hal::adc;
+use esp_idf_hal::delay;
+use esp_idf_hal::gpio::{
+    Gpio12, Gpio13, Gpio15, Gpio27, Gpio33, Gpio34, Gpio4, InputOutput, Unknown,
+};
+use esp_idf_hal::prelude::*;
+use esp_idf_hal::spi;
+use esp_idf_sys::EspError;
+use log::error;
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+use std::sync::mpsc::{Receiver, Sender, TryRecvError};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+pub mod auto_brightness;
+pub mod message_queue;
+pub mod status_pages;
+pub mod text_catalog;
+
+pub type SegmentDisplayTx = Sender<SegmentDisplayMessage>;
+pub type SegmentDisplayRx = Receiver<SegmentDisplayMessage>;
+
+/// A TM1637 next to the main display; the type of its CLK pin depends on the
+/// [SecondarySegmentDisplayWiring].
+type SecondaryDisplay<'a> = dyn SegmentDisplay<Error = TmError<EspError>> + 'a;
+
+/// Which pin does what depends on the [SegmentDisplayKind]:
+/// * TM1637: g27 is CLK, g13 is DIO.
+/// * TM1638: g27 is CLK, g13 is DIO, g15 is STB.
+/// * MAX7219: g27 is CLK, g13 is DIN, g15 is LOAD/CS.
+///
+/// The secondary TM1637 has g33 as DIO, and either shares g27 as CLK or has g4 as CLK.
+/// The ambient light sensor (LDR) is read on g34.
+pub struct SegmentDisplayFeatureStartPins {
+    pub inout_g27: Gpio27<InputOutput>,
+    pub inout_g13: Gpio13<InputOutput>,
+    pub inout_g15: Gpio15<InputOutput>,
+    pub spi2: spi::SPI2,
+    pub inout_g33: Gpio33<InputOutput>,
+    pub inout_g4: Gpio4<InputOutput>,
+    pub adc1: adc::ADC1,
+    pub analog_g34: Gpio34<adc::Atten11dB<adc::ADC1>>,
+}
+
+/// The LDR which sets the brightness of the displays.
+struct AmbientLightSensor {
+    adc: adc::PoweredAdc<adc::ADC1>,
+    pin: Gpio34<adc::Atten11dB<adc::ADC1>>,
+}
+
+/// Shows the messages received via its own channel on the secondary display, taking them from
+/// a [SegmentDisplayMessageQueue] as the main display does. The display is cleared once every
+/// message has dropped out of the queue.
+struct SecondaryDisplayStream<'a> {
+    display: Box<SecondaryDisplay<'a>>,
+    seg_display_rx: SegmentDisplayRx,
+    message_queue: SegmentDisplayMessageQueue,
+
+    /// priority of the message on the display; [None] while the display is blank
+    shown_message_priority: Option<SegmentDisplayMessagePriority>,
+
+    renderer: SegmentRenderer,
+    reported_bit_delay_us: Option<u16>,
+}
+
+impl<'a> SecondaryDisplayStream<'a> {
+    fn new(display: Box<SecondaryDisplay<'a>>, seg_display_rx: SegmentDisplayRx) -> Self {
+        Self {
+            display,
+            seg_display_rx,
+            message_queue: SegmentDisplayMessageQueue::new(),
+            shown_message_priority: None,
+            renderer: SegmentRenderer::new(),
+            reported_bit_delay_us: None,
+        }
+    }
+
+    /// Picks up the new messages and renders the next frame.
+    /// Returns the time left until the next frame is due.
+    fn tick(&mut self) -> Option<Duration> {
+        loop {
+            match self.seg_display_rx.try_recv() {
+                Ok(msg) => self.message_queue.push(msg, Instant::now()),
+                Err(TryRecvError::Empty) => break,
+                Err(e) => {
+                    error!(
+                        "[E0029b][segment display printing thread] {}",
+                        e.to_string()
+                    );
+
+                    break;
+                }
+            }
+        }
+
+        let is_idle = self.renderer.is_idle(Instant::now());
+
+        // a critical message preempts the message on the display
+        let is_message_due = match (
+            self.message_queue.top_priority(Instant::now()),
+            self.shown_message_priority,
+        ) {
+            (None, _) => false,
+            (Some(top), Some(shown)) if !is_idle => {
+                top == SegmentDisplayMessagePriority::Critical && top > shown
+            }
+            (Some(_), _) => true,
+        };
+
+        if is_message_due {
+            if let Some(msg) = self.message_queue.next(Instant::now()) {
+                let display_size = self.display.display_size();
+                let banner_config = BannerAutoScrollConfig {
+                    scroll_min_char_count: display_size + 1,
+                    delay_ms: 750,
+                    min_char_count_to_be_displayed: display_size,
+                };
+
+                let animation = message_animation(msg.content, banner_config);
+                if let Err(e) = self.renderer.play(&*self.display, &animation) {
+                    error!("[E0031k][peripherals] {}", e.to_string());
+                    ERROR_CODES.record(e.error_code());
+                }
+
+                self.shown_message_priority = Some(msg.priority);
+            }
+        } else if is_idle && self.shown_message_priority.take().is_some() {
+            self.renderer = SegmentRenderer::new();
+
+            if let Err(e) = self.display.clear() {
+                error!("[E0031j][peripherals] {}", e.to_string());
+                ERROR_CODES.record(e.error_code());
+            }
+        }
+
+        match self.renderer.tick(&mut *self.display, Instant::now()) {
+            Ok(d) => d,
+            Err(err) => {
+                error!(
+                    "[E0028c][segment display printing thread] {}",
+                    err.to_string()
+                );
+
+                None
+            }
+        }
+    }
+}
+
+/// The animation which shows the [content] of a message.
+fn message_animation(
+    content: SegmentDisplayMessageContent,
+    banner_config: BannerAutoScrollConfig,
+) -> SegmentAnimation {
+    match content {
+        SegmentDisplayMessageContent::Text(text) => SegmentAnimation::Scroll {
+            text,
+            config: banner_config,
+            hold_ms: 2000,
+        },
+        SegmentDisplayMessageContent::Bargraph {
+            percent,
+            style,
+            thresholds,
+        } => SegmentAnimation::Bargraph {
+            percent,
+            style,
+            thresholds,
+            duration_ms: DefaultValues::SEGMENT_DISPLAY_BARGRAPH_MS,
+        },
+    }
+}
+
+pub struct SegmentDisplayFeature;
+
+impl SegmentDisplayFeature {
+    /// Spawns the display thread with the display configured in [`EnvValues::SEGMENT_DISPLAY_KIND`]
+    /// and the secondary display configured in [`EnvValues::SECONDARY_SEGMENT_DISPLAY_WIRING`].
+    /// Each display shows the messages received via its own channel; the main display shows the
+    /// status pages of [`EnvValues::SEGMENT_DISPLAY_STATUS_PAGES`] in between.
+    pub fn start(
+        pins: SegmentDisplayFeatureStartPins,
+        seg_display_rx: SegmentDisplayRx,
+        secondary_seg_display_rx: SegmentDisplayRx,
+        is_segment_display_healthy: &Arc<AtomicBool>,
+        is_segment_display_self_test_requested: &Arc<AtomicBool>,
+        segment_display_self_test_result: &Arc<AtomicU8>,
+        network_status: &Arc<NetworkStatus>,
+    ) -> anyhow::Result<()> {
+        let seg_display_kind = EnvValues::segment_display_kind()?;
+        let seg_display_size = EnvValues::segment_display_size()?;
+        let seg_display_digit_order = EnvValues::segment_display_digit_order()?;
+        let seg_display_rotation = if EnvValues::segment_display_upside_down()? {
+            DisplayRotation::UpsideDown
+        } else {
+            DisplayRotation::Normal
+        };
+        let bit_delay_us = EnvValues::segment_display_bit_delay_us()?;
+        let max_bit_delay_us = EnvValues::segment_display_max_bit_delay_us()?;
+        let secondary_wiring = EnvValues::secondary_segment_display_wiring()?;
+        let secondary_size = EnvValues::secondary_segment_display_size()?;
+        let status_pages = EnvValues::segment_display_status_pages()?;
+        let utc_offset_minutes = EnvValues::wall_clock_utc_offset_minutes()?;
+        let is_ambient_light_sensor_enabled = EnvValues::ambient_light_sensor_enabled()?;
+        let auto_brightness_config = AutoBrightnessConfig {
+            dark_mv: EnvValues::ambient_light_dark_mv()?,
+            bright_mv: EnvValues::ambient_light_bright_mv()?,
+            day_brightness: EnvValues::segment_display_day_brightness()?,
+            night_brightness: EnvValues::segment_display_night_brightness()?,
+            night_schedule: EnvValues::segment_display_night_schedule()?,
+            idle_timeout: EnvValues::segment_display_idle_timeout_ms()?.map(Duration::from_millis),
+            idle_brightness: EnvValues::segment_display_idle_brightness()?,
+        };
+
+        // the CLK of the MAX7219 is driven by the SPI bus
+        if seg_display_kind == SegmentDisplayKind::Max7219
+            && secondary_wiring == SecondarySegmentDisplayWiring::SharedClk
+        {
+            let err = DeviceError::PeripheralPin(
+                "E0042",
+                "the secondary segment display can't share the clk with a max7219",
+            );
+            ERROR_CODES.record(err.error_code());
+
+            return Err(err.into());
+        }
+
+        let is_segment_display_healthy = Arc::<AtomicBool>::clone(is_segment_display_healthy);
+        let is_segment_display_self_test_requested =
+            Arc::<AtomicBool>::clone(is_segment_display_self_test_requested);
+        let segment_display_self_test_result =
+            Arc::<AtomicU8>::clone(segment_display_self_test_result);
+        let network_status = Arc::<NetworkStatus>::clone(network_status);
+
+        thread::Builder::new().spawn(move || {
+            let mut status_page_carousel = StatusPageCarousel::new(status_pages);
+            let status_page_sources = StatusPageSources {
+                network_status: &network_status,
+                utc_offset_minutes,
+            };
+            let mut auto_brightness = AutoBrightness::new(auto_brightness_config, Instant::now());
+
+            // without a sensor the brightness follows the night schedule
+            let mut ambient_light_sensor = if is_ambient_light_sensor_enabled {
+                match adc::PoweredAdc::new(pins.adc1, adc::config::Config::new().calibration(true))
+                {
+                    Ok(adc) => Some(AmbientLightSensor {
+                        adc,
+                        pin: pins.analog_g34,
+                    }),
+                    Err(e) => {
+                        error!("[E0043a][peripherals] {}", e.to_string());
+
+                        None
+                    }
+                }
+            } else {
+                None
+            };
+
+            let clk_g27 = SharedOutputPin::new(pins.inout_g27);
+            let mut dio_g13 = pins.inout_g13;
+            let mut stb_g15 = pins.inout_g15;
+            let mut secondary_dio_g33 = pins.inout_g33;
+            let mut secondary_clk_g4 = pins.inout_g4;
+
+            // the CLK is only shared when it is needed, so that the MAX7219 can take it back
+            let mut secondary_clk_g27 = (secondary_wiring
+                == SecondarySegmentDisplayWiring::SharedClk)
+                .then(|| clk_g27.clone());
+
+            let secondary_display = match secondary_wiring {
+                SecondarySegmentDisplayWiring::Disabled => None,
+                SecondarySegmentDisplayWiring::SharedClk => {
+                    secondary_clk_g27.as_mut().and_then(|clk| {
+                        Self::new_secondary_display(
+                            clk,
+                            &mut secondary_dio_g33,
+                            secondary_size,
+                            bit_delay_us,
+                            max_bit_delay_us,
+                        )
+                    })
+                }
+                SecondarySegmentDisplayWiring::Independent => Self::new_secondary_display(
+                    &mut secondary_clk_g4,
+                    &mut secondary_dio_g33,
+                    secondary_size,
+                    bit_delay_us,
+                    max_bit_delay_us,
+                ),
+            };
+            let mut secondary =
+                secondary_display.map(|d| SecondaryDisplayStream::new(d, secondary_seg_display_rx));
+
+            match seg_display_kind {
+                SegmentDisplayKind::Tm1637 => {
+                    let mut clk_g27 = clk_g27;
+                    let mut tm = match Tm1637::new(
+                        &mut clk_g27,
+                        &mut dio_g13,
+                        delay::FreeRtos {},
+                        seg_display_size,
+                    ) {
+                        Ok(t) => t,
+                        Err(e) => {
+                            error!("[E0031d][peripherals] {}", e.to_string());
+
+                            return;
+                        }
+                    };
+                    if let Err(e) = tm.set_digit_order(&seg_display_digit_order) {
+                        error!("[E0031e][peripherals] {}", e.to_string());
+                    }
+                    tm.set_rotation(seg_display_rotation);
+                    tm.set_bit_delay_us(bit_delay_us);
+                    tm.set_max_bit_delay_us(max_bit_delay_us);
+
+                    Self::run(
+                        &mut tm,
+                        &seg_display_rx,
+                        secondary.as_mut(),
+                        &is_segment_display_healthy,
+                        &is_segment_display_self_test_requested,
+                        &segment_display_self_test_result,
+                        &mut status_page_carousel,
+                        &status_page_sources,
+                        ambient_light_sensor.as_mut(),
+                        &mut auto_brightness,
+                    );
+                }
+                SegmentDisplayKind::Tm1638 => {
+                    let mut clk_g27 = clk_g27;
+                    let mut tm =
+                        Tm1638::new(&mut stb_g15, &mut clk_g27, &mut dio_g13, delay::FreeRtos {});
+
+                    Self::run(
+                        &mut tm,
+                        &seg_display_rx,
+                        secondary.as_mut(),
+                        &is_segment_display_healthy,
+                        &is_segment_display_self_test_requested,
+                        &segment_display_self_test_result,
+                        &mut status_page_carousel,
+                        &status_page_sources,
+                        ambient_light_sensor.as_mut(),
+                        &mut auto_brightness,
+                    );
+                }
+                SegmentDisplayKind::Max7219 => {
+                    let clk_g27 = match clk_g27.try_into_inner() {
+                        Ok(c) => c,
+                        Err(_) => {
+                            error!("[E0031i][peripherals] the clk is shared");
+
+                            return;
+                        }
+                    };
+
+                    let config =
+                        <spi::config::Config as Default>::default().baudrate(1.MHz().into());
+                    let spi_pins = spi::Pins {
+                        sclk: clk_g27,
+                        sdo: dio_g13,
+                        sdi: Option::<Gpio12<Unknown>>::None,
+                        cs: Some(stb_g15),
+                    };
+
+                    let spi_master = match spi::Master::<spi::SPI2, _, _, _, _>::new(
+                        pins.spi2, spi_pins, config,
+                    ) {
+                        Ok(s) => s,
+                        Err(e) => {
+                            error!("[E0031f][peripherals] {}", e.to_string());
+
+                            return;
+                        }
+                    };
+
+                    let mut max7219 = match Max7219::new(spi_master, seg_display_size) {
+                        Ok(m) => m,
+                        Err(e) => {
+                            error!("[E0031g][peripherals] {}", e.to_string());
+
+                            return;
+                        }
+                    };
+
+                    Self::run(
+                        &mut max7219,
+                        &seg_display_rx,
+                        secondary.as_mut(),
+                        &is_segment_display_healthy,
+                        &is_segment_display_self_test_requested,
+                        &segment_display_self_test_result,
+                        &mut status_page_carousel,
+                        &status_page_sources,
+                        ambient_light_sensor.as_mut(),
+                        &mut auto_brightness,
+                    );
+                }
+            }
+        })?;
+
+        Ok(())
+    }
+
+    fn new_secondary_display<'a, CLK>(
+        clk: &'a mut CLK,
+        dio: &'a mut Gpio33<InputOutput>,
+        display_size: u8,
+        bit_delay_us: u16,
+        max_bit_delay_us: u16,
+    ) -> Option<Box<SecondaryDisplay<'a>>>
+    where
+        CLK: OutputPin<Error = EspError>,
+    {
+        match Tm1637::new(clk, dio, delay::FreeRtos {}, display_size) {
+            Ok(mut tm) => {
+                tm.set_bit_delay_us(bit_delay_us);
+                tm.set_max_bit_delay_us(max_bit_delay_us);
+
+                Some(Box::new(tm))
+            }
+            Err(e) => {
+                error!("[E0031h][peripherals] {}", e.to_string());
+
+                None
+            }
+        }
+    }
+
+    /// The display loop; shows the status pages and the messages received via [seg_display_rx]
+    /// on the main display, and drives the [secondary] display if there is one.
+    /// The uptime page is shown when no status page is available.
+    /// The brightness of both displays is set by [auto_brightness]; showing a message wakes them.
+    #[allow(clippy::too_many_arguments)]
+    fn run<D>(
+        display: &mut D,
+        seg_display_rx: &SegmentDisplayRx,
+        mut secondary: Option<&mut SecondaryDisplayStream<'_>>,
+        is_segment_display_healthy: &Arc<AtomicBool>,
+        is_segment_display_self_test_requested: &Arc<AtomicBool>,
+        segment_display_self_test_result: &Arc<AtomicU8>,
+        status_page_carousel: &mut StatusPageCarousel,
+        status_page_sources: &StatusPageSources,
+        mut ambient_light_sensor: Option<&mut AmbientLightSensor>,
+        auto_brightness: &mut AutoBrightness,
+    ) where
+        D: SegmentDisplay,
+        D::Error: ToErrorCode,
+    {
+        let system_time = esp_idf_svc::systime::EspSystemTime {};
+
+        let mut applied_brightness = auto_brightness.brightness(
+            Instant::now(),
+            WALL_CLOCK.local_minute_of_day(status_page_sources.utc_offset_minutes),
+        );
+        Self::set_display_brightness(display, applied_brightness);
+        if let Some(s) = secondary.as_mut() {
+            Self::set_display_brightness(&mut *s.display, applied_brightness);
+        }
+
+        let tm_clear_res = display.clear();
+        if let Err(e) = tm_clear_res {
+            error!("[E0031a][peripherals] {}", e.to_string());
+            ERROR_CODES.record(e.error_code());
+        }
+
+        let display_size = display.display_size();
+        let banner_config = BannerAutoScrollConfig {
+            scroll_min_char_count: display_size + 1,
+            delay_ms: 750,
+            min_char_count_to_be_displayed: display_size,
+        };
+
+        let mut renderer = SegmentRenderer::new();
+
+        // the messages received via the [seg_display_rx] channel which are waiting to be shown
+        let mut message_queue = SegmentDisplayMessageQueue::new();
+
+        // priority of the message on the display; [None] while a status page is shown
+        let mut shown_message_priority: Option<SegmentDisplayMessagePriority> = None;
+
+        // show the queued messages only after the defined number of status page screens, unless
+        // they are critical. This is to avoid error texts from hogging up the display
+        // this value is `5` when running time is less than `1 hour`
+        // and `2` when running time is more than `1 hour`
+        let mut min_clock_screens_before_message = 5_i32;
+        let mut clock_screens_since_message = min_clock_screens_before_message;
+
+        let mut show_colon = false;
+
+        let mut reported_bit_delay_us: Option<u16> = None;
+
+        let mut last_ambient_light_reading = Instant::now();
+        let mut is_ambient_light_sensor_failing = false;
+
+        // only the changed digits are sent to the display; rewrite everything now and then
+        // so that a display which has been unplugged and plugged back in is restored
+        let mut last_full_refresh = Instant::now();
+
+        loop {
+            if last_full_refresh.elapsed()
+                >= Duration::from_millis(DefaultValues::SEGMENT_DISPLAY_FULL_REFRESH_MS)
+            {
+                display.invalidate_framebuffer();
+                if let Some(s) = secondary.as_mut() {
+                    s.display.invalidate_framebuffer();
+                }
+
+                last_full_refresh = Instant::now();
+            }
+
+            if is_segment_display_self_test_requested.swap(false, Ordering::Relaxed) {
+                let mut result = Self::run_self_test(display);
+                if let Some(s) = secondary.as_mut() {
+                    if Self::run_self_test(&mut *s.display) == SegmentDisplaySelfTestResult::Failed
+                    {
+                        result = SegmentDisplaySelfTestResult::Failed;
+                    }
+                }
+
+                segment_display_self_test_result.store(result as u8, Ordering::Relaxed);
+            }
+
+            if last_ambient_light_reading.elapsed()
+                >= Duration::from_millis(DefaultValues::AMBIENT_LIGHT_SAMPLE_MS)
+            {
+                if let Some(sensor) = ambient_light_sensor.as_mut() {
+                    match sensor.adc.read(&mut sensor.pin) {
+                        Ok(mv) => {
+                            auto_brightness.add_ambient_light_reading(mv);
+                            is_ambient_light_sensor_failing = false;
+                        }
+                        Err(e) => {
+                            if !is_ambient_light_sensor_failing {
+                                error!(
+                                    "[E0043b][segment display printing thread] the ambient light \
+                                    sensor can't be read, falling back to the night schedule: {:?}",
+                                    e
+                                );
+                            }
+
+                            auto_brightness.clear_ambient_light();
+                            is_ambient_light_sensor_failing = true;
+                        }
+                    }
+                }
+
+                last_ambient_light_reading = Instant::now();
+            }
+
+            let brightness = auto_brightness.brightness(
+                Instant::now(),
+                WALL_CLOCK.local_minute_of_day(status_page_sources.utc_offset_minutes),
+            );
+            if brightness != applied_brightness {
+                Self::set_display_brightness(display, brightness);
+                if let Some(s) = secondary.as_mut() {
+                    Self::set_display_brightness(&mut *s.display, brightness);
+                }
+
+                applied_brightness = brightness;
+            }
+
+            let is_display_healthy =
+                display.is_healthy() && secondary.as_ref().map_or(true, |s| s.display.is_healthy());
+            if is_segment_display_healthy.swap(is_display_healthy, Ordering::Relaxed)
+                != is_display_healthy
+            {
+                if is_display_healthy {
+                    log::info!(
+                        "[segment display printing thread] the segment display is back online"
+                    );
+                    ERROR_CODES.clear(ErrorCodeGroup::Display);
+                } else {
+                    error!(
+                        "[E0038][segment display printing thread] the segment display is missing"
+                    );
+                }
+            }
+
+            loop {
+                match seg_display_rx.try_recv() {
+                    Ok(msg) => message_queue.push(msg, Instant::now()),
+                    Err(TryRecvError::Empty) => break,
+                    Err(e) => {
+                        error!("[E0029][segment display printing thread] {}", e.to_string());
+
+                        break;
+                    }
+                }
+            }
+
+            if renderer.is_idle(Instant::now()) {
+                shown_message_priority = None;
+            }
+
+            // a message preempts the status page, even if its banner is still scrolling.
+            // A critical message preempts the status page right away, and the other messages too
+            let is_message_due = match (
+                message_queue.top_priority(Instant::now()),
+                shown_message_priority,
+            ) {
+                (None, _) => false,
+                (Some(top), Some(shown)) => {
+                    top == SegmentDisplayMessagePriority::Critical && top > shown
+                }
+                (Some(top), None) => {
+                    top == SegmentDisplayMessagePriority::Critical
+                        || clock_screens_since_message >= min_clock_screens_before_message
+                }
+            };
+
+            if is_message_due {
+                if let Some(msg) = message_queue.next(Instant::now()) {
+                    let animation = message_animation(msg.content, banner_config);
+                    if let Err(e) = renderer.play(display, &animation) {
+                        error!("[E0031b][peripherals] {}", e.to_string());
+                        ERROR_CODES.record(e.error_code());
+                    }
+
+                    shown_message_priority = Some(msg.priority);
+                    clock_screens_since_message = 0_i32;
+                    auto_brightness.register_activity(Instant::now());
+                }
+            }
+
+            if renderer.is_idle(Instant::now()) {
+                let uptime = system_time.now();
+
+                min_clock_screens_before_message = if uptime.as_secs() < 3600 {
+                    5_i32
+                } else {
+                    2_i32
+                };
+
+                let page = status_page_carousel
+                    .current(Instant::now(), |p| status_page_sources.is_available(p))
+                    .unwrap_or(StatusPage::Uptime);
+
+                show_colon = !show_colon;
+                let animation = status_page_sources
+                    .animation(page, uptime, show_colon, banner_config)
+                    .or_else(|| {
+                        status_page_sources.animation(
+                            StatusPage::Uptime,
+                            uptime,
+                            show_colon,
+                            banner_config,
+                        )
+                    });
+
+                if let Some(animation) = animation {
+                    if let Err(err) = renderer.play(display, &animation) {
+                        error!(
+                            "[E0028a][segment display printing thread] {}",
+                            err.to_string()
+                        );
+                        ERROR_CODES.record(err.error_code());
+                    }
+                }
+
+                clock_screens_since_message += 1_i32;
+            }
+
+            let next_frame_in = match renderer.tick(display, Instant::now()) {
+                Ok(d) => d,
+                Err(err) => {
+                    error!(
+                        "[E0028b][segment display printing thread] {}",
+                        err.to_string()
+                    );
+                    ERROR_CODES.record(err.error_code());
+
+                    None
+                }
+            };
+
+            let next_frame_in = match (next_frame_in, secondary.as_mut().and_then(|s| s.tick())) {
+                (Some(d), Some(secondary_d)) => Some(d.min(secondary_d)),
+                (d, secondary_d) => d.or(secondary_d),
+            };
+
+            Self::report_bit_delay(&*display, &mut reported_bit_delay_us, "main");
+            if let Some(s) = secondary.as_mut() {
+                Self::report_bit_delay(&*s.display, &mut s.reported_bit_delay_us, "secondary");
+            }
+
+            // wake up in time for the next frame, but often enough to pick up the new messages
+            let tick_duration = Duration::from_millis(DefaultValues::SEGMENT_DISPLAY_TICK_MS);
+            thread::sleep(next_frame_in.map_or(tick_duration, |d| d.min(tick_duration)));
+        }
+    }
+
+    /// Turns the display on with the [brightness], or off.
+    fn set_display_brightness<D>(display: &mut D, brightness: DisplayBrightness)
+    where
+        D: SegmentDisplay + ?Sized,
+        D::Error: ToErrorCode,
+    {
+        match brightness {
+            DisplayBrightness::Off => display.set_display_state(DisplayState::Off),
+            DisplayBrightness::On(b) => {
+                display.set_display_state(DisplayState::On);
+                display.set_brightness(b);
+            }
+        }
+
+        if let Err(e) = display.write_display_state() {
+            error!(
+                "[E0043c][segment display printing thread] {}",
+                e.to_string()
+            );
+            ERROR_CODES.record(e.error_code());
+        }
+    }
+
+    /// Logs the bit delay of the display when the driver has settled on a new one.
+    fn report_bit_delay<D>(display: &D, reported_bit_delay_us: &mut Option<u16>, display_name: &str)
+    where
+        D: SegmentDisplay + ?Sized,
+    {
+        let bit_delay_us = display.bit_delay_us();
+        if bit_delay_us == *reported_bit_delay_us {
+            return;
+        }
+
+        if let Some(d) = bit_delay_us {
+            log::info!(
+                "[segment display printing thread] the bus timing of the {} segment display \
+                has settled on a bit delay of {} us",
+                display_name,
+                d
+            );
+        }
+
+        *reported_bit_delay_us = bit_delay_us;
+    }
+
+    /// Runs the self test of the display, logs the steps which were not acknowledged and
+    /// returns the outcome which is reported with the ping.
+    fn run_self_test<D>(display: &mut D) -> SegmentDisplaySelfTestResult
+    where
+        D: SegmentDisplay + ?Sized,
+    {
+        log::info!("[segment display printing thread] running the segment display self test...");
+
+        let report = display.run_self_test(DefaultValues::SEGMENT_DISPLAY_SELF_TEST_STEP_MS);
+        match &report {
+            None => {
+                log::warn!("[segment display printing thread] the segment display has no self test")
+            }
+            Some(report) if report.is_passed() => log::info!(
+                "[segment display printing thread] the segment display self test passed \
+                ({} steps)",
+                report.steps_count
+            ),
+            Some(report) => {
+                error!(
+                    "[E0041a][segment display printing thread] the segment display self test \
+                    failed: {} of {} steps were not acknowledged",
+                    report.failures.len(),
+                    report.steps_count
+                );
+
+                for failure in &report.failures {
+                    error!(
+                        "[E0041b][segment display printing thread] step {:?}, byte {:?}",
+                        failure.step, failure.byte
+                    );
+                }
+            }
+        }
+
+        SegmentDisplaySelfTestResult::of(report.as_ref())
+    }
+}
@@ -0,0 +1,240 @@
+use crate::common::errors::error_codes::ERROR_CODES;
+use crate::constants::env_values::EnvValues;
+use crate::features::network::connection_state::WifiConnectionState;
+use crate::features::network::status::NetworkStatus;
+use crate::helpers::wall_clock::WALL_CLOCK;
+use crate::libs::segment_display::animation::SegmentAnimation;
+use crate::libs::segment_display::BannerAutoScrollConfig;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use strum_macros::EnumString;
+
+/// A screen shown on the segment display while there is no message to show.
+#[derive(Debug, Clone, Copy, PartialEq, EnumString)]
+pub enum StatusPage {
+    /// Running time since the boot, `00:00` or `00h 00n 00c`.
+    #[strum(serialize = "uptime")]
+    Uptime,
+
+    /// Time of the day, `HH:MM`, once the wall clock has been synced with the server.
+    #[strum(serialize = "clock")]
+    Clock,
+
+    #[strum(serialize = "ip")]
+    Ip,
+
+    /// State of the Wi-Fi connection, eg: `WiFi Scan` or `WiFi On`.
+    #[strum(serialize = "wifi")]
+    Wifi,
+
+    /// Signal strength of the Wi-Fi access point, eg: `rSSI -67`.
+    #[strum(serialize = "rssi")]
+    Rssi,
+
+    /// Time since the last successful ping to Sirius Proxima, eg: `Ping 3n 05c`.
+    #[strum(serialize = "ping_age")]
+    PingAge,
+
+    #[strum(serialize = "version")]
+    Version,
+
+    #[strum(serialize = "device_id")]
+    DeviceId,
+
+    /// The most recent critical error code, eg: `E102`, once there has been one.
+    #[strum(serialize = "error_code")]
+    ErrorCode,
+}
+
+/// A page of the carousel and the minimum time it stays on the display.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StatusPageConfig {
+    pub page: StatusPage,
+    pub dwell: Duration,
+}
+
+impl FromStr for StatusPageConfig {
+    type Err = anyhow::Error;
+
+    /// Parses `page:dwell_ms`, eg: `clock:5000`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (page, dwell_ms) = s
+            .split_once(':')
+            .ok_or_else(|| anyhow::anyhow!("expected `page:dwell_ms`, got {:?}", s))?;
+
+        Ok(Self {
+            page: StatusPage::from_str(page.trim())?,
+            dwell: Duration::from_millis(dwell_ms.trim().parse::<u64>()?),
+        })
+    }
+}
+
+/// Goes through the configured pages one after another. A page stays until its dwell time has
+/// passed; a page whose data is not available (eg: the ip while offline) is skipped.
+#[derive(Debug, Clone)]
+pub struct StatusPageCarousel {
+    pages: Vec<StatusPageConfig>,
+    index: usize,
+
+    /// [None] until a page has been picked, or while no page is available
+    shown_since: Option<Instant>,
+}
+
+impl StatusPageCarousel {
+    pub fn new(pages: Vec<StatusPageConfig>) -> Self {
+        Self {
+            pages,
+            index: 0,
+            shown_since: None,
+        }
+    }
+
+    /// The page to show at [now]; [None] if no page is configured or available.
+    pub fn current<F>(&mut self, now: Instant, is_available: F) -> Option<StatusPage>
+    where
+        F: Fn(StatusPage) -> bool,
+    {
+        let current = self.pages.get(self.index)?;
+
+        let is_due = match self.shown_since {
+            None => true,
+            Some(since) => {
+                now.saturating_duration_since(since) >= current.dwell || !is_available(current.page)
+            }
+        };
+
+        if is_due {
+            let first = match self.shown_since {
+                None => self.index,
+                Some(_) => self.index + 1,
+            };
+
+            let next = (0..self.pages.len())
+                .map(|i| (first + i) % self.pages.len())
+                .find(|i| matches!(self.pages.get(*i), Some(p) if is_available(p.page)));
+
+            match next {
+                Some(i) => {
+                    self.index = i;
+                    self.shown_since = Some(now);
+                }
+                None => {
+                    self.shown_since = None;
+
+                    return None;
+                }
+            }
+        }
+
+        self.pages.get(self.index).map(|p| p.page)
+    }
+}
+
+/// Where the data of the status pages comes from.
+pub struct StatusPageSources<'a> {
+    pub network_status: &'a NetworkStatus,
+
+    /// offset of the local time to UTC, for the clock page
+    pub utc_offset_minutes: i16,
+}
+
+impl<'a> StatusPageSources<'a> {
+    pub fn is_available(&self, page: StatusPage) -> bool {
+        match page {
+            StatusPage::Uptime | StatusPage::Wifi | StatusPage::Version | StatusPage::DeviceId => {
+                true
+            }
+            StatusPage::Clock => WALL_CLOCK.now_unix().is_some(),
+            StatusPage::Ip => self.network_status.ipv4().is_some(),
+            StatusPage::Rssi => self.network_status.rssi().is_some(),
+            StatusPage::PingAge => self.network_status.last_ping_age().is_some(),
+            StatusPage::ErrorCode => ERROR_CODES.latest_critical().is_some(),
+        }
+    }
+
+    /// A single screen of the [page]; [None] if its data is not available.
+    /// [show_colon] is toggled by the caller on each screen, so that the colon blinks.
+    pub fn animation(
+        &self,
+        page: StatusPage,
+        uptime: Duration,
+        show_colon: bool,
+        banner_config: BannerAutoScrollConfig,
+    ) -> Option<SegmentAnimation> {
+        let scroll = |text: String| SegmentAnimation::Scroll {
+            text,
+            config: banner_config,
+            hold_ms: 2000,
+        };
+
+        let animation = match page {
+            StatusPage::Uptime => {
+                let seconds = uptime.as_secs() % 60;
+                let minutes = (uptime.as_secs() / 60) % 60;
+                let hours = (uptime.as_secs() / 60) / 60;
+
+                if hours < 1 {
+                    // when running time is less than 1 hour then just show `00:00`
+                    SegmentAnimation::Static {
+                        text: format!("{:02}{:02}", minutes, seconds),
+                        show_colon,
+                        duration_ms: 1000,
+                    }
+                } else {
+                    // when running time is more than 1 hour then show `00h 00n 00c`
+                    scroll(format!(
+                        "{}{} {:02}{} {:02}{}",
+                        hours, "h", minutes, "n", seconds, "c"
+                    ))
+                }
+            }
+            StatusPage::Clock => {
+                let minute_of_day = WALL_CLOCK.local_minute_of_day(self.utc_offset_minutes)?;
+
+                SegmentAnimation::Static {
+                    text: format!("{:02}{:02}", minute_of_day / 60, minute_of_day % 60),
+                    show_colon,
+                    duration_ms: 1000,
+                }
+            }
+            StatusPage::Ip => scroll(format!("IP {}", self.network_status.ipv4()?)),
+            StatusPage::Wifi => {
+                let state = match self.network_status.connection_state() {
+                    WifiConnectionState::Idle => "off",
+                    WifiConnectionState::Scanning => "Scan",
+                    WifiConnectionState::Associating => "Join",
+                    WifiConnectionState::ObtainingIp => "dHCP",
+                    WifiConnectionState::Online => "On",
+                    WifiConnectionState::Backoff => "rEtry",
+                };
+
+                scroll(format!("WiFi {}", state))
+            }
+            StatusPage::Rssi => scroll(format!("rSSI {}", self.network_status.rssi()?)),
+            StatusPage::PingAge => scroll(format!(
+                "Ping {}",
+                Self::short_duration(self.network_status.last_ping_age()?)
+            )),
+            StatusPage::Version => scroll(format!("v{}", EnvValues::APP_VERSION)),
+            StatusPage::DeviceId => scroll(EnvValues::DEVICE_ID.to_owned()),
+            StatusPage::ErrorCode => scroll(ERROR_CODES.latest_critical()?.short()),
+        };
+
+        Some(animation)
+    }
+
+    /// The two largest units of the [duration], eg: `12c`, `3n 05c` or `2h 03n`.
+    fn short_duration(duration: Duration) -> String {
+        let seconds = duration.as_secs() % 60;
+        let minutes = (duration.as_secs() / 60) % 60;
+        let hours = (duration.as_secs() / 60) / 60;
+
+        if hours > 0 {
+            format!("{}h {:02}n", hours, minutes)
+        } else if minutes > 0 {
+            format!("{}n {:02}c", minutes, seconds)
+        } else {
+            format!("{}c", seconds)
+        }
+    }
+}
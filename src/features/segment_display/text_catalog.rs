@@ -0,0 +1,75 @@
+use crate::constants::segment_display_text::{
+    SegmentDisplayLocale, SegmentDisplayText, SegmentDisplayTextKey,
+};
+use lazy_static::lazy_static;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::RwLock;
+
+lazy_static! {
+    pub static ref SEGMENT_DISPLAY_TEXTS: SegmentDisplayTextCatalog =
+        SegmentDisplayTextCatalog::new();
+}
+
+/// The texts of the segment display in the configured locale.
+/// The texts pushed by the server are used in place of the compiled-in catalog, and a text
+/// which is missing from both is taken from the English catalog.
+#[derive(Debug)]
+pub struct SegmentDisplayTextCatalog {
+    locale: RwLock<SegmentDisplayLocale>,
+    pushed_texts: RwLock<HashMap<SegmentDisplayTextKey, String>>,
+}
+
+impl SegmentDisplayTextCatalog {
+    pub fn new() -> Self {
+        Self {
+            locale: RwLock::new(SegmentDisplayLocale::En),
+            pushed_texts: RwLock::new(HashMap::new()),
+        }
+    }
+
+    pub fn set_locale(&self, locale: SegmentDisplayLocale) {
+        if let Ok(mut l) = self.locale.write() {
+            *l = locale;
+        }
+    }
+
+    /// Replaces the texts pushed before; an empty [texts] goes back to the compiled-in catalog.
+    /// Returns the names in [texts] which are not known.
+    pub fn set_pushed_texts(&self, texts: &HashMap<String, String>) -> Vec<String> {
+        let mut unknown_names = vec![];
+        let mut pushed_texts = HashMap::new();
+
+        for (name, text) in texts {
+            match SegmentDisplayTextKey::from_str(name) {
+                Ok(key) => {
+                    pushed_texts.insert(key, text.clone());
+                }
+                Err(_) => unknown_names.push(name.clone()),
+            }
+        }
+
+        if let Ok(mut t) = self.pushed_texts.write() {
+            *t = pushed_texts;
+        }
+
+        unknown_names
+    }
+
+    pub fn text(&self, key: SegmentDisplayTextKey) -> String {
+        if let Some(text) = self
+            .pushed_texts
+            .read()
+            .ok()
+            .and_then(|t| t.get(&key).cloned())
+        {
+            return text;
+        }
+
+        let locale = self.locale.read().map_or(SegmentDisplayLocale::En, |l| *l);
+
+        SegmentDisplayText::compiled(locale, key)
+            .unwrap_or_else(|| SegmentDisplayText::fallback(key))
+            .to_owned()
+    }
+}
@@ -0,0 +1,251 @@
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BackoffConfig {
+    /// the delay after the first failure
+    pub initial: Duration,
+
+    /// the delay grows by this factor after each failure
+    pub multiplier: f32,
+
+    /// the longest delay, before the jitter
+    pub max: Duration,
+
+    /// the delay is moved by up to this share of it, either way, so that the devices which
+    /// failed together don't retry together
+    pub jitter_percent: u8,
+
+    /// the delays start over from [initial] once the connection has been up for this long
+    pub reset_after: Duration,
+}
+
+/// Exponential backoff with jitter: the delay between the retries grows with each failure up
+/// to a cap, and it starts over once the connection has been stable for a while.
+/// The time is passed in by the caller, and the jitter comes from a seeded generator, so that
+/// it doesn't depend on the clock or on the hardware.
+#[derive(Debug, Clone, Copy)]
+pub struct Backoff {
+    config: BackoffConfig,
+
+    /// failures since the last reset
+    attempt: u32,
+
+    /// [None] while the connection is down
+    up_since: Option<Instant>,
+
+    /// xorshift32 state; never 0
+    rng_state: u32,
+}
+
+impl Backoff {
+    /// [seed] should differ between the devices, eg: taken from the hardware random generator.
+    pub fn new(config: BackoffConfig, seed: u32) -> Self {
+        Self {
+            config,
+            attempt: 0,
+            up_since: None,
+            rng_state: seed.max(1),
+        }
+    }
+
+    pub const fn attempt(&self) -> u32 {
+        self.attempt
+    }
+
+    /// The delay before the next retry, after a failure.
+    pub fn next_delay(&mut self) -> Duration {
+        let base = self.base_delay();
+        self.attempt = self.attempt.saturating_add(1);
+
+        let jitter_range = base.mul_f32(self.config.jitter_percent.min(100) as f32 / 100.0);
+        let jitter = jitter_range.mul_f32(self.next_random_unit());
+
+        // somewhere in `base - jitter_range ..= base + jitter_range`
+        (base + jitter * 2).saturating_sub(jitter_range)
+    }
+
+    /// The connection is up since [now].
+    pub fn mark_up(&mut self, now: Instant) {
+        if self.up_since.is_none() {
+            self.up_since = Some(now);
+        }
+    }
+
+    /// The connection went down at [now]; the delays start over if it had been stable.
+    pub fn mark_down(&mut self, now: Instant) {
+        if let Some(since) = self.up_since.take() {
+            if now.saturating_duration_since(since) >= self.config.reset_after {
+                self.reset();
+            }
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.attempt = 0;
+    }
+
+    /// `initial * multiplier ^ attempt`, capped at [BackoffConfig::max].
+    fn base_delay(&self) -> Duration {
+        let mut delay = self.config.initial;
+
+        for _ in 0..self.attempt {
+            if delay >= self.config.max {
+                break;
+            }
+
+            delay = delay.mul_f32(self.config.multiplier.max(1.0));
+        }
+
+        delay.min(self.config.max)
+    }
+
+    /// A number from 0 to 1.
+    fn next_random_unit(&mut self) -> f32 {
+        let mut x = self.rng_state;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.rng_state = x;
+
+        (x as f64 / u32::MAX as f64) as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(jitter_percent: u8) -> BackoffConfig {
+        BackoffConfig {
+            initial: Duration::from_millis(100),
+            multiplier: 2.0,
+            max: Duration::from_millis(1000),
+            jitter_percent,
+            reset_after: Duration::from_secs(10),
+        }
+    }
+
+    fn fail(backoff: &mut Backoff, times: u32) {
+        for _ in 0..times {
+            backoff.next_delay();
+        }
+    }
+
+    #[test]
+    fn delay_grows_by_the_multiplier_up_to_max() {
+        let mut backoff = Backoff::new(config(0), 1);
+
+        let delays: Vec<u128> = (0..7).map(|_| backoff.next_delay().as_millis()).collect();
+
+        assert_eq!(delays, vec![100, 200, 400, 800, 1000, 1000, 1000]);
+        assert_eq!(backoff.attempt(), 7);
+    }
+
+    #[test]
+    fn multiplier_below_one_keeps_the_delay() {
+        let mut backoff = Backoff::new(
+            BackoffConfig {
+                multiplier: 0.5,
+                ..config(0)
+            },
+            1,
+        );
+
+        fail(&mut backoff, 3);
+
+        assert_eq!(backoff.next_delay().as_millis(), 100);
+    }
+
+    #[test]
+    fn jitter_stays_within_its_share_of_the_delay() {
+        let mut backoff = Backoff::new(config(25), 0x1234_5678);
+        fail(&mut backoff, 4);
+
+        let delays: Vec<Duration> = (0..100)
+            .map(|_| {
+                let delay = backoff.next_delay();
+                backoff.reset();
+                fail(&mut backoff, 4);
+
+                delay
+            })
+            .collect();
+
+        for delay in &delays {
+            assert!(
+                (750..=1250).contains(&delay.as_millis()),
+                "{:?} is outside of 750..=1250 ms",
+                delay
+            );
+        }
+        assert!(delays.iter().any(|d| *d != delays[0]));
+    }
+
+    #[test]
+    fn jitter_is_the_same_for_the_same_seed() {
+        let mut a = Backoff::new(config(50), 42);
+        let mut b = Backoff::new(config(50), 42);
+        let mut c = Backoff::new(config(50), 43);
+
+        let delays_a: Vec<Duration> = (0..5).map(|_| a.next_delay()).collect();
+        let delays_b: Vec<Duration> = (0..5).map(|_| b.next_delay()).collect();
+        let delays_c: Vec<Duration> = (0..5).map(|_| c.next_delay()).collect();
+
+        assert_eq!(delays_a, delays_b);
+        assert_ne!(delays_a, delays_c);
+    }
+
+    #[test]
+    fn jitter_percent_above_100_is_taken_as_100() {
+        let mut backoff = Backoff::new(config(250), 7);
+        fail(&mut backoff, 4);
+
+        for _ in 0..100 {
+            let delay = backoff.next_delay();
+            backoff.reset();
+            fail(&mut backoff, 4);
+
+            assert!(delay <= Duration::from_millis(2000), "{:?}", delay);
+        }
+    }
+
+    #[test]
+    fn mark_down_resets_only_after_reset_after() {
+        let start = Instant::now();
+        let mut backoff = Backoff::new(config(0), 1);
+        fail(&mut backoff, 3);
+
+        backoff.mark_up(start);
+        backoff.mark_down(start + Duration::from_secs(9));
+        assert_eq!(backoff.attempt(), 3);
+        assert_eq!(backoff.next_delay().as_millis(), 800);
+
+        backoff.mark_up(start + Duration::from_secs(20));
+        backoff.mark_down(start + Duration::from_secs(30));
+        assert_eq!(backoff.attempt(), 0);
+        assert_eq!(backoff.next_delay().as_millis(), 100);
+    }
+
+    #[test]
+    fn mark_up_keeps_the_first_time() {
+        let start = Instant::now();
+        let mut backoff = Backoff::new(config(0), 1);
+        fail(&mut backoff, 2);
+
+        backoff.mark_up(start);
+        backoff.mark_up(start + Duration::from_secs(8));
+        backoff.mark_down(start + Duration::from_secs(10));
+
+        assert_eq!(backoff.attempt(), 0);
+    }
+
+    #[test]
+    fn mark_down_without_mark_up_keeps_the_attempts() {
+        let mut backoff = Backoff::new(config(0), 1);
+        fail(&mut backoff, 2);
+
+        backoff.mark_down(Instant::now() + Duration::from_secs(60));
+
+        assert_eq!(backoff.attempt(), 2);
+    }
+}
@@ -0,0 +1,47 @@
+use crate::constants::env_values::EnvValues;
+use lazy_static::lazy_static;
+use std::sync::RwLock;
+
+lazy_static! {
+    pub static ref DEVICE_IDENTITY: DeviceIdentity = DeviceIdentity::new();
+}
+
+/// The name and the location of the device, as they are reported to Sirius Proxima.
+/// The values set in the provisioning portal are used in place of the compiled-in ones.
+#[derive(Debug, Default)]
+pub struct DeviceIdentity {
+    device_name: RwLock<Option<String>>,
+    device_location: RwLock<Option<String>>,
+}
+
+impl DeviceIdentity {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// [None] goes back to the compiled-in value.
+    pub fn set(&self, device_name: Option<String>, device_location: Option<String>) {
+        if let Ok(mut n) = self.device_name.write() {
+            *n = device_name;
+        }
+        if let Ok(mut l) = self.device_location.write() {
+            *l = device_location;
+        }
+    }
+
+    pub fn device_name(&self) -> String {
+        self.device_name
+            .read()
+            .ok()
+            .and_then(|n| n.clone())
+            .unwrap_or_else(|| EnvValues::DEVICE_NAME.to_owned())
+    }
+
+    pub fn device_location(&self) -> String {
+        self.device_location
+            .read()
+            .ok()
+            .and_then(|l| l.clone())
+            .unwrap_or_else(|| EnvValues::DEVICE_LOCATION.to_owned())
+    }
+}
@@ -1,3 +1,6 @@
 pub mod logs;
 pub mod chip_info;
 pub mod atomic_esp_system_time;
+pub mod wall_clock;
+pub mod device_identity;
+pub mod backoff;
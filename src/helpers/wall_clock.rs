@@ -0,0 +1,97 @@
+use crate::helpers::atomic_esp_system_time::AtomicSystemTime;
+use lazy_static::lazy_static;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+lazy_static! {
+    pub static ref WALL_CLOCK: WallClock = WallClock::new();
+}
+
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+/// The time of the day, taken from the `Date` header of the Sirius Proxima responses.
+/// The system time counts from the boot, so only the offset to the unix time is kept.
+#[derive(Debug, Default)]
+pub struct WallClock {
+    /// unix time in ms at the system time zero; 0 until the first sync
+    unix_ms_at_system_time_zero: AtomicU64,
+}
+
+impl WallClock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Syncs the clock with a `Date` header, eg: `Sun, 06 Nov 1994 08:49:37 GMT`.
+    /// Returns false if the date couldn't be parsed.
+    pub fn sync_from_http_date(&self, http_date: &str) -> bool {
+        match parse_http_date(http_date) {
+            None => false,
+            Some(unix_secs) => {
+                let offset_ms = (unix_secs * 1000).saturating_sub(AtomicSystemTime::now_millis());
+                self.unix_ms_at_system_time_zero
+                    .store(offset_ms, Ordering::Relaxed);
+
+                true
+            }
+        }
+    }
+
+    /// The unix time; [None] until the clock has been synced.
+    pub fn now_unix(&self) -> Option<Duration> {
+        match self.unix_ms_at_system_time_zero.load(Ordering::Relaxed) {
+            0 => None,
+            offset_ms => Some(Duration::from_millis(
+                offset_ms + AtomicSystemTime::now_millis(),
+            )),
+        }
+    }
+
+    /// Minutes since the local midnight, 0 to 1439; [None] until the clock has been synced.
+    pub fn local_minute_of_day(&self, utc_offset_minutes: i16) -> Option<u16> {
+        let unix_minutes = (self.now_unix()?.as_secs() / 60) as i64;
+
+        Some((unix_minutes + utc_offset_minutes as i64).rem_euclid(24 * 60) as u16)
+    }
+}
+
+/// Parses an HTTP date in the IMF-fixdate format (`Sun, 06 Nov 1994 08:49:37 GMT`)
+/// into the seconds since the unix epoch.
+pub fn parse_http_date(http_date: &str) -> Option<u64> {
+    let parts: Vec<&str> = http_date.split_whitespace().collect();
+    let (day, month, year, time) = match parts.as_slice() {
+        [_, day, month, year, time, "GMT"] => (day, month, year, time),
+        _ => return None,
+    };
+
+    let day = day.parse::<u64>().ok().filter(|d| (1..=31).contains(d))?;
+    let month = MONTHS.iter().position(|m| m == month)? as u64 + 1;
+    let year = year.parse::<u64>().ok().filter(|y| *y >= 1970)?;
+
+    let hms: Vec<u64> = time
+        .split(':')
+        .map(|t| t.parse::<u64>().ok())
+        .collect::<Option<Vec<u64>>>()?;
+    let (hours, minutes, seconds) = match hms.as_slice() {
+        [h, m, s] if *h < 24 && *m < 60 && *s < 61 => (*h, *m, *s),
+        _ => return None,
+    };
+
+    Some(days_since_unix_epoch(year, month, day) * 86_400 + hours * 3600 + minutes * 60 + seconds)
+}
+
+/// Days from 1970-01-01 to the date of the proleptic Gregorian calendar.
+/// ref: http://howardhinnant.github.io/date_algorithms.html#days_from_civil
+const fn days_since_unix_epoch(year: u64, month: u64, day: u64) -> u64 {
+    // the year starts in March, so that the leap day is the last day of the year
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year / 400;
+    let year_of_era = year - era * 400;
+    let shifted_month = (month + 9) % 12;
+    let day_of_year = (153 * shifted_month + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    era * 146_097 + day_of_era - 719_468
+}
@@ -0,0 +1,19 @@
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum Max7219Error<E> {
+    #[error("an SPI error occured")]
+    Spi(E),
+
+    #[error("{0:?} an auto scroll error occured: {1:?}")]
+    AutoScroll(String, String),
+
+    #[error("{0:?} an invalid display size: {1:?}")]
+    DisplaySize(String, String),
+}
+
+impl<E> From<E> for Max7219Error<E> {
+    fn from(err: E) -> Self {
+        Self::Spi(err)
+    }
+}
@@ -0,0 +1,36 @@
+/// The registers of the MAX7219. Every SPI frame is 16 bits: the register address followed
+/// by the data byte.
+#[repr(u8)]
+pub enum Max7219Register {
+    /// The digit registers are 0x01 (DIG0) to 0x08 (DIG7).
+    Digit0 = 0x01,
+
+    /// 0x00: no BCD decoding, the data bytes are raw segments.
+    DecodeMode = 0x09,
+
+    /// 0x00 (dimmest) to 0x0F (brightest).
+    Intensity = 0x0A,
+
+    /// Number of scanned digits minus one.
+    ScanLimit = 0x0B,
+
+    /// 0x00: shut down, 0x01: normal operation.
+    Shutdown = 0x0C,
+
+    /// 0x01 lights every segment.
+    DisplayTest = 0x0F,
+}
+
+/// The segment bits of a MAX7219 digit register in "no decode"-mode.
+/// Unlike the TM1637 the segment A is the bit 6 and the segment G is the bit 0.
+#[repr(u8)]
+pub enum Max7219SegmentBits {
+    SegG = 0b0000_0001,
+    SegF = 0b0000_0010,
+    SegE = 0b0000_0100,
+    SegD = 0b0000_1000,
+    SegC = 0b0001_0000,
+    SegB = 0b0010_0000,
+    SegA = 0b0100_0000,
+    SegDot = 0b1000_0000,
+}
@@ -0,0 +1,238 @@
+#![allow(clippy::integer_arithmetic, clippy::cast_possible_truncation)]
+
+// ref: https://datasheets.maximintegrated.com/en/ds/MAX7219-MAX7221.pdf
+
+pub mod errors;
+pub mod mappings;
+
+extern crate embedded_hal as hal;
+
+use crate::libs::max7219::errors::Max7219Error;
+use crate::libs::max7219::mappings::{Max7219Register, Max7219SegmentBits};
+use crate::libs::segment_display::animation::{SegmentAnimation, SegmentFrame};
+use crate::libs::segment_display::text_layout::TextLayout;
+use crate::libs::segment_display::SegmentDisplay;
+use crate::libs::tm1637::glyphs::GlyphRegistry;
+use crate::libs::tm1637::mappings::{Brightness, DisplayState, SegmentBits, SpecialCharBits};
+use crate::libs::tm1637::TextAlignment;
+use hal::blocking::spi::Write;
+
+/// A MAX7219 drives up to 8 digits.
+pub const MAX7219_DIGITS_COUNT: u8 = 8;
+
+/// Translates the segments of [`SegmentBits`] into the bit order of the MAX7219.
+const fn to_max7219_segments(segments: u8) -> u8 {
+    let mapping = [
+        (SegmentBits::SegA as u8, Max7219SegmentBits::SegA as u8),
+        (SegmentBits::SegB as u8, Max7219SegmentBits::SegB as u8),
+        (SegmentBits::SegC as u8, Max7219SegmentBits::SegC as u8),
+        (SegmentBits::SegD as u8, Max7219SegmentBits::SegD as u8),
+        (SegmentBits::SegE as u8, Max7219SegmentBits::SegE as u8),
+        (SegmentBits::SegF as u8, Max7219SegmentBits::SegF as u8),
+        (SegmentBits::SegG as u8, Max7219SegmentBits::SegG as u8),
+        (
+            SegmentBits::SegColonOrDot as u8,
+            Max7219SegmentBits::SegDot as u8,
+        ),
+    ];
+
+    let mut output = 0_u8;
+    let mut i = 0;
+    while i < mapping.len() {
+        #[allow(clippy::indexing_slicing)]
+        let (from, to) = mapping[i];
+        if segments & from != 0 {
+            output |= to;
+        }
+        i += 1;
+    }
+
+    output
+}
+
+/// Driver of a MAX7219 module (commonly 8 digits) on the SPI bus.
+/// The chip select is expected to be driven by the SPI peripheral.
+pub struct Max7219<SPI> {
+    spi: SPI,
+    display_size: u8,
+    text_alignment: TextAlignment,
+    brightness: u8,
+    is_on: bool,
+    glyphs: GlyphRegistry,
+}
+
+impl<SPI, E> Max7219<SPI>
+where
+    SPI: Write<u8, Error = E>,
+{
+    /// Creates the driver for a module with [display_size] digits (1 to 8) and wakes the
+    /// MAX7219 up from its shutdown mode.
+    pub fn new(spi: SPI, display_size: u8) -> anyhow::Result<Self, Max7219Error<E>> {
+        if display_size == 0 || display_size > MAX7219_DIGITS_COUNT {
+            return Err(Max7219Error::DisplaySize(
+                "E0040".to_owned(),
+                format!(
+                    "'display_size' should be between 1 and {}, found {}",
+                    MAX7219_DIGITS_COUNT, display_size
+                ),
+            ));
+        }
+
+        let mut max7219 = Self {
+            spi,
+            display_size,
+            text_alignment: TextAlignment::Right,
+            brightness: Brightness::L7 as u8,
+            is_on: true,
+            glyphs: GlyphRegistry::new(),
+        };
+
+        max7219.write_register(Max7219Register::DisplayTest as u8, 0x00)?;
+        max7219.write_register(Max7219Register::DecodeMode as u8, 0x00)?;
+        max7219.write_register(Max7219Register::ScanLimit as u8, display_size - 1)?;
+        max7219.clear()?;
+        max7219.write_display_state()?;
+
+        Ok(max7219)
+    }
+
+    /// Sets the alignment of the texts which are shorter than the display size.
+    pub fn set_text_alignment(&mut self, text_alignment: TextAlignment) {
+        self.text_alignment = text_alignment;
+    }
+
+    fn text_layout(&self) -> TextLayout<'_> {
+        TextLayout::new(self.display_size, self.text_alignment, &self.glyphs)
+    }
+
+    /// Prints a text, padded or cut to the display size.
+    pub fn print_text(
+        &mut self,
+        text: &str,
+        show_colon: bool,
+    ) -> anyhow::Result<(), Max7219Error<E>> {
+        let cells = self.text_layout().padded_cells(text);
+
+        self.print_cells(&cells, show_colon)
+    }
+
+    /// Prints a frame which has been laid out by [`Max7219::animation_frames`].
+    pub fn print_frame(&mut self, frame: &SegmentFrame) -> anyhow::Result<(), Max7219Error<E>> {
+        self.print_cells(&frame.segments, frame.show_colon)
+    }
+
+    fn print_cells(
+        &mut self,
+        cells: &[u8],
+        show_colon: bool,
+    ) -> anyhow::Result<(), Max7219Error<E>> {
+        let mut segments = cells.to_vec();
+
+        // there is no colon on these modules; light the dot after the second digit instead
+        if show_colon {
+            if let Some(s) = segments.get_mut(1) {
+                *s |= SpecialCharBits::ColonOrDot as u8;
+            }
+        }
+
+        self.print_digit_segments(&segments)
+    }
+
+    /// Writes the segments of each digit, from left to right.
+    pub fn print_digit_segments(&mut self, segments: &[u8]) -> anyhow::Result<(), Max7219Error<E>> {
+        for (pos, s) in segments.iter().enumerate() {
+            if pos >= self.display_size as usize {
+                break;
+            }
+
+            // DIG0 is the rightmost digit
+            let register = Max7219Register::Digit0 as u8 + self.display_size - 1 - pos as u8;
+            self.write_register(register, to_max7219_segments(*s))?;
+        }
+
+        Ok(())
+    }
+
+    /// Not committed until [`Max7219::write_display_state`] is called.
+    pub fn set_display_state(&mut self, ds: DisplayState) {
+        self.is_on = matches!(ds, DisplayState::On);
+    }
+
+    /// Not committed until [`Max7219::write_display_state`] is called.
+    pub fn set_brightness(&mut self, brightness: Brightness) {
+        self.brightness = brightness as u8;
+    }
+
+    /// Sends the intensity and the shutdown registers.
+    pub fn write_display_state(&mut self) -> anyhow::Result<(), Max7219Error<E>> {
+        // L0 to L7 are spread over the 16 levels of the MAX7219
+        let intensity = self.brightness * 2 + 1;
+        self.write_register(Max7219Register::Intensity as u8, intensity)?;
+
+        self.write_register(Max7219Register::Shutdown as u8, u8::from(self.is_on))
+    }
+
+    pub fn clear(&mut self) -> anyhow::Result<(), Max7219Error<E>> {
+        self.print_digit_segments(&[0; MAX7219_DIGITS_COUNT as usize])
+    }
+
+    fn write_register(&mut self, register: u8, data: u8) -> anyhow::Result<(), Max7219Error<E>> {
+        self.spi.write(&[register, data])?;
+
+        Ok(())
+    }
+}
+
+impl<SPI, E> SegmentDisplay for Max7219<SPI>
+where
+    SPI: Write<u8, Error = E>,
+{
+    type Error = Max7219Error<E>;
+
+    fn display_size(&self) -> u8 {
+        self.display_size
+    }
+
+    fn print_text(&mut self, text: &str, show_colon: bool) -> anyhow::Result<(), Max7219Error<E>> {
+        Max7219::print_text(self, text, show_colon)
+    }
+
+    fn print_frame(&mut self, frame: &SegmentFrame) -> anyhow::Result<(), Max7219Error<E>> {
+        Max7219::print_frame(self, frame)
+    }
+
+    fn print_digit_segments(&mut self, segments: &[u8]) -> anyhow::Result<(), Max7219Error<E>> {
+        Max7219::print_digit_segments(self, segments)
+    }
+
+    fn set_brightness(&mut self, brightness: Brightness) {
+        Max7219::set_brightness(self, brightness);
+    }
+
+    fn set_display_state(&mut self, ds: DisplayState) {
+        Max7219::set_display_state(self, ds);
+    }
+
+    fn write_display_state(&mut self) -> anyhow::Result<(), Max7219Error<E>> {
+        Max7219::write_display_state(self)
+    }
+
+    fn clear(&mut self) -> anyhow::Result<(), Max7219Error<E>> {
+        Max7219::clear(self)
+    }
+
+    fn animation_frames(
+        &self,
+        animation: &SegmentAnimation,
+    ) -> anyhow::Result<Vec<SegmentFrame>, Max7219Error<E>> {
+        self.text_layout()
+            .animation_frames(animation)
+            .ok_or_else(|| {
+                Max7219Error::AutoScroll(
+                    "E0026c".to_owned(),
+                    "'min_char_count_to_be_displayed' should not be greater than the 'display_size'"
+                        .to_owned(),
+                )
+            })
+    }
+}
@@ -1 +1,4 @@
+pub mod max7219;
+pub mod segment_display;
 pub mod tm1637;
+pub mod tm1638;
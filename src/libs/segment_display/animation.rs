@@ -0,0 +1,145 @@
+use crate::libs::segment_display::bargraph::BargraphStyle;
+use crate::libs::segment_display::{BannerAutoScrollConfig, SegmentDisplay};
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// A single screen of the segment display and how long it stays on the display.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SegmentFrame {
+    /// Segments of each digit, from left to right, padded to the display size.
+    pub(crate) segments: Vec<u8>,
+    pub(crate) show_colon: bool,
+    pub(crate) duration_ms: u16,
+}
+
+/// Animations which are broken down into [SegmentFrame]s by [`SegmentDisplay::animation_frames`].
+#[derive(Debug, Clone)]
+pub enum SegmentAnimation {
+    /// Shows the text for [duration_ms].
+    Static {
+        text: String,
+        show_colon: bool,
+        duration_ms: u16,
+    },
+
+    /// Slides a window of the display size over the text as per the auto scroll config.
+    /// A text which is too short to be scrolled is shown as a single frame.
+    /// The last frame stays on the display for [hold_ms].
+    Scroll {
+        text: String,
+        config: BannerAutoScrollConfig,
+        hold_ms: u16,
+    },
+
+    /// Moves the text in from the right edge until it has left the display on the left edge.
+    Marquee { text: String, step_ms: u16 },
+
+    /// Shows a level (0 to 100 %) as a bargraph for [duration_ms].
+    Bargraph {
+        percent: u8,
+        style: BargraphStyle,
+        thresholds: Vec<u8>,
+        duration_ms: u16,
+    },
+
+    /// Switches the text on and off [count] times.
+    Blink {
+        text: String,
+        on_ms: u16,
+        off_ms: u16,
+        count: u8,
+    },
+}
+
+/// Renders a queue of frames onto any [SegmentDisplay] without blocking the calling thread.
+/// [`SegmentRenderer::tick`] is expected to be called periodically; it only writes to the
+/// display once the current frame has been shown for its duration.
+pub struct SegmentRenderer {
+    frames: VecDeque<SegmentFrame>,
+
+    /// time at which the frame currently on the display expires
+    current_frame_until: Option<Instant>,
+}
+
+impl Default for SegmentRenderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SegmentRenderer {
+    pub const fn new() -> Self {
+        Self {
+            frames: VecDeque::new(),
+            current_frame_until: None,
+        }
+    }
+
+    /// Drops the queued frames and starts the [animation] on the next tick.
+    /// This preempts the animation which is currently in progress.
+    pub fn play<D>(&mut self, display: &D, animation: &SegmentAnimation) -> Result<(), D::Error>
+    where
+        D: SegmentDisplay + ?Sized,
+    {
+        let frames = display.animation_frames(animation)?;
+
+        self.frames.clear();
+        self.frames.extend(frames);
+        self.current_frame_until = None;
+
+        Ok(())
+    }
+
+    /// Appends the [animation] after the frames which are already queued.
+    pub fn enqueue<D>(&mut self, display: &D, animation: &SegmentAnimation) -> Result<(), D::Error>
+    where
+        D: SegmentDisplay + ?Sized,
+    {
+        let frames = display.animation_frames(animation)?;
+
+        self.frames.extend(frames);
+
+        Ok(())
+    }
+
+    /// Returns true if there are no queued frames and the last frame has expired.
+    pub fn is_idle(&self, now: Instant) -> bool {
+        if !self.frames.is_empty() {
+            return false;
+        }
+
+        match self.current_frame_until {
+            None => true,
+            Some(until) => now >= until,
+        }
+    }
+
+    /// Renders the next frame if the current one has expired.
+    /// Returns the time left until the next frame is due, or [None] if the renderer is idle.
+    pub fn tick<D>(&mut self, display: &mut D, now: Instant) -> Result<Option<Duration>, D::Error>
+    where
+        D: SegmentDisplay + ?Sized,
+    {
+        if let Some(until) = self.current_frame_until {
+            if now < until {
+                return Ok(Some(until - now));
+            }
+        }
+
+        let frame = match self.frames.pop_front() {
+            None => {
+                self.current_frame_until = None;
+
+                return Ok(None);
+            }
+            Some(f) => f,
+        };
+
+        let duration = Duration::from_millis(u64::from(frame.duration_ms));
+        self.current_frame_until = Some(now + duration);
+
+        display.print_frame(&frame)?;
+
+        Ok(Some(duration))
+    }
+}
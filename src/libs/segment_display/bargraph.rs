@@ -0,0 +1,97 @@
+use crate::libs::tm1637::mappings::SegmentBits;
+use serde::Deserialize;
+
+/// How a level is drawn across the digits.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub enum BargraphStyle {
+    /// A bar growing from the left to the right, two steps per digit: the left vertical pair
+    /// (F, E) and then the right vertical pair (B, C).
+    /// Threshold markers light the segment A of the digit they fall into.
+    #[serde(rename = "bar")]
+    Bar,
+
+    /// A tank level filling every digit from the bottom to the top, in five steps:
+    /// D, the lower vertical pair (E, C), G, the upper vertical pair (F, B) and A.
+    /// Threshold markers light the horizontal segment (D, G or A) nearest to them on the
+    /// rightmost digit.
+    #[serde(rename = "level")]
+    Level,
+}
+
+const BAR_STEPS_PER_DIGIT: u16 = 2;
+
+const LEVEL_STEPS: [u8; 5] = [
+    SegmentBits::SegD as u8,
+    SegmentBits::SegE as u8 | SegmentBits::SegC as u8,
+    SegmentBits::SegG as u8,
+    SegmentBits::SegF as u8 | SegmentBits::SegB as u8,
+    SegmentBits::SegA as u8,
+];
+
+/// Number of lit steps out of [step_count] for [percent] (0 to 100), rounded to the nearest step.
+fn lit_steps(percent: u8, step_count: u16) -> u16 {
+    let percent = u16::from(percent.min(100));
+
+    (percent * step_count + 50) / 100
+}
+
+/// The segments of each digit, from left to right, to draw [percent] (0 to 100) as per the
+/// [style]. [thresholds] are percents which are marked on the scale.
+pub fn bargraph_segments(
+    percent: u8,
+    style: BargraphStyle,
+    thresholds: &[u8],
+    display_size: u8,
+) -> Vec<u8> {
+    let mut segments = vec![0_u8; display_size as usize];
+
+    match style {
+        BargraphStyle::Bar => {
+            let step_count = u16::from(display_size) * BAR_STEPS_PER_DIGIT;
+            let lit_step_count = lit_steps(percent, step_count);
+
+            for (pos, digit) in segments.iter_mut().enumerate() {
+                let first_step = pos as u16 * BAR_STEPS_PER_DIGIT;
+
+                if lit_step_count > first_step {
+                    *digit |= SegmentBits::SegF as u8 | SegmentBits::SegE as u8;
+                }
+                if lit_step_count > first_step + 1 {
+                    *digit |= SegmentBits::SegB as u8 | SegmentBits::SegC as u8;
+                }
+            }
+
+            for threshold in thresholds {
+                // the last step of the bar which lights up when the threshold is reached
+                let step = lit_steps(*threshold, step_count).saturating_sub(1);
+                let pos = (step / BAR_STEPS_PER_DIGIT) as usize;
+
+                if let Some(digit) = segments.get_mut(pos) {
+                    *digit |= SegmentBits::SegA as u8;
+                }
+            }
+        }
+
+        BargraphStyle::Level => {
+            let lit_step_count = lit_steps(percent, LEVEL_STEPS.len() as u16) as usize;
+            let level: u8 = LEVEL_STEPS
+                .iter()
+                .take(lit_step_count)
+                .fold(0, |a, s| a | s);
+
+            segments.fill(level);
+
+            if let Some(last) = segments.last_mut() {
+                for threshold in thresholds {
+                    *last |= match threshold {
+                        0..=32 => SegmentBits::SegD as u8,
+                        33..=66 => SegmentBits::SegG as u8,
+                        _ => SegmentBits::SegA as u8,
+                    };
+                }
+            }
+        }
+    }
+
+    segments
+}
@@ -0,0 +1,158 @@
+pub mod animation;
+pub mod bargraph;
+pub mod self_test;
+pub mod text_layout;
+
+use crate::libs::segment_display::animation::{SegmentAnimation, SegmentFrame};
+use crate::libs::segment_display::bargraph::{bargraph_segments, BargraphStyle};
+use crate::libs::segment_display::self_test::SegmentDisplaySelfTestReport;
+use crate::libs::tm1637::mappings::{Brightness, DisplayState};
+use serde::{Deserialize, Serialize};
+use strum_macros::EnumString;
+
+/// The segment display controllers supported by the display thread.
+#[derive(Debug, Clone, Copy, PartialEq, EnumString)]
+pub enum SegmentDisplayKind {
+    #[strum(serialize = "tm1637")]
+    Tm1637,
+
+    #[strum(serialize = "tm1638")]
+    Tm1638,
+
+    #[strum(serialize = "max7219")]
+    Max7219,
+}
+
+/// How a secondary TM1637 display is wired next to the main display.
+#[derive(Debug, Clone, Copy, PartialEq, EnumString)]
+pub enum SecondarySegmentDisplayWiring {
+    /// There is no secondary display.
+    #[strum(serialize = "none")]
+    Disabled,
+
+    /// The secondary display shares the CLK line of the main display and has its own DIO line.
+    #[strum(serialize = "shared_clk")]
+    SharedClk,
+
+    /// The secondary display has its own CLK and DIO lines.
+    #[strum(serialize = "independent")]
+    Independent,
+}
+
+/// The outcome of the latest self test of the segment displays; reported to Sirius Proxima.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[repr(u8)]
+pub enum SegmentDisplaySelfTestResult {
+    #[serde(rename = "not_run")]
+    NotRun = 0,
+
+    #[serde(rename = "passed")]
+    Passed = 1,
+
+    /// At least one step wasn't acknowledged by a display.
+    #[serde(rename = "failed")]
+    Failed = 2,
+
+    /// The controller has no self test, eg: the MAX7219.
+    #[serde(rename = "unsupported")]
+    Unsupported = 3,
+}
+
+impl SegmentDisplaySelfTestResult {
+    pub const fn from_u8(result: u8) -> Self {
+        match result {
+            1 => SegmentDisplaySelfTestResult::Passed,
+            2 => SegmentDisplaySelfTestResult::Failed,
+            3 => SegmentDisplaySelfTestResult::Unsupported,
+            _ => SegmentDisplaySelfTestResult::NotRun,
+        }
+    }
+
+    pub fn of(report: Option<&SegmentDisplaySelfTestReport>) -> Self {
+        match report {
+            None => SegmentDisplaySelfTestResult::Unsupported,
+            Some(r) if r.is_passed() => SegmentDisplaySelfTestResult::Passed,
+            Some(_) => SegmentDisplaySelfTestResult::Failed,
+        }
+    }
+}
+
+/// How a text which doesn't fit on the display is scrolled.
+#[derive(Debug, Clone, Copy)]
+pub struct BannerAutoScrollConfig {
+    pub(crate) scroll_min_char_count: u8,
+    pub(crate) delay_ms: u16,
+    pub(crate) min_char_count_to_be_displayed: u8,
+}
+
+/// What the display thread needs from a segment display driver.
+/// The segments are laid out as in [`crate::libs::tm1637::mappings::SegmentBits`]; the drivers
+/// translate them if their controller orders the segments differently.
+pub trait SegmentDisplay {
+    type Error: std::fmt::Display;
+
+    /// The number of digits.
+    fn display_size(&self) -> u8;
+
+    /// Returns false if the controller isn't responding. Controllers without any feedback
+    /// on the bus are always healthy.
+    fn is_healthy(&self) -> bool {
+        true
+    }
+
+    /// Prints a text on the display, padded or cut to the display size.
+    /// [show_colon] lights the colon (or the dot) after the second digit.
+    fn print_text(&mut self, text: &str, show_colon: bool) -> Result<(), Self::Error>;
+
+    /// Prints a frame which has been laid out by [`SegmentDisplay::animation_frames`].
+    fn print_frame(&mut self, frame: &SegmentFrame) -> Result<(), Self::Error>;
+
+    /// Writes the raw segments of each digit, from left to right.
+    fn print_digit_segments(&mut self, segments: &[u8]) -> Result<(), Self::Error>;
+
+    /// Draws [percent] (0 to 100) as a bargraph across the digits.
+    /// [thresholds] are percents which are marked on the scale (eg: the pump cut-off level).
+    fn print_bargraph(
+        &mut self,
+        percent: u8,
+        style: BargraphStyle,
+        thresholds: &[u8],
+    ) -> Result<(), Self::Error> {
+        let segments = bargraph_segments(percent, style, thresholds, self.display_size());
+
+        self.print_digit_segments(&segments)
+    }
+
+    /// Not committed until [`SegmentDisplay::write_display_state`] or a write operation.
+    fn set_brightness(&mut self, brightness: Brightness);
+
+    /// Not committed until [`SegmentDisplay::write_display_state`] or a write operation.
+    fn set_display_state(&mut self, ds: DisplayState);
+
+    /// Sends the brightness and the on/off state to the controller.
+    fn write_display_state(&mut self) -> Result<(), Self::Error>;
+
+    fn clear(&mut self) -> Result<(), Self::Error>;
+
+    /// Makes the next write send the whole display again, for the drivers which only send
+    /// the changes.
+    fn invalidate_framebuffer(&mut self) {}
+
+    /// The bit delay of the bit banged controllers, which may have been slowed down after
+    /// bus errors. [None] for the other controllers.
+    fn bit_delay_us(&self) -> Option<u16> {
+        None
+    }
+
+    /// Runs the self test of the controller, holding each step for [step_ms].
+    /// [None] if the controller has no self test.
+    fn run_self_test(&mut self, _step_ms: u16) -> Option<SegmentDisplaySelfTestReport> {
+        None
+    }
+
+    /// Breaks the [animation] down into frames of the display size.
+    fn animation_frames(
+        &self,
+        animation: &SegmentAnimation,
+    ) -> Result<Vec<SegmentFrame>, Self::Error>;
+}
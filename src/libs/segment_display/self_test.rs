@@ -0,0 +1,39 @@
+/// A step of the self test of a segment display.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SegmentDisplaySelfTestStep {
+    /// Every segment, the dots and the colon are lit.
+    AllSegments,
+
+    /// A single segment is lit; `segment` is the bit of [`SegmentBits`], 0 (A) to 7 (DP).
+    ///
+    /// [`SegmentBits`]: crate::libs::tm1637::mappings::SegmentBits
+    SegmentWalk { digit: u8, segment: u8 },
+
+    /// Every segment is lit with the brightness `level`.
+    Brightness { level: u8 },
+
+    /// The display is cleared and its brightness is restored.
+    Clear,
+}
+
+/// A step which the controller didn't acknowledge.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SegmentDisplaySelfTestFailure {
+    pub step: SegmentDisplaySelfTestStep,
+
+    /// The byte which was not acknowledged; [None] if it is not known, eg: the GPIO failed.
+    pub byte: Option<u8>,
+}
+
+/// The outcome of each step of the self test, whatever the controller.
+#[derive(Debug, Clone, Default)]
+pub struct SegmentDisplaySelfTestReport {
+    pub steps_count: u16,
+    pub failures: Vec<SegmentDisplaySelfTestFailure>,
+}
+
+impl SegmentDisplaySelfTestReport {
+    pub fn is_passed(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
@@ -0,0 +1,211 @@
+use crate::libs::segment_display::animation::{SegmentAnimation, SegmentFrame};
+use crate::libs::segment_display::bargraph::bargraph_segments;
+use crate::libs::segment_display::BannerAutoScrollConfig;
+use crate::libs::tm1637::glyphs::GlyphRegistry;
+use crate::libs::tm1637::mappings::SpecialCharBits;
+use crate::libs::tm1637::TextAlignment;
+
+/// Breaks texts and animations down into screens of the display size.
+/// Shared by the segment display drivers; it doesn't talk to the hardware.
+///
+/// Everything is measured in display cells (digits) rather than in bytes or characters:
+/// a wide character (eg: `M`) takes two cells and a `.` is merged into the cell in front of it.
+#[derive(Debug, Clone, Copy)]
+pub struct TextLayout<'a> {
+    pub display_size: u8,
+    pub text_alignment: TextAlignment,
+    pub glyphs: &'a GlyphRegistry,
+}
+
+impl<'a> TextLayout<'a> {
+    pub const fn new(
+        display_size: u8,
+        text_alignment: TextAlignment,
+        glyphs: &'a GlyphRegistry,
+    ) -> Self {
+        Self {
+            display_size,
+            text_alignment,
+            glyphs,
+        }
+    }
+
+    /// The segments of each cell needed to show the [text], from left to right.
+    /// A `.` lights the dot of the cell in front of it, unless that dot is already lit.
+    pub fn cells(&self, text: &str) -> Vec<u8> {
+        let dot = SpecialCharBits::ColonOrDot as u8;
+        let mut cells: Vec<u8> = vec![];
+
+        for chr in text.chars() {
+            if chr == '.' && self.glyphs.get(chr).is_none() {
+                if let Some(last) = cells.last_mut() {
+                    if *last & dot == 0 {
+                        *last |= dot;
+
+                        continue;
+                    }
+                }
+            }
+
+            cells.extend(self.glyphs.segments(chr));
+        }
+
+        cells
+    }
+
+    /// Pads the [cells] with blank cells to the display size as per the [alignment].
+    /// Longer inputs are returned as they are.
+    pub fn pad(&self, cells: &[u8], alignment: TextAlignment) -> Vec<u8> {
+        let width = self.display_size as usize;
+        let blank_count = width.saturating_sub(cells.len());
+        let blank = SpecialCharBits::Space as u8;
+
+        let mut output = Vec::with_capacity(width.max(cells.len()));
+        if alignment == TextAlignment::Right {
+            output.resize(blank_count, blank);
+        }
+        output.extend_from_slice(cells);
+        output.resize(output.len().max(width), blank);
+
+        output
+    }
+
+    /// The cells of the [text] padded to the display size as per the text alignment.
+    pub fn padded_cells(&self, text: &str) -> Vec<u8> {
+        self.pad(&self.cells(text), self.text_alignment)
+    }
+
+    /// Slides a window of the display size over the cells of the [string].
+    /// Returns [None] if [`BannerAutoScrollConfig::min_char_count_to_be_displayed`] is
+    /// greater than the display size.
+    pub fn auto_scrolling_banners(
+        &self,
+        string: &str,
+        config: &BannerAutoScrollConfig,
+    ) -> Option<Vec<Vec<u8>>> {
+        if config.min_char_count_to_be_displayed > self.display_size {
+            return None;
+        }
+
+        let cells = self.cells(string);
+
+        // if the string takes [min_char_count_to_be_displayed] cells or less then pad it and return it back
+        if cells.len() <= config.min_char_count_to_be_displayed as usize {
+            return Some(vec![self.pad(&cells, self.text_alignment)]);
+        }
+
+        let window_size = self.display_size as usize;
+        let min_cell_count = config.min_char_count_to_be_displayed as usize;
+
+        let output = (0..cells.len())
+            .map(|start| {
+                let end = cells.len().min(start + window_size);
+
+                #[allow(clippy::indexing_slicing)]
+                &cells[start..end]
+            })
+            .take_while(|window| window.len() >= min_cell_count)
+            .map(|window| self.pad(window, TextAlignment::Left))
+            .collect();
+
+        Some(output)
+    }
+
+    /// Breaks the [animation] down into frames.
+    /// Returns [None] if the auto scroll config of a [`SegmentAnimation::Scroll`] doesn't fit
+    /// on the display.
+    pub fn animation_frames(&self, animation: &SegmentAnimation) -> Option<Vec<SegmentFrame>> {
+        let blank = self.pad(&[], TextAlignment::Left);
+
+        let frames = match animation {
+            SegmentAnimation::Static {
+                text,
+                show_colon,
+                duration_ms,
+            } => vec![SegmentFrame {
+                segments: self.padded_cells(text),
+                show_colon: *show_colon,
+                duration_ms: *duration_ms,
+            }],
+
+            SegmentAnimation::Scroll {
+                text,
+                config,
+                hold_ms,
+            } => {
+                let banners = if self.cells(text).len() >= config.scroll_min_char_count as usize {
+                    self.auto_scrolling_banners(text, config)?
+                } else {
+                    vec![self.padded_cells(text)]
+                };
+
+                let last_index = banners.len().saturating_sub(1);
+                banners
+                    .into_iter()
+                    .enumerate()
+                    .map(|(index, banner)| SegmentFrame {
+                        segments: banner,
+                        show_colon: false,
+                        duration_ms: if index == last_index {
+                            *hold_ms
+                        } else {
+                            config.delay_ms
+                        },
+                    })
+                    .collect()
+            }
+
+            SegmentAnimation::Marquee { text, step_ms } => {
+                let cell_bucket: Vec<u8> = [blank.clone(), self.cells(text), blank].concat();
+
+                cell_bucket
+                    .windows(self.display_size as usize)
+                    .map(|window| SegmentFrame {
+                        segments: window.to_vec(),
+                        show_colon: false,
+                        duration_ms: *step_ms,
+                    })
+                    .collect()
+            }
+
+            SegmentAnimation::Bargraph {
+                percent,
+                style,
+                thresholds,
+                duration_ms,
+            } => vec![SegmentFrame {
+                segments: bargraph_segments(*percent, *style, thresholds, self.display_size),
+                show_colon: false,
+                duration_ms: *duration_ms,
+            }],
+
+            SegmentAnimation::Blink {
+                text,
+                on_ms,
+                off_ms,
+                count,
+            } => {
+                let padded_cells = self.padded_cells(text);
+
+                (0..*count)
+                    .flat_map(|_| {
+                        [
+                            SegmentFrame {
+                                segments: padded_cells.clone(),
+                                show_colon: false,
+                                duration_ms: *on_ms,
+                            },
+                            SegmentFrame {
+                                segments: blank.clone(),
+                                show_colon: false,
+                                duration_ms: *off_ms,
+                            },
+                        ]
+                    })
+                    .collect()
+            }
+        };
+
+        Some(frames)
+    }
+}
@@ -2,14 +2,23 @@ use thiserror::Error;
 
 #[derive(Error, Debug)]
 pub enum TmError<E> {
-    #[error("an ack error occured")]
-    Ack,
+    #[error("{0:?} an ack error occured while sending the byte {1:#010b}")]
+    Ack(String, u8),
 
     #[error("an IO error occured")]
     IO(E),
 
-    #[error("[0:?] an auto scroll error occured: {1:?}")]
+    #[error("{0:?} an auto scroll error occured: {1:?}")]
     AutoScroll(String, String),
+
+    #[error("{0:?} an invalid display size: {1:?}")]
+    DisplaySize(String, String),
+
+    #[error("{0:?} an invalid digit order: {1:?}")]
+    DigitOrder(String, String),
+
+    #[error("{0:?} an invalid glyph: {1:?}")]
+    Glyph(String, String),
 }
 
 impl<E> From<E> for TmError<E> {
@@ -0,0 +1,52 @@
+use crate::libs::tm1637::mappings::{char_to_bits, transliterate, wide_char_to_bits};
+use std::collections::HashMap;
+
+/// Raw segment patterns registered by the application at runtime.
+/// A registered glyph takes precedence over the built-in font and may span several digits.
+#[derive(Debug, Clone, Default)]
+pub struct GlyphRegistry {
+    glyphs: HashMap<char, Vec<u8>>,
+}
+
+impl GlyphRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds or replaces the glyph of [chr]. Returns the glyph which has been replaced.
+    pub fn register(&mut self, chr: char, segments: &[u8]) -> Option<Vec<u8>> {
+        self.glyphs.insert(chr, segments.to_vec())
+    }
+
+    /// Removes the glyph of [chr], so that the built-in font is used again.
+    pub fn unregister(&mut self, chr: char) -> Option<Vec<u8>> {
+        self.glyphs.remove(&chr)
+    }
+
+    pub fn get(&self, chr: char) -> Option<&[u8]> {
+        self.glyphs.get(&chr).map(Vec::as_slice)
+    }
+
+    /// The segments of every digit needed to show [chr], from left to right.
+    /// Non ASCII characters without a registered glyph are shown as their nearest ASCII letter.
+    pub fn segments(&self, chr: char) -> Vec<u8> {
+        if let Some(g) = self.get(chr) {
+            return g.to_vec();
+        }
+
+        let chr = transliterate(chr);
+        match wide_char_to_bits(chr) {
+            Some(w) => w.to_vec(),
+            None => vec![char_to_bits(chr)],
+        }
+    }
+
+    /// The segments of [chr] squeezed into a single digit.
+    /// Only the first digit of a registered multi digit glyph is used.
+    pub fn single_digit_segments(&self, chr: char) -> u8 {
+        match self.get(chr).and_then(<[u8]>::first) {
+            Some(s) => *s,
+            None => char_to_bits(transliterate(chr)),
+        }
+    }
+}
@@ -0,0 +1,96 @@
+use crate::libs::tm1637::mappings::KeyScanBits;
+
+/// Number of identical consecutive key scans required before a key state is accepted.
+pub const DEFAULT_KEY_DEBOUNCE_SAMPLES: u8 = 3;
+
+/// A debounced key transition reported by the key scanner.
+/// The key code is in the range of 1 to 16 (K1/SG1 = 1, K1/SG8 = 8, K2/SG1 = 9, K2/SG8 = 16).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum KeyEvent {
+    Pressed(u8),
+    Released(u8),
+}
+
+/// Decodes the raw key scan byte of the TM1637 into a key code.
+/// Returns [None] if no key is pressed or if the byte is not a valid key scan value.
+pub const fn decode_key_code(raw: u8) -> Option<u8> {
+    if raw == KeyScanBits::NoKeyPressed as u8 {
+        return None;
+    }
+
+    // the segment line is transmitted inverted and in reverse bit order in bits 5-7,
+    // eg: K1/SG1 = 0b1110_1111, K1/SG2 = 0b0110_1111, K2/SG1 = 0b1111_0111
+    let inverted_segment_bits = !raw & KeyScanBits::SegmentLine as u8;
+    let segment_line = ((inverted_segment_bits >> 7) & 0b001)
+        | ((inverted_segment_bits >> 5) & 0b010)
+        | ((inverted_segment_bits >> 3) & 0b100);
+    let is_k1 = raw & KeyScanBits::KeyLineK1 as u8 == 0;
+    let is_k2 = raw & KeyScanBits::KeyLineK2 as u8 == 0;
+
+    match (is_k1, is_k2) {
+        (true, false) => Some(segment_line + 1),
+        (false, true) => Some(segment_line + 9),
+        _ => None,
+    }
+}
+
+/// Debounces the key codes sampled from the TM1637 and turns them into press/release events.
+pub struct KeyDebouncer {
+    samples_required: u8,
+    candidate: Option<u8>,
+    candidate_count: u8,
+    stable: Option<u8>,
+}
+
+impl KeyDebouncer {
+    pub const fn new(samples_required: u8) -> Self {
+        Self {
+            samples_required,
+            candidate: None,
+            candidate_count: 0,
+            stable: None,
+        }
+    }
+
+    /// Sets the number of identical consecutive samples required to accept a key state.
+    /// A value of `0` or `1` disables the debouncing.
+    pub fn set_samples_required(&mut self, samples_required: u8) {
+        self.samples_required = samples_required;
+    }
+
+    /// The debounced key which is currently held down.
+    pub const fn stable_key(&self) -> Option<u8> {
+        self.stable
+    }
+
+    /// Feeds a new key scan sample into the debouncer.
+    /// Returns an event if the debounced key state has changed.
+    /// When the held key changes from one key to another, the release of the old key is
+    /// reported first and the press of the new key is reported by the following sample.
+    pub fn update(&mut self, sample: Option<u8>) -> Option<KeyEvent> {
+        if sample == self.candidate {
+            self.candidate_count = self.candidate_count.saturating_add(1);
+        } else {
+            self.candidate = sample;
+            self.candidate_count = 1;
+        }
+
+        if self.candidate_count < self.samples_required || self.candidate == self.stable {
+            return None;
+        }
+
+        match (self.stable, self.candidate) {
+            (Some(old), _) => {
+                self.stable = None;
+
+                Some(KeyEvent::Released(old))
+            }
+            (None, Some(new)) => {
+                self.stable = Some(new);
+
+                Some(KeyEvent::Pressed(new))
+            }
+            (None, None) => None,
+        }
+    }
+}
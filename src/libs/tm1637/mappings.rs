@@ -65,7 +65,14 @@ pub enum UpperCharBits {
         | SegmentBits::SegC as u8
         | SegmentBits::SegD as u8
         | SegmentBits::SegE as u8,
+    CharK = SegmentBits::SegA as u8
+        | SegmentBits::SegC as u8
+        | SegmentBits::SegE as u8
+        | SegmentBits::SegF as u8
+        | SegmentBits::SegG as u8,
     CharL = SegmentBits::SegD as u8 | SegmentBits::SegE as u8 | SegmentBits::SegF as u8,
+    /// Single digit fallback, see [`WideCharBits::CHAR_M`].
+    CharM = SegmentBits::SegA as u8 | SegmentBits::SegC as u8 | SegmentBits::SegE as u8,
     CharN = SegmentBits::SegA as u8
         | SegmentBits::SegB as u8
         | SegmentBits::SegC as u8
@@ -93,6 +100,8 @@ pub enum UpperCharBits {
         | SegmentBits::SegD as u8
         | SegmentBits::SegE as u8
         | SegmentBits::SegF as u8,
+    /// Single digit fallback, see [`WideCharBits::CHAR_W`].
+    CharW = SegmentBits::SegB as u8 | SegmentBits::SegD as u8 | SegmentBits::SegF as u8,
 }
 
 /// Maps a character to its closest possible representation on a 7-segment display.
@@ -128,6 +137,8 @@ pub enum LowerCharBits {
         | SegmentBits::SegG as u8,
     CharI = SegmentBits::SegC as u8,
     CharL = SegmentBits::SegD as u8 | SegmentBits::SegE as u8,
+    /// Single digit fallback, see [`WideCharBits::CHAR_M_LOWER`].
+    CharM = SegmentBits::SegC as u8 | SegmentBits::SegE as u8,
     CharN = SegmentBits::SegC as u8 | SegmentBits::SegE as u8 | SegmentBits::SegG as u8,
     CharO = SegmentBits::SegC as u8
         | SegmentBits::SegD as u8
@@ -172,6 +183,215 @@ pub enum SpecialCharBits {
         | SegmentBits::SegB as u8
         | SegmentBits::SegC as u8
         | SegmentBits::SegD as u8,
+    Apostrophe = SegmentBits::SegB as u8,
+    DoubleQuote = SegmentBits::SegB as u8 | SegmentBits::SegF as u8,
+    Degree = SegmentBits::SegA as u8
+        | SegmentBits::SegB as u8
+        | SegmentBits::SegF as u8
+        | SegmentBits::SegG as u8,
+    Caret = SegmentBits::SegA as u8 | SegmentBits::SegB as u8 | SegmentBits::SegF as u8,
+    Overline = SegmentBits::SegA as u8,
+    Pipe = SegmentBits::SegE as u8 | SegmentBits::SegF as u8,
+    Slash = SegmentBits::SegB as u8 | SegmentBits::SegE as u8 | SegmentBits::SegG as u8,
+    Backslash = SegmentBits::SegC as u8 | SegmentBits::SegF as u8 | SegmentBits::SegG as u8,
+    ExclamationMark = SegmentBits::SegB as u8 | SegmentBits::SegColonOrDot as u8,
+}
+
+/// Turns the segments of a digit upside down (A<->D, B<->E, C<->F).
+/// The segment G and the dot keep their bit.
+pub const fn rotate_segments(segments: u8) -> u8 {
+    let swaps = [
+        (SegmentBits::SegA as u8, SegmentBits::SegD as u8),
+        (SegmentBits::SegB as u8, SegmentBits::SegE as u8),
+        (SegmentBits::SegC as u8, SegmentBits::SegF as u8),
+    ];
+
+    let mut output = segments & (SegmentBits::SegG as u8 | SegmentBits::SegColonOrDot as u8);
+    let mut i = 0;
+    while i < swaps.len() {
+        #[allow(clippy::indexing_slicing)]
+        let (upper, lower) = swaps[i];
+        if segments & upper != 0 {
+            output |= lower;
+        }
+        if segments & lower != 0 {
+            output |= upper;
+        }
+        i += 1;
+    }
+
+    output
+}
+
+/// Characters which are only recognizable when they are spread over two digits.
+/// The first byte goes into the left digit.
+#[derive(Debug)]
+pub struct WideCharBits;
+
+impl WideCharBits {
+    /// Outer verticals with the top bar; the inner verticals only reach the middle.
+    pub const CHAR_M: [u8; 2] = [
+        SegmentBits::SegA as u8
+            | SegmentBits::SegB as u8
+            | SegmentBits::SegE as u8
+            | SegmentBits::SegF as u8,
+        SegmentBits::SegA as u8
+            | SegmentBits::SegB as u8
+            | SegmentBits::SegC as u8
+            | SegmentBits::SegF as u8,
+    ];
+    /// Outer verticals with the bottom bar; the inner verticals only reach the middle.
+    pub const CHAR_W: [u8; 2] = [
+        SegmentBits::SegC as u8
+            | SegmentBits::SegD as u8
+            | SegmentBits::SegE as u8
+            | SegmentBits::SegF as u8,
+        SegmentBits::SegB as u8
+            | SegmentBits::SegC as u8
+            | SegmentBits::SegD as u8
+            | SegmentBits::SegE as u8,
+    ];
+    pub const CHAR_M_LOWER: [u8; 2] = [
+        SegmentBits::SegC as u8 | SegmentBits::SegE as u8 | SegmentBits::SegG as u8,
+        SegmentBits::SegC as u8 | SegmentBits::SegG as u8,
+    ];
+    pub const CHAR_W_LOWER: [u8; 2] = [
+        SegmentBits::SegC as u8 | SegmentBits::SegD as u8 | SegmentBits::SegE as u8,
+        SegmentBits::SegC as u8 | SegmentBits::SegD as u8,
+    ];
+}
+
+/// Maps an ASCII character to its closest possible single digit representation.
+/// Letters which can't be told apart on 7 segments share a pattern (eg: `X` and `H`, `Z` and `2`).
+/// Everything else is shown as a question mark.
+pub const fn char_to_bits(chr: char) -> u8 {
+    match chr {
+        // upper case matching
+        'A' => UpperCharBits::CharA as u8,
+        'C' => UpperCharBits::CharC as u8,
+        'E' => UpperCharBits::CharE as u8,
+        'F' | 'f' => UpperCharBits::CharF as u8,
+        'G' | 'g' => UpperCharBits::CharG as u8,
+        'H' | 'X' | 'x' => UpperCharBits::CharH as u8,
+        'I' => UpperCharBits::CharI as u8,
+        'J' | 'j' => UpperCharBits::CharJ as u8,
+        'K' | 'k' => UpperCharBits::CharK as u8,
+        'L' => UpperCharBits::CharL as u8,
+        'M' => UpperCharBits::CharM as u8,
+        'N' => UpperCharBits::CharN as u8,
+        'O' => UpperCharBits::CharO as u8,
+        'P' | 'p' => UpperCharBits::CharP as u8,
+        'R' => UpperCharBits::CharR as u8,
+        'S' | 's' => UpperCharBits::CharS as u8,
+        'U' | 'V' => UpperCharBits::CharU as u8,
+        'W' | 'w' => UpperCharBits::CharW as u8,
+
+        // lower case matching
+        'a' => LowerCharBits::CharA as u8,
+        'b' | 'B' => LowerCharBits::CharB as u8,
+        'c' => LowerCharBits::CharC as u8,
+        'd' | 'D' => LowerCharBits::CharD as u8,
+        'e' => LowerCharBits::CharE as u8,
+        'h' => LowerCharBits::CharH as u8,
+        'i' => LowerCharBits::CharI as u8,
+        'l' => LowerCharBits::CharL as u8,
+        'm' => LowerCharBits::CharM as u8,
+        'n' => LowerCharBits::CharN as u8,
+        'o' => LowerCharBits::CharO as u8,
+        'q' | 'Q' => LowerCharBits::CharQ as u8,
+        'r' => LowerCharBits::CharR as u8,
+        't' | 'T' => LowerCharBits::CharT as u8,
+        'u' | 'v' => LowerCharBits::CharU as u8,
+        'y' | 'Y' => LowerCharBits::CharY as u8,
+
+        // number matching
+        '0' => NumberCharBits::Zero as u8,
+        '1' => NumberCharBits::One as u8,
+        '2' | 'Z' | 'z' => NumberCharBits::Two as u8,
+        '3' => NumberCharBits::Three as u8,
+        '4' => NumberCharBits::Four as u8,
+        '5' => NumberCharBits::Five as u8,
+        '6' => NumberCharBits::Six as u8,
+        '7' => NumberCharBits::Seven as u8,
+        '8' => NumberCharBits::Eight as u8,
+        '9' => NumberCharBits::Nine as u8,
+
+        // special character matching
+        ' ' => SpecialCharBits::Space as u8,
+        '-' => SpecialCharBits::Minus as u8,
+        '_' => SpecialCharBits::Underscore as u8,
+        '=' => SpecialCharBits::Equals as u8,
+        '[' | '(' | '{' | '<' => SpecialCharBits::BracketLeft as u8,
+        ']' | ')' | '}' | '>' => SpecialCharBits::BracketRight as u8,
+        '.' | ',' | ':' => SpecialCharBits::ColonOrDot as u8,
+        '\'' | '`' => SpecialCharBits::Apostrophe as u8,
+        '"' => SpecialCharBits::DoubleQuote as u8,
+        '*' | '°' => SpecialCharBits::Degree as u8,
+        '^' => SpecialCharBits::Caret as u8,
+        '~' => SpecialCharBits::Overline as u8,
+        '|' => SpecialCharBits::Pipe as u8,
+        '/' => SpecialCharBits::Slash as u8,
+        '\\' => SpecialCharBits::Backslash as u8,
+        '!' => SpecialCharBits::ExclamationMark as u8,
+
+        // '?' and default everything else to a question mark (unknown char)
+        _ => SpecialCharBits::QuestionMark as u8,
+    }
+}
+
+/// Maps a non ASCII character to the ASCII character which looks the closest on 7 segments,
+/// eg: `é` to `e`, `Ł` to `L`, `“` to `"`. Characters without a counterpart are returned as is.
+pub const fn transliterate(chr: char) -> char {
+    match chr {
+        'À' | 'Á' | 'Â' | 'Ã' | 'Ä' | 'Å' | 'Ą' | 'Ā' | 'Ă' => 'A',
+        'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' | 'ą' | 'ā' | 'ă' => 'a',
+        'Ç' | 'Ć' | 'Č' => 'C',
+        'ç' | 'ć' | 'č' => 'c',
+        'Ď' | 'Đ' | 'Ð' => 'D',
+        'ď' | 'đ' | 'ð' => 'd',
+        'È' | 'É' | 'Ê' | 'Ë' | 'Ę' | 'Ě' | 'Ē' => 'E',
+        'è' | 'é' | 'ê' | 'ë' | 'ę' | 'ě' | 'ē' => 'e',
+        'Ğ' => 'G',
+        'ğ' => 'g',
+        'Ì' | 'Í' | 'Î' | 'Ï' | 'İ' | 'Ī' => 'I',
+        'ì' | 'í' | 'î' | 'ï' | 'ı' | 'ī' => 'i',
+        'Ł' | 'Ľ' => 'L',
+        'ł' | 'ľ' => 'l',
+        'Ñ' | 'Ń' | 'Ň' => 'N',
+        'ñ' | 'ń' | 'ň' => 'n',
+        'Ò' | 'Ó' | 'Ô' | 'Õ' | 'Ö' | 'Ø' | 'Ő' | 'Ō' => 'O',
+        'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' | 'ő' | 'ō' => 'o',
+        'Ř' => 'R',
+        'ř' => 'r',
+        'Ś' | 'Š' | 'Ş' => 'S',
+        'ś' | 'š' | 'ş' | 'ß' => 's',
+        'Ť' | 'Ţ' => 'T',
+        'ť' | 'ţ' => 't',
+        'Ù' | 'Ú' | 'Û' | 'Ü' | 'Ů' | 'Ű' | 'Ū' => 'U',
+        'ù' | 'ú' | 'û' | 'ü' | 'ů' | 'ű' | 'ū' => 'u',
+        'Ý' | 'Ÿ' => 'Y',
+        'ý' | 'ÿ' => 'y',
+        'Ź' | 'Ż' | 'Ž' => 'Z',
+        'ź' | 'ż' | 'ž' => 'z',
+        '‘' | '’' | '´' => '\'',
+        '“' | '”' | '«' | '»' => '"',
+        '–' | '—' | '−' => '-',
+        '·' | '…' => '.',
+        '\u{a0}' => ' ',
+        'º' => '°',
+        _ => chr,
+    }
+}
+
+/// Maps the characters which look better when spread over two digits, see [WideCharBits].
+pub const fn wide_char_to_bits(chr: char) -> Option<[u8; 2]> {
+    match chr {
+        'M' => Some(WideCharBits::CHAR_M),
+        'W' => Some(WideCharBits::CHAR_W),
+        'm' => Some(WideCharBits::CHAR_M_LOWER),
+        'w' => Some(WideCharBits::CHAR_W_LOWER),
+        _ => None,
+    }
 }
 
 /// The "ISA"/Commands of the TM1637. See data sheet
@@ -181,6 +401,10 @@ pub enum ISA {
     /// Start instruction
     DataCommandWriteToDisplay = 0b0100_0000, // "write data to display register"-mode
 
+    /// Read instruction
+    /// Followed by one byte clocked out of the TM1637 which holds the key scan data.
+    DataCommandReadKeys = 0b0100_0010, // "read key scan data"-mode
+
     // send this + <recv ack> + send byte 0 + <recv ack> + ... send byte 3
     /// Starts at display address zero. Each further byte that is send will go
     /// into the next display address. The micro controller does an internal auto increment
@@ -217,10 +441,27 @@ pub enum ISA {
      ***/
 }
 
+/// Masks for the key scan byte returned after [`ISA::DataCommandReadKeys`].
+/// The byte is clocked out LSB first. Only one key can be reported at a time.
+/// Bits 0-2 are always high.
+#[repr(u8)]
+pub enum KeyScanBits {
+    /// Value read back when no key is pressed.
+    NoKeyPressed = 0b1111_1111,
+    /// Bits 5-7 carry the SG1 to SG8 line of the pressed key, inverted and in reverse bit
+    /// order (SG1 = 0b111, SG2 = 0b011, SG3 = 0b101, ..., SG8 = 0b000).
+    SegmentLine = 0b1110_0000,
+    /// Bit 4 is pulled low when the pressed key sits on the K1 line.
+    KeyLineK1 = 0b0001_0000,
+    /// Bit 3 is pulled low when the pressed key sits on the K2 line.
+    KeyLineK2 = 0b0000_1000,
+}
+
 /// The level of brightness.
 /// The TM1637 "DisplayControl"-command transports the brightness information
 /// in bits 0 to 2.
 #[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum Brightness {
     // useless assignment because it is default but it shows clearly
     // that 3 bits are used
@@ -236,6 +477,22 @@ pub enum Brightness {
     L7 = 0b111,
 }
 
+impl Brightness {
+    /// The brightness of the [level] (0 to 7); a higher level is taken as [`Brightness::L7`].
+    pub const fn from_level(level: u8) -> Self {
+        match level {
+            0 => Brightness::L0,
+            1 => Brightness::L1,
+            2 => Brightness::L2,
+            3 => Brightness::L3,
+            4 => Brightness::L4,
+            5 => Brightness::L5,
+            6 => Brightness::L6,
+            _ => Brightness::L7,
+        }
+    }
+}
+
 /// Whether the display is on or off.
 /// The TM1637 "DisplayControl"-command transports the display on/off information
 /// in the third bit (2^3) of the command.
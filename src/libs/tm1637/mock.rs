@@ -0,0 +1,423 @@
+//! Host-side stand-in for a TM1637 module.
+//!
+//! [MockTm1637Bus] hands out a CLK/DIO pin pair and a delay provider which can be passed to
+//! [`Tm1637::new`](super::Tm1637::new) on Linux. The bus records every level change of the two
+//! lines, answers the acknowledge and key scan clocks like the chip does, and decodes the
+//! recorded transactions back into commands and segment bytes.
+
+use crate::libs::tm1637::mappings::{KeyScanBits, ISA};
+use crate::libs::tm1637::DISPLAY_REGISTERS_COUNT;
+use embedded_hal::blocking::delay::{DelayMs, DelayUs};
+use embedded_hal::digital::v2::{InputPin, OutputPin};
+use std::cell::RefCell;
+use std::convert::Infallible;
+use std::rc::Rc;
+
+/// Level of the CLK and DIO lines after a pin change. `true` is high.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BusLevel {
+    pub clk: bool,
+    pub dio: bool,
+}
+
+/// A decoded bus transaction (start condition to stop condition).
+#[derive(Debug, Clone, PartialEq)]
+pub enum DecodedCommand {
+    /// "write data to display register"-mode
+    DataWrite,
+
+    /// "read key scan data"-mode; holds the key scan byte which was clocked out.
+    ReadKeys(u8),
+
+    /// Address command followed by the segment bytes written from that address onwards.
+    Segments { address: u8, segments: Vec<u8> },
+
+    /// Display control command.
+    DisplayControl { is_on: bool, brightness: u8 },
+
+    /// Anything which doesn't match the commands above.
+    Unknown(Vec<u8>),
+}
+
+#[derive(Default)]
+struct BusState {
+    master_clk: bool,
+    master_dio: bool,
+
+    is_ack_enabled: bool,
+    key_scan_data: u8,
+
+    waveform: Vec<BusLevel>,
+    transactions: Vec<Vec<u8>>,
+
+    in_transaction: bool,
+    current_bytes: Vec<u8>,
+    current_byte: u8,
+    bit_count: u8,
+
+    /// the chip pulls DIO low from the falling edge of the 8th clock until the 9th clock ends
+    is_acking: bool,
+    is_ack_clock: bool,
+
+    /// key scan data is clocked out after the read command has been acknowledged
+    is_read_pending: bool,
+    is_reading: bool,
+    read_bit: u8,
+    is_read_clock_high: bool,
+
+    elapsed_us: u64,
+}
+
+impl BusState {
+    /// DIO level driven by the simulated chip; `false` means pulled low.
+    fn chip_dio(&self) -> bool {
+        if self.is_acking {
+            return false;
+        }
+
+        if self.is_reading && self.read_bit < 8 {
+            return (self.key_scan_data >> self.read_bit) & 0x01 == 0x01;
+        }
+
+        true
+    }
+
+    /// Open drain: either side can pull the line low.
+    fn bus_dio(&self) -> bool {
+        self.master_dio && self.chip_dio()
+    }
+
+    fn record(&mut self) {
+        let level = BusLevel {
+            clk: self.master_clk,
+            dio: self.bus_dio(),
+        };
+
+        self.waveform.push(level);
+    }
+
+    fn set_dio(&mut self, level: bool) {
+        let old_dio = self.bus_dio();
+        self.master_dio = level;
+        let new_dio = self.bus_dio();
+
+        if self.master_clk && old_dio && !new_dio {
+            // start condition
+            self.in_transaction = true;
+            self.current_bytes = vec![];
+            self.current_byte = 0;
+            self.bit_count = 0;
+        } else if self.master_clk && !old_dio && new_dio && self.in_transaction {
+            // stop condition
+            self.in_transaction = false;
+            self.is_reading = false;
+            self.is_read_pending = false;
+            self.transactions
+                .push(std::mem::take(&mut self.current_bytes));
+        }
+
+        self.record();
+    }
+
+    fn set_clk(&mut self, level: bool) {
+        let is_rising = !self.master_clk && level;
+        let is_falling = self.master_clk && !level;
+        self.master_clk = level;
+
+        if is_rising {
+            self.on_clk_rising();
+        } else if is_falling {
+            self.on_clk_falling();
+        }
+
+        self.record();
+    }
+
+    fn on_clk_rising(&mut self) {
+        if !self.in_transaction {
+            return;
+        }
+
+        if self.is_reading {
+            self.is_read_clock_high = true;
+
+            return;
+        }
+
+        if self.bit_count < 8 {
+            if self.master_dio {
+                self.current_byte |= 1 << self.bit_count;
+            }
+            self.bit_count += 1;
+
+            return;
+        }
+
+        // 9th clock: the acknowledge
+        self.is_ack_clock = true;
+        self.current_bytes.push(self.current_byte);
+
+        if self.current_byte == ISA::DataCommandReadKeys as u8 {
+            self.is_read_pending = true;
+        }
+
+        self.current_byte = 0;
+        self.bit_count = 0;
+    }
+
+    fn on_clk_falling(&mut self) {
+        if !self.in_transaction {
+            return;
+        }
+
+        if self.is_reading {
+            if self.is_read_clock_high {
+                self.is_read_clock_high = false;
+                self.read_bit += 1;
+
+                // the key scan byte and its acknowledge clock are done
+                if self.read_bit > 8 {
+                    self.is_reading = false;
+                    self.current_bytes.push(self.key_scan_data);
+                }
+            }
+
+            return;
+        }
+
+        if self.is_ack_clock {
+            self.is_ack_clock = false;
+            self.is_acking = false;
+
+            if self.is_read_pending {
+                self.is_read_pending = false;
+                self.is_reading = true;
+                self.read_bit = 0;
+            }
+
+            return;
+        }
+
+        if self.bit_count == 8 && self.is_ack_enabled {
+            self.is_acking = true;
+        }
+    }
+}
+
+/// The simulated TM1637 and its two lines.
+#[derive(Clone)]
+pub struct MockTm1637Bus {
+    state: Rc<RefCell<BusState>>,
+}
+
+impl Default for MockTm1637Bus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MockTm1637Bus {
+    pub fn new() -> Self {
+        let state = BusState {
+            master_clk: true,
+            master_dio: true,
+            is_ack_enabled: true,
+            key_scan_data: KeyScanBits::NoKeyPressed as u8,
+            ..BusState::default()
+        };
+
+        Self {
+            state: Rc::new(RefCell::new(state)),
+        }
+    }
+
+    pub fn clk(&self) -> MockClkPin {
+        MockClkPin {
+            state: Rc::clone(&self.state),
+        }
+    }
+
+    pub fn dio(&self) -> MockDioPin {
+        MockDioPin {
+            state: Rc::clone(&self.state),
+        }
+    }
+
+    pub fn delay(&self) -> MockDelay {
+        MockDelay {
+            state: Rc::clone(&self.state),
+        }
+    }
+
+    /// If disabled, the simulated chip never acknowledges a byte (an unplugged display).
+    pub fn set_ack_enabled(&self, is_ack_enabled: bool) {
+        self.state.borrow_mut().is_ack_enabled = is_ack_enabled;
+    }
+
+    /// Sets the raw key scan byte clocked out on the next key read.
+    pub fn set_key_scan_data(&self, key_scan_data: u8) {
+        self.state.borrow_mut().key_scan_data = key_scan_data;
+    }
+
+    /// Every level change of the CLK and DIO lines in the order they happened.
+    pub fn waveform(&self) -> Vec<BusLevel> {
+        self.state.borrow().waveform.clone()
+    }
+
+    /// Total time spent in the delay provider, in µs.
+    pub fn elapsed_us(&self) -> u64 {
+        self.state.borrow().elapsed_us
+    }
+
+    /// The raw bytes of every completed transaction.
+    pub fn transactions(&self) -> Vec<Vec<u8>> {
+        self.state.borrow().transactions.clone()
+    }
+
+    /// Decodes every completed transaction into a command.
+    pub fn decode(&self) -> Vec<DecodedCommand> {
+        self.transactions()
+            .iter()
+            .map(|bytes| decode_transaction(bytes))
+            .collect()
+    }
+
+    /// Replays the decoded segment writes into the 6 display registers.
+    pub fn registers(&self) -> [u8; DISPLAY_REGISTERS_COUNT] {
+        let mut registers = [0_u8; DISPLAY_REGISTERS_COUNT];
+
+        for command in self.decode() {
+            if let DecodedCommand::Segments { address, segments } = command {
+                for (offset, segment) in segments.iter().enumerate() {
+                    if let Some(r) = registers.get_mut(address as usize + offset) {
+                        *r = *segment;
+                    }
+                }
+            }
+        }
+
+        registers
+    }
+
+    /// The last display control command sent, as `(is_on, brightness)`.
+    pub fn display_control(&self) -> Option<(bool, u8)> {
+        self.decode().iter().rev().find_map(|command| {
+            if let DecodedCommand::DisplayControl { is_on, brightness } = command {
+                return Some((*is_on, *brightness));
+            }
+
+            None
+        })
+    }
+
+    /// Forgets the recorded waveform and transactions.
+    pub fn reset_recording(&self) {
+        let mut state = self.state.borrow_mut();
+
+        state.waveform.clear();
+        state.transactions.clear();
+        state.elapsed_us = 0;
+    }
+}
+
+fn decode_transaction(bytes: &[u8]) -> DecodedCommand {
+    let (command, data) = match bytes.split_first() {
+        None => return DecodedCommand::Unknown(vec![]),
+        Some(b) => b,
+    };
+
+    if *command == ISA::DataCommandWriteToDisplay as u8 && data.is_empty() {
+        return DecodedCommand::DataWrite;
+    }
+
+    if *command == ISA::DataCommandReadKeys as u8 {
+        if let [key_scan_data] = data {
+            return DecodedCommand::ReadKeys(*key_scan_data);
+        }
+    }
+
+    if command & 0b1100_0000 == ISA::AddressCommandD0 as u8 {
+        return DecodedCommand::Segments {
+            address: command & 0b0000_0111,
+            segments: data.to_vec(),
+        };
+    }
+
+    if command & 0b1111_0000 == ISA::DisplayControlOff as u8 && data.is_empty() {
+        return DecodedCommand::DisplayControl {
+            is_on: command & 0b0000_1000 != 0,
+            brightness: command & 0b0000_0111,
+        };
+    }
+
+    DecodedCommand::Unknown(bytes.to_vec())
+}
+
+pub struct MockClkPin {
+    state: Rc<RefCell<BusState>>,
+}
+
+impl OutputPin for MockClkPin {
+    type Error = Infallible;
+
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        self.state.borrow_mut().set_clk(false);
+
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        self.state.borrow_mut().set_clk(true);
+
+        Ok(())
+    }
+}
+
+pub struct MockDioPin {
+    state: Rc<RefCell<BusState>>,
+}
+
+impl OutputPin for MockDioPin {
+    type Error = Infallible;
+
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        self.state.borrow_mut().set_dio(false);
+
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        self.state.borrow_mut().set_dio(true);
+
+        Ok(())
+    }
+}
+
+impl InputPin for MockDioPin {
+    type Error = Infallible;
+
+    fn is_high(&self) -> Result<bool, Self::Error> {
+        Ok(self.state.borrow().bus_dio())
+    }
+
+    fn is_low(&self) -> Result<bool, Self::Error> {
+        Ok(!self.state.borrow().bus_dio())
+    }
+}
+
+/// Doesn't sleep; it only adds the requested time to [`MockTm1637Bus::elapsed_us`].
+pub struct MockDelay {
+    state: Rc<RefCell<BusState>>,
+}
+
+impl DelayUs<u16> for MockDelay {
+    fn delay_us(&mut self, us: u16) {
+        self.state.borrow_mut().elapsed_us += u64::from(us);
+    }
+}
+
+impl DelayMs<u16> for MockDelay {
+    fn delay_ms(&mut self, ms: u16) {
+        self.state.borrow_mut().elapsed_us += u64::from(ms) * 1000;
+    }
+}
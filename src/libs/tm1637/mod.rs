@@ -6,119 +6,328 @@
 // ref: https://github.com/rustrum/tmledkey-hal-drv/blob/b5e0759c41442d4e28c0ae26ad2bc393c43f814c/src/lib.rs
 
 pub mod errors;
+pub mod glyphs;
+pub mod keys;
 pub mod mappings;
+#[cfg(not(target_os = "espidf"))]
+pub mod mock;
+pub mod numbers;
+pub mod self_test;
+pub mod shared_pin;
 
 extern crate embedded_hal as hal;
 
+use crate::libs::segment_display::animation::{SegmentAnimation, SegmentFrame};
+use crate::libs::segment_display::self_test::SegmentDisplaySelfTestReport;
+use crate::libs::segment_display::text_layout::TextLayout;
+use crate::libs::segment_display::{BannerAutoScrollConfig, SegmentDisplay};
 use crate::libs::tm1637::errors::TmError;
-use crate::libs::tm1637::mappings::{
-    Brightness, DisplayState, GpioPinValue, LowerCharBits, NumberCharBits, SpecialCharBits,
-    UpperCharBits, ISA,
+use crate::libs::tm1637::glyphs::GlyphRegistry;
+use crate::libs::tm1637::keys::{
+    decode_key_code, KeyDebouncer, KeyEvent, DEFAULT_KEY_DEBOUNCE_SAMPLES,
 };
-use embedded_hal::prelude::{
-    _embedded_hal_blocking_delay_DelayMs, _embedded_hal_blocking_delay_DelayUs,
+use crate::libs::tm1637::mappings::{
+    rotate_segments, Brightness, DisplayState, GpioPinValue, SpecialCharBits, ISA,
 };
-use esp_idf_hal::delay;
+use crate::libs::tm1637::numbers::number_to_segments;
+use hal::blocking::delay::{DelayMs, DelayUs};
 use hal::digital::v2::{InputPin, OutputPin};
 
 pub const DISPLAY_REGISTERS_COUNT: usize = 6;
 
-pub struct Tm1637BannerAutoScrollConfig {
-    pub(crate) scroll_min_char_count: u8,
-    pub(crate) delay_ms: u16,
-    pub(crate) min_char_count_to_be_displayed: u8,
+/// Number of bit delays to wait for the TM1637 to acknowledge a byte.
+pub const ACK_TIMEOUT_POLL_COUNT: u8 = 10;
+
+/// Half of a clock period; fine for the short wires of the common modules.
+pub const DEFAULT_BIT_DELAY_US: u16 = 100;
+
+/// The slowest timing the bit delay is slowed down to when the bytes are not acknowledged.
+pub const DEFAULT_MAX_BIT_DELAY_US: u16 = 1600;
+
+/// The bit delay is multiplied by this factor before a transaction is retried.
+const BIT_DELAY_SLOW_DOWN_FACTOR: u16 = 2;
+
+/// A slowed down bit delay is divided by [BIT_DELAY_SLOW_DOWN_FACTOR] after this many
+/// transactions in a row have been acknowledged, until it is back to the configured one.
+const BIT_DELAY_SPEED_UP_TRANSACTION_COUNT: u16 = 50;
+
+/// The digit count of the commonly available TM1637 modules.
+pub const DEFAULT_DISPLAY_SIZE: u8 = 4;
+
+/// Digit order of a module where the n-th digit from the left is wired to the n-th register.
+pub const DEFAULT_DIGIT_ORDER: [u8; DISPLAY_REGISTERS_COUNT] = [0, 1, 2, 3, 4, 5];
+
+/// Alignment of a text which is shorter than the display size.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TextAlignment {
+    Left,
+    Right,
+}
+
+/// How the module is mounted.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DisplayRotation {
+    Normal,
+
+    /// Mounted at 180 degrees; the digits and the segments are drawn upside down.
+    UpsideDown,
 }
 
-pub struct Tm1637<'a, CLK, DIO> {
+pub struct Tm1637<'a, CLK, DIO, DELAY> {
     clk: &'a mut CLK,
     dio: &'a mut DIO,
     pub(crate) display_size: u8,
-    delay_fn: delay::FreeRtos,
+
+    /// Maps the n-th digit from the left to its display register.
+    /// Some 6-digit modules are wired in a scrambled order (eg: 2, 1, 0, 5, 4, 3).
+    digit_order: [u8; DISPLAY_REGISTERS_COUNT],
+
+    text_alignment: TextAlignment,
+    rotation: DisplayRotation,
+    delay_fn: DELAY,
 
     /// Representation of the display state in bits for the TM1637.
     /// Bits 7-4 are zero. Later the "display control"-command prefix will be there.
     /// Bits 3-0 are for display on/off and brightness.
     brightness: u8,
 
+    /// Half of a clock period, as configured.
+    base_delay_us: u16,
+
+    /// Half of a clock period in use; above [base_delay_us] while the timing is slowed down.
     delay_us: u16,
+
+    /// Upper limit of [delay_us] when the timing is slowed down after an ack error.
+    max_delay_us: u16,
+
+    /// Transactions acknowledged in a row at the current [delay_us].
+    acked_transaction_count: u16,
+
+    key_debouncer: KeyDebouncer,
+
+    /// false if the last byte sent was not acknowledged by the TM1637
+    is_healthy: bool,
+
+    /// Shadow copy of the 6 display registers as they were last written.
+    /// [None] if they are unknown: at start up or after a failed write.
+    framebuffer: Option<[u8; DISPLAY_REGISTERS_COUNT]>,
+
+    /// The last "display control"-command acknowledged by the TM1637.
+    sent_display_control: Option<u8>,
+
+    glyphs: GlyphRegistry,
 }
 
-impl<'a, CLK, DIO, E> Tm1637<'a, CLK, DIO>
+impl<'a, CLK, DIO, DELAY, E> Tm1637<'a, CLK, DIO, DELAY>
 where
     CLK: OutputPin<Error = E>,
     DIO: InputPin<Error = E> + OutputPin<Error = E>,
+    DELAY: DelayUs<u16> + DelayMs<u16>,
 {
-    pub fn new(clk: &'a mut CLK, dio: &'a mut DIO) -> Self {
-        Self {
+    /// Creates the driver for a module with [display_size] digits (1 to 6).
+    /// * `dio` An open drain pin: the TM1637 pulls it low for the acknowledge and the key scan
+    ///   data, which can't be read back from a push-pull output.
+    /// * `delay_fn` The delay provider; `esp_idf_hal::delay::FreeRtos` on the device.
+    pub fn new(
+        clk: &'a mut CLK,
+        dio: &'a mut DIO,
+        delay_fn: DELAY,
+        display_size: u8,
+    ) -> anyhow::Result<Self, TmError<E>> {
+        if display_size == 0 || display_size as usize > DISPLAY_REGISTERS_COUNT {
+            return Err(TmError::DisplaySize(
+                "E0035".to_owned(),
+                format!(
+                    "'display_size' should be between 1 and {}, found {}",
+                    DISPLAY_REGISTERS_COUNT, display_size
+                ),
+            ));
+        }
+
+        Ok(Self {
             clk,
             dio,
-            display_size: 4,
-            delay_fn: delay::FreeRtos {},
+            display_size,
+            digit_order: DEFAULT_DIGIT_ORDER,
+            text_alignment: TextAlignment::Right,
+            rotation: DisplayRotation::Normal,
+            delay_fn,
             brightness: DisplayState::On as u8 | Brightness::L7 as u8,
 
-            delay_us: 100_u16,
+            base_delay_us: DEFAULT_BIT_DELAY_US,
+            delay_us: DEFAULT_BIT_DELAY_US,
+            max_delay_us: DEFAULT_MAX_BIT_DELAY_US,
+            acked_transaction_count: 0,
+
+            key_debouncer: KeyDebouncer::new(DEFAULT_KEY_DEBOUNCE_SAMPLES),
+
+            is_healthy: true,
+
+            framebuffer: None,
+            sent_display_control: None,
+
+            glyphs: GlyphRegistry::new(),
+        })
+    }
+
+    /// Sets the register of each digit, from left to right.
+    /// [digit_order] should hold exactly [display_size] unique register addresses.
+    pub fn set_digit_order(&mut self, digit_order: &[u8]) -> anyhow::Result<(), TmError<E>> {
+        if digit_order.len() != self.display_size as usize {
+            return Err(TmError::DigitOrder(
+                "E0036a".to_owned(),
+                format!(
+                    "the digit order should have {} entries, found {}",
+                    self.display_size,
+                    digit_order.len()
+                ),
+            ));
+        }
+
+        let mut next_digit_order = DEFAULT_DIGIT_ORDER;
+        for (pos, register) in digit_order.iter().enumerate() {
+            if *register as usize >= DISPLAY_REGISTERS_COUNT
+                || digit_order.iter().take(pos).any(|r| r == register)
+            {
+                return Err(TmError::DigitOrder(
+                    "E0036b".to_owned(),
+                    format!(
+                        "invalid or duplicate register '{}' in the digit order",
+                        register
+                    ),
+                ));
+            }
+
+            #[allow(clippy::indexing_slicing)]
+            {
+                next_digit_order[pos] = *register;
+            }
+        }
+
+        self.digit_order = next_digit_order;
+
+        Ok(())
+    }
+
+    /// Sets half of a clock period. Long wires need a slower timing, short ones can go faster.
+    /// The max bit delay is raised to [delay_us] if it is lower.
+    pub fn set_bit_delay_us(&mut self, delay_us: u16) {
+        self.base_delay_us = delay_us;
+        self.delay_us = delay_us;
+        self.max_delay_us = self.max_delay_us.max(delay_us);
+        self.acked_transaction_count = 0;
+    }
+
+    /// Sets the slowest timing to retry with when the TM1637 doesn't acknowledge a byte.
+    /// A max bit delay which is not above the bit delay turns the slow down off.
+    pub fn set_max_bit_delay_us(&mut self, max_delay_us: u16) {
+        self.max_delay_us = max_delay_us.max(self.base_delay_us);
+        self.delay_us = self.delay_us.min(self.max_delay_us);
+    }
+
+    /// The bit delay in use, which may have been slowed down after ack errors.
+    pub const fn bit_delay_us(&self) -> u16 {
+        self.delay_us
+    }
+
+    /// Returns false if the last byte sent to the TM1637 was not acknowledged.
+    /// This usually means that the display is unplugged or miswired.
+    pub const fn is_healthy(&self) -> bool {
+        self.is_healthy
+    }
+
+    /// Sets the alignment of the texts which are shorter than the display size.
+    pub fn set_text_alignment(&mut self, text_alignment: TextAlignment) {
+        self.text_alignment = text_alignment;
+    }
+
+    fn text_layout(&self) -> TextLayout<'_> {
+        TextLayout::new(self.display_size, self.text_alignment, &self.glyphs)
+    }
+
+    /// Returns the display register of the n-th digit from the left.
+    /// When the display is upside down, the leftmost digit is the last one of the module.
+    fn digit_register(&self, pos: u8) -> u8 {
+        let pos = match self.rotation {
+            DisplayRotation::Normal => pos,
+            DisplayRotation::UpsideDown => self.display_size.saturating_sub(pos + 1),
+        };
+
+        self.digit_order.get(pos as usize).copied().unwrap_or(pos)
+    }
+
+    /// Sets how the module is mounted.
+    pub fn set_rotation(&mut self, rotation: DisplayRotation) {
+        self.rotation = rotation;
+    }
+
+    /// Turns the segments of each digit upside down if the display is mounted at 180 degrees.
+    /// The dot of an upside down digit sits in front of it, so the dot of each digit
+    /// moves over to the next digit. The dot of the last cell is carried over to a blank
+    /// digit if the cells don't fill the display; there is no dot after the last digit
+    /// of the display, so it can't be shown there.
+    fn rotate_cells(&self, cells: &[u8]) -> anyhow::Result<Vec<u8>, TmError<E>> {
+        if self.rotation == DisplayRotation::Normal {
+            return Ok(cells.to_vec());
+        }
+
+        let dot = SpecialCharBits::ColonOrDot as u8;
+        let mut previous_dot = 0_u8;
+
+        let mut rotated_cells: Vec<u8> = cells
+            .iter()
+            .take(self.display_size as usize)
+            .map(|c| {
+                let rotated = rotate_segments(c & !dot) | previous_dot;
+                previous_dot = c & dot;
+
+                rotated
+            })
+            .collect();
+
+        if previous_dot != 0 {
+            if rotated_cells.len() >= self.display_size as usize {
+                return Err(TmError::Glyph(
+                    "E0047".to_owned(),
+                    "the dot of the last digit can't be shown on an upside down display".to_owned(),
+                ));
+            }
+
+            rotated_cells.push(previous_dot);
         }
+
+        Ok(rotated_cells)
     }
 
+    /// Maps a character to the segments of a single digit.
+    /// Registered glyphs take precedence over the built-in font.
     pub fn char_to_bytes(&self, chr: char) -> u8 {
-        match chr {
-            // upper case matching
-            'A' => UpperCharBits::CharA as u8,
-            'C' => UpperCharBits::CharC as u8,
-            'E' => UpperCharBits::CharE as u8,
-            'F' | 'f' => UpperCharBits::CharF as u8,
-            'G' | 'g' => UpperCharBits::CharG as u8,
-            'H' => UpperCharBits::CharH as u8,
-            'I' => UpperCharBits::CharI as u8,
-            'J' | 'j' => UpperCharBits::CharJ as u8,
-            'L' => UpperCharBits::CharL as u8,
-            'N' => UpperCharBits::CharN as u8,
-            'O' => UpperCharBits::CharO as u8,
-            'P' | 'p' => UpperCharBits::CharP as u8,
-            'R' => UpperCharBits::CharR as u8,
-            'S' | 's' => UpperCharBits::CharS as u8,
-            'U' => UpperCharBits::CharU as u8,
-
-            // lower case matching
-            'a' => LowerCharBits::CharA as u8,
-            'b' | 'B' => LowerCharBits::CharB as u8,
-            'c' => LowerCharBits::CharC as u8,
-            'd' | 'D' => LowerCharBits::CharD as u8,
-            'e' => LowerCharBits::CharE as u8,
-            'h' => LowerCharBits::CharH as u8,
-            'i' => LowerCharBits::CharI as u8,
-            'l' => LowerCharBits::CharL as u8,
-            'n' => LowerCharBits::CharN as u8,
-            'o' => LowerCharBits::CharO as u8,
-            'q' | 'Q' => LowerCharBits::CharQ as u8,
-            'r' => LowerCharBits::CharR as u8,
-            't' | 'T' => LowerCharBits::CharT as u8,
-            'u' => LowerCharBits::CharU as u8,
-            'y' | 'Y' => LowerCharBits::CharY as u8,
-
-            // number matching
-            '0' => NumberCharBits::Zero as u8,
-            '1' => NumberCharBits::One as u8,
-            '2' => NumberCharBits::Two as u8,
-            '3' => NumberCharBits::Three as u8,
-            '4' => NumberCharBits::Four as u8,
-            '5' => NumberCharBits::Five as u8,
-            '6' => NumberCharBits::Six as u8,
-            '7' => NumberCharBits::Seven as u8,
-            '8' => NumberCharBits::Eight as u8,
-            '9' => NumberCharBits::Nine as u8,
-
-            // special character matching
-            ' ' => SpecialCharBits::Space as u8,
-            '-' => SpecialCharBits::Minus as u8,
-            '_' => SpecialCharBits::Underscore as u8,
-            '=' => SpecialCharBits::Equals as u8,
-            '[' => SpecialCharBits::BracketLeft as u8,
-            ']' => SpecialCharBits::BracketRight as u8,
-
-            // '?' and default everything else to a question mark (unknown char)
-            _ => SpecialCharBits::QuestionMark as u8,
+        self.glyphs.single_digit_segments(chr)
+    }
+
+    /// Adds or replaces the raw segments shown for [chr], from the left digit to the right one.
+    /// A glyph can span 1 to 6 digits.
+    pub fn register_glyph(&mut self, chr: char, segments: &[u8]) -> anyhow::Result<(), TmError<E>> {
+        if segments.is_empty() || segments.len() > DISPLAY_REGISTERS_COUNT {
+            return Err(TmError::Glyph(
+                "E0039".to_owned(),
+                format!(
+                    "the glyph of '{}' should span 1 to {} digits, found {}",
+                    chr,
+                    DISPLAY_REGISTERS_COUNT,
+                    segments.len()
+                ),
+            ));
         }
+
+        self.glyphs.register(chr, segments);
+
+        Ok(())
+    }
+
+    /// Removes a registered glyph, so that [chr] is shown with the built-in font again.
+    pub fn unregister_glyph(&mut self, chr: char) {
+        self.glyphs.unregister(chr);
     }
 
     pub fn print_char(
@@ -157,44 +366,28 @@ where
         &mut self,
         string: &str,
         show_colon: bool,
-        auto_scroll: Option<&Tm1637BannerAutoScrollConfig>,
+        auto_scroll: Option<&BannerAutoScrollConfig>,
         delay_ms: u16,
     ) -> anyhow::Result<(), TmError<E>> {
-        let mut string_bucket: Option<Vec<String>> = None;
+        let mut cell_bucket: Option<Vec<Vec<u8>>> = None;
 
         // this will be only available when auto scroll has been approved for the input string
-        let mut approved_auto_scroll_config: Option<&Tm1637BannerAutoScrollConfig> = None;
+        let mut approved_auto_scroll_config: Option<&BannerAutoScrollConfig> = None;
 
         if let Some(c) = auto_scroll {
-            if string.len() >= c.scroll_min_char_count as usize {
-                string_bucket = Some(self.get_auto_scrolling_banners(string, c)?);
+            if self.text_layout().cells(string).len() >= c.scroll_min_char_count as usize {
+                cell_bucket = Some(self.get_auto_scrolling_banners(string, c)?);
                 approved_auto_scroll_config = Some(c);
             }
         }
 
-        if string_bucket.is_none() {
-            // todo currently display size of upto 4 is taken care of.
-            //  add support for smaller or bigger display size
-            let sanitized_string = format!("{:>4}", string);
-
-            string_bucket = Some(vec![sanitized_string]);
+        if cell_bucket.is_none() {
+            cell_bucket = Some(vec![self.text_layout().padded_cells(string)]);
         }
 
-        if let Some(v) = string_bucket {
-            for next_string in &v {
-                // clear screen if the string is empty
-                if next_string.is_empty() {
-                    return self.clear();
-                }
-
-                let char_bucket: Vec<char> = next_string.chars().into_iter().collect();
-                for (pos, item) in char_bucket.iter().enumerate() {
-                    if pos >= self.display_size as usize {
-                        break;
-                    }
-
-                    self.print_char(pos as u8, *item, show_colon)?;
-                }
+        if let Some(v) = cell_bucket {
+            for next_cells in &v {
+                self.print_cells(next_cells, show_colon)?;
 
                 if let Some(ac) = approved_auto_scroll_config {
                     self.set_delay_ms(ac.delay_ms);
@@ -207,52 +400,110 @@ where
         Ok(())
     }
 
-    fn get_auto_scrolling_banners(
-        &self,
-        string: &str,
-        config: &Tm1637BannerAutoScrollConfig,
-    ) -> anyhow::Result<Vec<String>, TmError<E>> {
-        if config.min_char_count_to_be_displayed > self.display_size {
-            return Err(TmError::AutoScroll(
-                "E0026".to_owned(),
-                "'min_char_count_to_be_displayed' should not be greater than the 'display_size'"
-                    .to_owned(),
-            ));
-        }
+    /// Writes the segments of each digit, from left to right, into the display registers
+    /// as per the rotation and the digit order.
+    /// The colon sits in the middle of the module, so it stays on the register 1.
+    fn print_cells(&mut self, cells: &[u8], show_colon: bool) -> anyhow::Result<(), TmError<E>> {
+        let mut next_framebuffer = self.framebuffer.unwrap_or_default();
+        for (pos, item) in self.rotate_cells(cells)?.into_iter().enumerate() {
+            if pos >= self.display_size as usize {
+                break;
+            }
 
-        // if the length of the string is equal to or lesser than the [min_char_count_to_be_displayed] then pad the string and return it back
-        if string.len() <= config.min_char_count_to_be_displayed as usize {
-            let sanitized_string = format!("{:>4}", string);
+            let address = self.digit_register(pos as u8);
+            let mut c = item;
+            if address == 1 && show_colon {
+                c |= SpecialCharBits::ColonOrDot as u8;
+            }
 
-            return Ok(vec![sanitized_string]);
+            if let Some(r) = next_framebuffer.get_mut(address as usize) {
+                *r = c;
+            }
         }
 
-        let mut output: Vec<String> = vec![];
-        let mut char_bucket: Vec<char> = string.chars().into_iter().collect();
+        self.flush_framebuffer(&next_framebuffer)
+    }
 
-        'outerloop: for outer in 0..char_bucket.len() {
-            let mut current_string: String = String::new();
-            for (_, item) in char_bucket
-                .iter_mut()
-                .enumerate()
-                .skip(outer)
-                .take(self.display_size as usize)
-            {
-                current_string.push_str(&*item.to_string());
-            }
+    /// Prints a single frame of an animation without waiting for its duration.
+    pub fn print_frame(&mut self, frame: &SegmentFrame) -> anyhow::Result<(), TmError<E>> {
+        self.print_cells(&frame.segments, frame.show_colon)
+    }
 
-            if current_string.len() < config.min_char_count_to_be_displayed as usize {
-                break 'outerloop;
-            }
+    /// Writes the segments of each digit, from left to right, as per the digit order.
+    pub fn print_digit_segments(&mut self, segments: &[u8]) -> anyhow::Result<(), TmError<E>> {
+        self.print_cells(segments, false)
+    }
 
-            // todo currently display size of upto 4 is taken care of.
-            //  add support for smaller or bigger display size
-            let item = format!("{:<4}", current_string);
+    /// Prints a signed integer. `----` is shown if it doesn't fit on the display.
+    /// * `leading_zeros` Pads the number with zeros instead of blanks, eg: `-007`.
+    pub fn print_int(&mut self, value: i32, leading_zeros: bool) -> anyhow::Result<(), TmError<E>> {
+        let segments =
+            number_to_segments(i64::from(value), 10, 0, leading_zeros, self.display_size);
 
-            output.push(item);
-        }
+        self.print_digit_segments(&segments)
+    }
+
+    /// Prints a number with [decimals] digits after the decimal point, eg: `-3.5` or `62.4`.
+    /// The dot of the digit in front of the decimals is lit; the value is rounded to the
+    /// decimals. `----` is shown if it doesn't fit on the display or if it isn't a finite number.
+    pub fn print_fixed(
+        &mut self,
+        value: f32,
+        decimals: u8,
+        leading_zeros: bool,
+    ) -> anyhow::Result<(), TmError<E>> {
+        let scaled = value * 10_f32.powi(i32::from(decimals));
+        let segments = if scaled.is_finite() {
+            number_to_segments(
+                scaled.round() as i64,
+                10,
+                decimals,
+                leading_zeros,
+                self.display_size,
+            )
+        } else {
+            number_to_segments(i64::MAX, 10, 0, false, self.display_size)
+        };
+
+        self.print_digit_segments(&segments)
+    }
+
+    /// Prints a number in hexadecimal (`0` to `9` and `A b C d E F`).
+    /// `----` is shown if it doesn't fit on the display.
+    pub fn print_hex(&mut self, value: u32, leading_zeros: bool) -> anyhow::Result<(), TmError<E>> {
+        let segments =
+            number_to_segments(i64::from(value), 16, 0, leading_zeros, self.display_size);
+
+        self.print_digit_segments(&segments)
+    }
+
+    /// Breaks an animation down into the frames which are rendered by the
+    /// [`animation::SegmentRenderer`].
+    pub fn animation_frames(
+        &self,
+        animation: &SegmentAnimation,
+    ) -> anyhow::Result<Vec<SegmentFrame>, TmError<E>> {
+        self.text_layout()
+            .animation_frames(animation)
+            .ok_or_else(Self::auto_scroll_config_error)
+    }
+
+    fn get_auto_scrolling_banners(
+        &self,
+        string: &str,
+        config: &BannerAutoScrollConfig,
+    ) -> anyhow::Result<Vec<Vec<u8>>, TmError<E>> {
+        self.text_layout()
+            .auto_scrolling_banners(string, config)
+            .ok_or_else(Self::auto_scroll_config_error)
+    }
 
-        Ok(output)
+    fn auto_scroll_config_error() -> TmError<E> {
+        TmError::AutoScroll(
+            "E0026a".to_owned(),
+            "'min_char_count_to_be_displayed' should not be greater than the 'display_size'"
+                .to_owned(),
+        )
     }
 
     /// Sets the display state. The display state is the 3rd bit of the
@@ -321,6 +572,35 @@ where
             n = DISPLAY_REGISTERS_COUNT as u8 - pos;
         }
 
+        #[allow(clippy::indexing_slicing)]
+        let segments = &segments[..n as usize];
+
+        if let Err(e) = self.with_slow_down(|tm| tm.write_segments_burst(segments, pos)) {
+            // the display is in an unknown state now; write everything on the next flush
+            self.invalidate_framebuffer();
+
+            return Err(e);
+        }
+
+        if let Some(fb) = &mut self.framebuffer {
+            for (offset, item) in segments.iter().enumerate() {
+                if let Some(r) = fb.get_mut(pos as usize + offset) {
+                    *r = *item;
+                }
+            }
+        } else if segments.len() == DISPLAY_REGISTERS_COUNT {
+            let mut fb = [0_u8; DISPLAY_REGISTERS_COUNT];
+            fb.copy_from_slice(segments);
+            self.framebuffer = Some(fb);
+        }
+
+        // it will be a common flow that people write something and expect the display to be on
+        // but the display control is only sent when it differs from the last one sent
+        self.write_display_state_if_changed()
+    }
+
+    /// Sends the data command, the address command and the segments in one auto increment burst.
+    fn write_segments_burst(&mut self, segments: &[u8], pos: u8) -> anyhow::Result<(), TmError<E>> {
         // Command 1
         // for more information about this flow: see data sheet / specification of TM1637
         // or AZDelivery's 7 segment display
@@ -334,29 +614,143 @@ where
 
         // Write the remaining data bytes
         // TM1637 does auto increment internally
-        for i in 0..n {
-            #[allow(clippy::indexing_slicing)]
-            self.write_byte_and_wait_ack(segments[i as usize])?;
+        for s in segments {
+            self.write_byte_and_wait_ack(*s)?;
         }
-        self.stop()?;
+        self.stop()
+    }
 
-        // we do this everytime because it will be a common flow that people write something
-        // and expect the display to be on
-        self.write_display_state()
+    /// Writes the registers which differ from the framebuffer in a single auto increment burst,
+    /// from the first changed register up to the last changed one.
+    /// All the registers are written if the framebuffer is unknown.
+    pub fn flush_framebuffer(
+        &mut self,
+        next_framebuffer: &[u8; DISPLAY_REGISTERS_COUNT],
+    ) -> anyhow::Result<(), TmError<E>> {
+        let changed_registers: Vec<usize> = match self.framebuffer {
+            None => (0..DISPLAY_REGISTERS_COUNT).collect(),
+            Some(fb) => (0..DISPLAY_REGISTERS_COUNT)
+                .filter(|i| fb.get(*i) != next_framebuffer.get(*i))
+                .collect(),
+        };
+
+        match (changed_registers.first(), changed_registers.last()) {
+            (Some(first), Some(last)) => {
+                #[allow(clippy::indexing_slicing)]
+                let segments = &next_framebuffer[*first..=*last];
+
+                self.write_segments_raw(segments, *first as u8)
+            }
+            _ => self.write_display_state_if_changed(),
+        }
+    }
+
+    /// Forgets the framebuffer, so that the next write sends all the registers and the
+    /// display control again. Useful to restore a display which has been power cycled.
+    pub fn invalidate_framebuffer(&mut self) {
+        self.framebuffer = None;
+        self.sent_display_control = None;
     }
 
     /// Send command that sets the display state on the micro controller.
     pub fn write_display_state(&mut self) -> anyhow::Result<(), TmError<E>> {
-        self.start()?;
         // bits 0-2 brightness; bit 3 is on/off
-        self.write_byte_and_wait_ack(ISA::DisplayControlOff as u8 | self.brightness)?;
-        self.stop()
+        let display_control = ISA::DisplayControlOff as u8 | self.brightness;
+
+        self.sent_display_control = None;
+
+        self.with_slow_down(|tm| {
+            tm.start()?;
+            tm.write_byte_and_wait_ack(display_control)?;
+            tm.stop()
+        })?;
+
+        self.sent_display_control = Some(display_control);
+
+        Ok(())
+    }
+
+    /// Sends the display state only if the brightness or the on/off state has changed since
+    /// it was last sent.
+    fn write_display_state_if_changed(&mut self) -> anyhow::Result<(), TmError<E>> {
+        if self.sent_display_control == Some(ISA::DisplayControlOff as u8 | self.brightness) {
+            return Ok(());
+        }
+
+        self.write_display_state()
     }
 
     /// Clears the display.
     pub fn clear(&mut self) -> anyhow::Result<(), TmError<E>> {
-        // begin at position 0 and write 0 into display registers 0 to 5
-        self.write_segments_raw(&[0, 0, 0, 0, 0, 0], 0)
+        // write 0 into display registers 0 to 5
+        self.flush_framebuffer(&[0; DISPLAY_REGISTERS_COUNT])
+    }
+
+    /// Sets the number of identical consecutive key scans required before [`Self::read_keys`]
+    /// reports a key as pressed or released.
+    pub fn set_key_debounce_samples(&mut self, samples: u8) {
+        self.key_debouncer.set_samples_required(samples);
+    }
+
+    /// The debounced key which is currently held down.
+    pub const fn pressed_key(&self) -> Option<u8> {
+        self.key_debouncer.stable_key()
+    }
+
+    /// Reads the key scan data from the TM1637 and decodes it without any debouncing.
+    /// Returns the key code (1 to 16) of the pressed key or [None] if no key is pressed.
+    pub fn read_key_code(&mut self) -> anyhow::Result<Option<u8>, TmError<E>> {
+        let raw = self.with_slow_down(|tm| {
+            tm.start()?;
+            tm.write_byte_and_wait_ack(ISA::DataCommandReadKeys as u8)?;
+            let raw = tm.read_byte()?;
+            tm.stop()?;
+
+            Ok(raw)
+        })?;
+
+        Ok(decode_key_code(raw))
+    }
+
+    /// Scans the keys and debounces the result.
+    /// This is meant to be called periodically (every 10 to 50 ms works well);
+    /// a press or release is reported once the key state has been stable for the configured
+    /// number of scans.
+    pub fn read_keys(&mut self) -> anyhow::Result<Option<KeyEvent>, TmError<E>> {
+        let key_code = self.read_key_code()?;
+
+        Ok(self.key_debouncer.update(key_code))
+    }
+
+    /// Reads a byte bit by bit from the TM1637 and sends the acknowledge.
+    fn read_byte(&mut self) -> anyhow::Result<u8, TmError<E>> {
+        let mut data = 0_u8;
+
+        // release the DIO line so that the TM1637 can drive it; it only can on an open drain pin
+        self.dio.set_high()?;
+
+        // 8 bits
+        // LSF (least significant bit) first
+        for bit in 0_u8..8_u8 {
+            self.clk.set_low()?;
+            self.set_delay_us();
+            self.clk.set_high()?;
+            self.set_delay_us();
+
+            if self.dio.is_high()? {
+                data |= 1 << bit;
+            }
+        }
+
+        // the TM1637 expects an acknowledge clock after the key scan byte
+        self.clk.set_low()?;
+        self.set_delay_us();
+        self.clk.set_high()?;
+        self.set_delay_us();
+        self.clk.set_low()?;
+        self.set_delay_us();
+
+        Ok(data)
     }
 
     /// Writes a byte bit by bit and waits for the acknowledge.
@@ -390,7 +784,7 @@ where
             data >>= 1_i32;
         }
 
-        self.recv_ack()
+        self.recv_ack(byte)
     }
 
     /// This tells the TM1637 that data input starts.
@@ -423,28 +817,89 @@ where
     }
 
     /// Receives one acknowledgment after a byte was sent.
-    fn recv_ack(&mut self) -> anyhow::Result<(), TmError<E>> {
+    /// The TM1637 acknowledges a byte by pulling DIO low on the 9th clock.
+    /// If DIO is not pulled low within [ACK_TIMEOUT_POLL_COUNT] bit delays, the display is
+    /// considered to be missing and the bus is released with a stop condition.
+    /// * `byte` The byte which was sent; it is returned along with the [TmError::Ack].
+    fn recv_ack(&mut self, byte: u8) -> anyhow::Result<(), TmError<E>> {
         self.clk.set_low()?;
-        self.dio.set_low()?;
+        // release DIO so that the TM1637 can pull it low
+        self.dio.set_high()?;
         self.set_delay_us();
         self.clk.set_high()?;
 
-        let is_dio_low: bool = self.dio.is_low()?;
+        let mut is_ack_received = false;
+        for _ in 0_u8..ACK_TIMEOUT_POLL_COUNT {
+            if self.dio.is_low()? {
+                is_ack_received = true;
 
-        // wait a few cycles for ACK to be more fail safe
-        for _ in 0_i32..10_i32 {
-            if is_dio_low {
                 break;
             }
+
+            self.set_delay_us();
         }
 
         self.clk.set_low()?;
         self.dio.set_low()?;
         self.set_delay_us();
 
+        self.is_healthy = is_ack_received;
+
+        if !is_ack_received {
+            self.stop()?;
+
+            return Err(TmError::Ack("E0037".to_owned(), byte));
+        }
+
         Ok(())
     }
 
+    /// Runs a bus transaction. As long as the TM1637 doesn't acknowledge a byte, the bit delay
+    /// is slowed down by [BIT_DELAY_SLOW_DOWN_FACTOR] and the whole transaction is retried,
+    /// until the max bit delay has been tried as well.
+    /// The slowed down bit delay is kept for the next transactions, and is stepped back toward
+    /// the configured one after [BIT_DELAY_SPEED_UP_TRANSACTION_COUNT] acknowledged transactions.
+    fn with_slow_down<T, F>(&mut self, mut transaction: F) -> anyhow::Result<T, TmError<E>>
+    where
+        F: FnMut(&mut Self) -> anyhow::Result<T, TmError<E>>,
+    {
+        loop {
+            match transaction(self) {
+                Err(TmError::Ack(_, _)) if self.delay_us < self.max_delay_us => {
+                    self.acked_transaction_count = 0;
+                    self.delay_us = self
+                        .delay_us
+                        .saturating_mul(BIT_DELAY_SLOW_DOWN_FACTOR)
+                        .clamp(1, self.max_delay_us);
+                }
+                Err(e) => {
+                    self.acked_transaction_count = 0;
+
+                    return Err(e);
+                }
+                Ok(res) => {
+                    self.speed_up();
+
+                    return Ok(res);
+                }
+            }
+        }
+    }
+
+    /// Counts an acknowledged transaction, and steps a slowed down bit delay back toward
+    /// the configured one once enough of them went through in a row.
+    fn speed_up(&mut self) {
+        if self.delay_us <= self.base_delay_us {
+            return;
+        }
+
+        self.acked_transaction_count = self.acked_transaction_count.saturating_add(1);
+        if self.acked_transaction_count >= BIT_DELAY_SPEED_UP_TRANSACTION_COUNT {
+            self.delay_us = (self.delay_us / BIT_DELAY_SLOW_DOWN_FACTOR).max(self.base_delay_us);
+            self.acked_transaction_count = 0;
+        }
+    }
+
     #[inline]
     fn set_delay_ms(&mut self, ms_delay: u16) {
         self.delay_fn.delay_ms(ms_delay);
@@ -455,3 +910,549 @@ where
         self.delay_fn.delay_us(self.delay_us);
     }
 }
+
+impl<'a, CLK, DIO, DELAY, E> SegmentDisplay for Tm1637<'a, CLK, DIO, DELAY>
+where
+    CLK: OutputPin<Error = E>,
+    DIO: InputPin<Error = E> + OutputPin<Error = E>,
+    DELAY: DelayUs<u16> + DelayMs<u16>,
+{
+    type Error = TmError<E>;
+
+    fn display_size(&self) -> u8 {
+        self.display_size
+    }
+
+    fn is_healthy(&self) -> bool {
+        Tm1637::is_healthy(self)
+    }
+
+    fn print_text(&mut self, text: &str, show_colon: bool) -> anyhow::Result<(), TmError<E>> {
+        let cells = self.text_layout().padded_cells(text);
+
+        self.print_cells(&cells, show_colon)
+    }
+
+    fn print_frame(&mut self, frame: &SegmentFrame) -> anyhow::Result<(), TmError<E>> {
+        Tm1637::print_frame(self, frame)
+    }
+
+    fn print_digit_segments(&mut self, segments: &[u8]) -> anyhow::Result<(), TmError<E>> {
+        Tm1637::print_digit_segments(self, segments)
+    }
+
+    fn set_brightness(&mut self, brightness: Brightness) {
+        Tm1637::set_brightness(self, brightness);
+    }
+
+    fn set_display_state(&mut self, ds: DisplayState) {
+        Tm1637::set_display_state(self, ds);
+    }
+
+    fn write_display_state(&mut self) -> anyhow::Result<(), TmError<E>> {
+        Tm1637::write_display_state(self)
+    }
+
+    fn clear(&mut self) -> anyhow::Result<(), TmError<E>> {
+        Tm1637::clear(self)
+    }
+
+    fn invalidate_framebuffer(&mut self) {
+        Tm1637::invalidate_framebuffer(self);
+    }
+
+    fn bit_delay_us(&self) -> Option<u16> {
+        Some(Tm1637::bit_delay_us(self))
+    }
+
+    fn run_self_test(&mut self, step_ms: u16) -> Option<SegmentDisplaySelfTestReport> {
+        Some(Tm1637::run_self_test(self, step_ms).into())
+    }
+
+    fn animation_frames(
+        &self,
+        animation: &SegmentAnimation,
+    ) -> anyhow::Result<Vec<SegmentFrame>, TmError<E>> {
+        Tm1637::animation_frames(self, animation)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::libs::segment_display::bargraph::BargraphStyle;
+    use crate::libs::tm1637::mappings::{char_to_bits, KeyScanBits, SegmentBits, WideCharBits};
+    use crate::libs::tm1637::mock::{BusLevel, DecodedCommand, MockTm1637Bus};
+
+    /// The display registers after each segment write, in the order they were written.
+    fn register_snapshots(bus: &MockTm1637Bus) -> Vec<[u8; DISPLAY_REGISTERS_COUNT]> {
+        let mut registers = [0_u8; DISPLAY_REGISTERS_COUNT];
+        let mut snapshots = vec![];
+
+        for command in bus.decode() {
+            if let DecodedCommand::Segments { address, segments } = command {
+                for (offset, segment) in segments.iter().enumerate() {
+                    registers[address as usize + offset] = *segment;
+                }
+                snapshots.push(registers);
+            }
+        }
+
+        snapshots
+    }
+
+    fn text_registers(text: &str) -> [u8; DISPLAY_REGISTERS_COUNT] {
+        let mut registers = [0_u8; DISPLAY_REGISTERS_COUNT];
+        for (r, c) in registers.iter_mut().zip(text.chars()) {
+            *r = char_to_bits(c);
+        }
+
+        registers
+    }
+
+    #[test]
+    fn write_segments_raw_sends_an_auto_increment_burst() {
+        let bus = MockTm1637Bus::new();
+        let (mut clk, mut dio) = (bus.clk(), bus.dio());
+        let mut tm = Tm1637::new(&mut clk, &mut dio, bus.delay(), 4).unwrap();
+
+        tm.write_segments_raw(&[0x3F, 0x06], 2).unwrap();
+
+        assert_eq!(
+            bus.transactions(),
+            vec![vec![0x40], vec![0xC2, 0x3F, 0x06], vec![0x8F]]
+        );
+        assert_eq!(
+            bus.decode(),
+            vec![
+                DecodedCommand::DataWrite,
+                DecodedCommand::Segments {
+                    address: 2,
+                    segments: vec![0x3F, 0x06],
+                },
+                DecodedCommand::DisplayControl {
+                    is_on: true,
+                    brightness: 7,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn write_segments_raw_is_cut_to_the_last_register() {
+        let bus = MockTm1637Bus::new();
+        let (mut clk, mut dio) = (bus.clk(), bus.dio());
+        let mut tm = Tm1637::new(&mut clk, &mut dio, bus.delay(), 6).unwrap();
+
+        tm.write_segments_raw(&[1, 2, 3, 4, 5, 6], 4).unwrap();
+        tm.write_segments_raw(&[], 0).unwrap();
+
+        assert_eq!(bus.registers(), [0, 0, 0, 0, 1, 2]);
+        assert_eq!(
+            bus.decode().get(1),
+            Some(&DecodedCommand::Segments {
+                address: 4,
+                segments: vec![1, 2],
+            })
+        );
+    }
+
+    #[test]
+    fn the_display_control_is_only_sent_when_it_changes() {
+        let bus = MockTm1637Bus::new();
+        let (mut clk, mut dio) = (bus.clk(), bus.dio());
+        let mut tm = Tm1637::new(&mut clk, &mut dio, bus.delay(), 4).unwrap();
+
+        tm.write_segments_raw(&[0x01], 0).unwrap();
+        tm.write_segments_raw(&[0x02], 1).unwrap();
+
+        let display_controls = bus
+            .decode()
+            .into_iter()
+            .filter(|c| matches!(c, DecodedCommand::DisplayControl { .. }))
+            .count();
+        assert_eq!(display_controls, 1);
+    }
+
+    #[test]
+    fn brightness_and_display_state_are_sent_on_write() {
+        let bus = MockTm1637Bus::new();
+        let (mut clk, mut dio) = (bus.clk(), bus.dio());
+        let mut tm = Tm1637::new(&mut clk, &mut dio, bus.delay(), 4).unwrap();
+
+        tm.set_brightness(Brightness::L3);
+        assert!(bus.transactions().is_empty());
+
+        tm.write_display_state().unwrap();
+        assert_eq!(bus.display_control(), Some((true, 3)));
+
+        tm.set_display_state(DisplayState::Off);
+        tm.write_display_state().unwrap();
+        assert_eq!(bus.display_control(), Some((false, 3)));
+
+        tm.set_display_state(DisplayState::On);
+        tm.set_brightness(Brightness::L0);
+        tm.write_segments_raw(&[0x01], 0).unwrap();
+        assert_eq!(bus.display_control(), Some((true, 0)));
+    }
+
+    #[test]
+    fn clear_only_sends_the_lit_registers() {
+        let bus = MockTm1637Bus::new();
+        let (mut clk, mut dio) = (bus.clk(), bus.dio());
+        let mut tm = Tm1637::new(&mut clk, &mut dio, bus.delay(), 4).unwrap();
+
+        tm.print_digit_segments(&[0, 0x06, 0x5B, 0]).unwrap();
+        bus.reset_recording();
+
+        tm.clear().unwrap();
+
+        assert_eq!(
+            bus.decode(),
+            vec![
+                DecodedCommand::DataWrite,
+                DecodedCommand::Segments {
+                    address: 1,
+                    segments: vec![0, 0],
+                },
+            ]
+        );
+        assert_eq!(bus.registers(), [0; DISPLAY_REGISTERS_COUNT]);
+    }
+
+    #[test]
+    fn an_unacknowledged_byte_fails_the_write() {
+        let bus = MockTm1637Bus::new();
+        let (mut clk, mut dio) = (bus.clk(), bus.dio());
+        let mut tm = Tm1637::new(&mut clk, &mut dio, bus.delay(), 4).unwrap();
+
+        bus.set_ack_enabled(false);
+        let res = tm.write_segments_raw(&[0x01], 0);
+
+        assert!(matches!(res, Err(TmError::Ack(ref code, 0x40)) if code == "E0037"));
+        assert!(!tm.is_healthy());
+
+        // the display is back; the whole framebuffer is unknown, so everything is sent again
+        bus.set_ack_enabled(true);
+        bus.reset_recording();
+        tm.print_digit_segments(&[0x01, 0, 0, 0]).unwrap();
+
+        assert!(tm.is_healthy());
+        assert_eq!(
+            bus.decode().get(1),
+            Some(&DecodedCommand::Segments {
+                address: 0,
+                segments: vec![0x01, 0, 0, 0, 0, 0],
+            })
+        );
+    }
+
+    #[test]
+    fn every_byte_is_clocked_with_its_ack() {
+        let bus = MockTm1637Bus::new();
+        let (mut clk, mut dio) = (bus.clk(), bus.dio());
+        let mut tm = Tm1637::new(&mut clk, &mut dio, bus.delay(), 4).unwrap();
+
+        tm.write_segments_raw(&[0xFF], 0).unwrap();
+
+        // 4 bytes of 8 bits and an ack clock each, and the clock of the 3 stop conditions;
+        // the bus is released once the chip has let go of DIO
+        let waveform = bus.waveform();
+        let clk_rising_edges = waveform
+            .windows(2)
+            .filter(|w| !w[0].clk && w[1].clk)
+            .count();
+        assert_eq!(clk_rising_edges, 4 * 9 + 3);
+        assert_eq!(
+            waveform.last(),
+            Some(&BusLevel {
+                clk: true,
+                dio: true
+            })
+        );
+    }
+
+    #[test]
+    fn the_bit_delay_is_slowed_down_and_stepped_back_to_the_configured_one() {
+        let bus = MockTm1637Bus::new();
+        let (mut clk, mut dio) = (bus.clk(), bus.dio());
+        let mut tm = Tm1637::new(&mut clk, &mut dio, bus.delay(), 4).unwrap();
+        tm.set_bit_delay_us(10);
+        tm.set_max_bit_delay_us(40);
+
+        // every slower timing is tried before the write fails
+        bus.set_ack_enabled(false);
+        assert!(tm.write_segments_raw(&[0x01], 0).is_err());
+        assert_eq!(tm.bit_delay_us(), 40);
+
+        // the first write also sends the display control, which is another transaction
+        bus.set_ack_enabled(true);
+        for n in 2..BIT_DELAY_SPEED_UP_TRANSACTION_COUNT {
+            tm.write_segments_raw(&[n as u8], 0).unwrap();
+        }
+        assert_eq!(tm.bit_delay_us(), 40);
+
+        tm.write_segments_raw(&[0x02], 0).unwrap();
+        assert_eq!(tm.bit_delay_us(), 20);
+
+        for n in 0..BIT_DELAY_SPEED_UP_TRANSACTION_COUNT * 2 {
+            tm.write_segments_raw(&[n as u8], 0).unwrap();
+        }
+        assert_eq!(tm.bit_delay_us(), 10);
+    }
+
+    #[test]
+    fn an_unacknowledged_transaction_restarts_the_speed_up_count() {
+        let bus = MockTm1637Bus::new();
+        let (mut clk, mut dio) = (bus.clk(), bus.dio());
+        let mut tm = Tm1637::new(&mut clk, &mut dio, bus.delay(), 4).unwrap();
+        tm.set_bit_delay_us(10);
+        tm.set_max_bit_delay_us(20);
+
+        bus.set_ack_enabled(false);
+        assert!(tm.write_segments_raw(&[0x01], 0).is_err());
+
+        // one transaction short of the speed up, counting the display control
+        bus.set_ack_enabled(true);
+        for n in 2..BIT_DELAY_SPEED_UP_TRANSACTION_COUNT {
+            tm.write_segments_raw(&[n as u8], 0).unwrap();
+        }
+        assert_eq!(tm.bit_delay_us(), 20);
+
+        bus.set_ack_enabled(false);
+        assert!(tm.write_segments_raw(&[0x01], 0).is_err());
+
+        bus.set_ack_enabled(true);
+        tm.write_segments_raw(&[0x02], 0).unwrap();
+        assert_eq!(tm.bit_delay_us(), 20);
+    }
+
+    #[test]
+    fn read_key_code_decodes_the_key_scan_byte() {
+        let bus = MockTm1637Bus::new();
+        let (mut clk, mut dio) = (bus.clk(), bus.dio());
+        let mut tm = Tm1637::new(&mut clk, &mut dio, bus.delay(), 4).unwrap();
+
+        assert_eq!(tm.read_key_code().unwrap(), None);
+
+        // K1/SG1
+        bus.set_key_scan_data(0b1110_1111);
+        assert_eq!(tm.read_key_code().unwrap(), Some(1));
+
+        // K2/SG2
+        bus.set_key_scan_data(0b0111_0111);
+        assert_eq!(tm.read_key_code().unwrap(), Some(10));
+
+        assert_eq!(
+            bus.decode(),
+            vec![
+                DecodedCommand::ReadKeys(0b1111_1111),
+                DecodedCommand::ReadKeys(0b1110_1111),
+                DecodedCommand::ReadKeys(0b0111_0111),
+            ]
+        );
+    }
+
+    #[test]
+    fn read_keys_reports_debounced_presses_and_releases() {
+        let bus = MockTm1637Bus::new();
+        let (mut clk, mut dio) = (bus.clk(), bus.dio());
+        let mut tm = Tm1637::new(&mut clk, &mut dio, bus.delay(), 4).unwrap();
+        tm.set_key_debounce_samples(2);
+
+        bus.set_key_scan_data(0b1110_1111);
+        assert_eq!(tm.read_keys().unwrap(), None);
+        assert_eq!(tm.read_keys().unwrap(), Some(KeyEvent::Pressed(1)));
+        assert_eq!(tm.read_keys().unwrap(), None);
+        assert_eq!(tm.pressed_key(), Some(1));
+
+        // a bounce doesn't release the key
+        bus.set_key_scan_data(KeyScanBits::NoKeyPressed as u8);
+        assert_eq!(tm.read_keys().unwrap(), None);
+        bus.set_key_scan_data(0b1110_1111);
+        assert_eq!(tm.read_keys().unwrap(), None);
+
+        bus.set_key_scan_data(KeyScanBits::NoKeyPressed as u8);
+        assert_eq!(tm.read_keys().unwrap(), None);
+        assert_eq!(tm.read_keys().unwrap(), Some(KeyEvent::Released(1)));
+        assert_eq!(tm.pressed_key(), None);
+    }
+
+    #[test]
+    fn print_text_pads_by_display_cells() {
+        let bus = MockTm1637Bus::new();
+        let (mut clk, mut dio) = (bus.clk(), bus.dio());
+        let mut tm = Tm1637::new(&mut clk, &mut dio, bus.delay(), 4).unwrap();
+
+        // the wide M takes two of the four cells, so one blank cell is left in front
+        SegmentDisplay::print_text(&mut tm, "Mo", false).unwrap();
+
+        let [m_left, m_right] = WideCharBits::CHAR_M;
+        assert_eq!(
+            bus.registers(),
+            [0, m_left, m_right, char_to_bits('o'), 0, 0]
+        );
+
+        // nothing is cut off when the wide characters fill the display
+        SegmentDisplay::print_text(&mut tm, "MW", false).unwrap();
+
+        let [w_left, w_right] = WideCharBits::CHAR_W;
+        assert_eq!(bus.registers(), [m_left, m_right, w_left, w_right, 0, 0]);
+    }
+
+    #[test]
+    fn print_bargraph_draws_the_level_across_the_digits() {
+        let bus = MockTm1637Bus::new();
+        let (mut clk, mut dio) = (bus.clk(), bus.dio());
+        let mut tm = Tm1637::new(&mut clk, &mut dio, bus.delay(), 4).unwrap();
+
+        // half of the eight bar steps, with the threshold marked on the third digit
+        SegmentDisplay::print_bargraph(&mut tm, 50, BargraphStyle::Bar, &[75]).unwrap();
+
+        let full = SegmentBits::SegF as u8
+            | SegmentBits::SegE as u8
+            | SegmentBits::SegB as u8
+            | SegmentBits::SegC as u8;
+        assert_eq!(
+            bus.registers(),
+            [full, full, SegmentBits::SegA as u8, 0, 0, 0]
+        );
+    }
+
+    #[test]
+    fn the_dots_move_to_the_next_digit_when_upside_down() {
+        let bus = MockTm1637Bus::new();
+        let (mut clk, mut dio) = (bus.clk(), bus.dio());
+        let mut tm = Tm1637::new(&mut clk, &mut dio, bus.delay(), 4).unwrap();
+        tm.set_rotation(DisplayRotation::UpsideDown);
+
+        let dot = SpecialCharBits::ColonOrDot as u8;
+        let (one, two) = (char_to_bits('1'), char_to_bits('2'));
+
+        // the dot of the last cell is carried over to the blank digit after it
+        tm.print_digit_segments(&[one | dot, two | dot]).unwrap();
+
+        assert_eq!(
+            bus.registers(),
+            [
+                0,
+                dot,
+                rotate_segments(two) | dot,
+                rotate_segments(one),
+                0,
+                0
+            ]
+        );
+    }
+
+    #[test]
+    fn the_dot_of_the_last_digit_is_rejected_when_upside_down() {
+        let bus = MockTm1637Bus::new();
+        let (mut clk, mut dio) = (bus.clk(), bus.dio());
+        let mut tm = Tm1637::new(&mut clk, &mut dio, bus.delay(), 4).unwrap();
+        tm.set_rotation(DisplayRotation::UpsideDown);
+
+        let dot = SpecialCharBits::ColonOrDot as u8;
+        let res = tm.print_digit_segments(&[0, 0, 0, char_to_bits('1') | dot]);
+
+        assert!(matches!(res, Err(TmError::Glyph(code, _)) if code == "E0047"));
+        assert!(bus.transactions().is_empty());
+    }
+
+    #[test]
+    fn the_self_test_passes_on_an_upside_down_display() {
+        let bus = MockTm1637Bus::new();
+        let (mut clk, mut dio) = (bus.clk(), bus.dio());
+        let mut tm = Tm1637::new(&mut clk, &mut dio, bus.delay(), 4).unwrap();
+        tm.set_rotation(DisplayRotation::UpsideDown);
+
+        let report = tm.run_self_test(0);
+
+        assert!(report.is_passed(), "{:?}", report.failures);
+        // 2 all segments, 4 digits by 8 segments but the dot of the last digit, 8 brightness
+        // levels and the clear
+        assert_eq!(report.steps_count, 2 + 31 + 8 + 1);
+    }
+
+    #[test]
+    fn registered_glyphs_take_their_own_cells() {
+        let bus = MockTm1637Bus::new();
+        let (mut clk, mut dio) = (bus.clk(), bus.dio());
+        let mut tm = Tm1637::new(&mut clk, &mut dio, bus.delay(), 4).unwrap();
+        tm.set_text_alignment(TextAlignment::Left);
+
+        tm.register_glyph('~', &[0x01, 0x40, 0x08]).unwrap();
+        SegmentDisplay::print_text(&mut tm, "~1", false).unwrap();
+        assert_eq!(bus.registers(), [0x01, 0x40, 0x08, char_to_bits('1'), 0, 0]);
+
+        assert!(matches!(
+            tm.register_glyph('~', &[]),
+            Err(TmError::Glyph(_, _))
+        ));
+
+        tm.unregister_glyph('~');
+        assert_eq!(tm.char_to_bytes('~'), char_to_bits('~'));
+    }
+
+    #[test]
+    fn print_string_scrolls_a_long_text() {
+        let bus = MockTm1637Bus::new();
+        let (mut clk, mut dio) = (bus.clk(), bus.dio());
+        let mut tm = Tm1637::new(&mut clk, &mut dio, bus.delay(), 4).unwrap();
+        tm.set_text_alignment(TextAlignment::Left);
+        tm.set_bit_delay_us(1);
+
+        let config = BannerAutoScrollConfig {
+            scroll_min_char_count: 5,
+            delay_ms: 300,
+            min_char_count_to_be_displayed: 3,
+        };
+        tm.print_string("HELLO", false, Some(&config), 0).unwrap();
+
+        assert_eq!(
+            register_snapshots(&bus),
+            vec![
+                text_registers("HELL"),
+                text_registers("ELLO"),
+                text_registers("LLO "),
+            ]
+        );
+        // the bit delays take well under a millisecond
+        assert_eq!(bus.elapsed_us() / 1000, 3 * 300);
+    }
+
+    #[test]
+    fn print_string_pads_a_short_text() {
+        let bus = MockTm1637Bus::new();
+        let (mut clk, mut dio) = (bus.clk(), bus.dio());
+        let mut tm = Tm1637::new(&mut clk, &mut dio, bus.delay(), 4).unwrap();
+
+        let config = BannerAutoScrollConfig {
+            scroll_min_char_count: 5,
+            delay_ms: 300,
+            min_char_count_to_be_displayed: 3,
+        };
+        tm.print_string("ON", true, Some(&config), 0).unwrap();
+
+        let mut expected = text_registers("  ON");
+        expected[1] |= SpecialCharBits::ColonOrDot as u8;
+        assert_eq!(register_snapshots(&bus), vec![expected]);
+    }
+
+    #[test]
+    fn an_auto_scroll_config_wider_than_the_display_is_rejected() {
+        let bus = MockTm1637Bus::new();
+        let (mut clk, mut dio) = (bus.clk(), bus.dio());
+        let mut tm = Tm1637::new(&mut clk, &mut dio, bus.delay(), 4).unwrap();
+
+        let config = BannerAutoScrollConfig {
+            scroll_min_char_count: 5,
+            delay_ms: 300,
+            min_char_count_to_be_displayed: 5,
+        };
+        let res = tm.print_string("HELLO WORLD", false, Some(&config), 0);
+
+        assert!(matches!(res, Err(TmError::AutoScroll(_, _))));
+        assert!(bus.transactions().is_empty());
+    }
+}
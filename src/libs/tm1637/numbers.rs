@@ -0,0 +1,80 @@
+use crate::libs::tm1637::mappings::{
+    LowerCharBits, NumberCharBits, SpecialCharBits, UpperCharBits,
+};
+
+/// Segments of a digit in the range of 0 to 15 (hex digits are shown as `A b C d E F`).
+const fn digit_to_bytes(digit: u8) -> u8 {
+    match digit {
+        0 => NumberCharBits::Zero as u8,
+        1 => NumberCharBits::One as u8,
+        2 => NumberCharBits::Two as u8,
+        3 => NumberCharBits::Three as u8,
+        4 => NumberCharBits::Four as u8,
+        5 => NumberCharBits::Five as u8,
+        6 => NumberCharBits::Six as u8,
+        7 => NumberCharBits::Seven as u8,
+        8 => NumberCharBits::Eight as u8,
+        9 => NumberCharBits::Nine as u8,
+        10 => UpperCharBits::CharA as u8,
+        11 => LowerCharBits::CharB as u8,
+        12 => UpperCharBits::CharC as u8,
+        13 => LowerCharBits::CharD as u8,
+        14 => UpperCharBits::CharE as u8,
+        15 => UpperCharBits::CharF as u8,
+        _ => SpecialCharBits::QuestionMark as u8,
+    }
+}
+
+/// Formats a number into the segments of each digit, from left to right.
+/// * `value` The number; for a fixed point number this is the value scaled by `radix ^ decimals`.
+/// * `radix` 2 to 16.
+/// * `decimals` The number of digits after the decimal point; `0` lights no dot.
+/// * `leading_zeros` Pads the number with zeros instead of blanks. The sign stays in front.
+/// * `display_size` The number of digits available.
+///
+/// If the number doesn't fit on the display then every digit shows a minus sign (eg: `----`).
+pub fn number_to_segments(
+    value: i64,
+    radix: u8,
+    decimals: u8,
+    leading_zeros: bool,
+    display_size: u8,
+) -> Vec<u8> {
+    let size = display_size as usize;
+    let radix = u64::from(radix.clamp(2, 16));
+    let is_negative = value < 0;
+    let mut magnitude = value.unsigned_abs();
+
+    // least significant digit first
+    // a fixed point number always has a digit in front of the dot, eg: `0.5`
+    let mut digits: Vec<u8> = vec![];
+    while magnitude > 0 || digits.len() <= decimals as usize {
+        digits.push((magnitude % radix) as u8);
+        magnitude /= radix;
+    }
+
+    let sign_count = usize::from(is_negative);
+    if digits.len() + sign_count > size {
+        return vec![SpecialCharBits::Minus as u8; size];
+    }
+
+    if leading_zeros {
+        digits.resize(size - sign_count, 0);
+    }
+
+    let mut segments = vec![SpecialCharBits::Space as u8; size - digits.len() - sign_count];
+    if is_negative {
+        segments.push(SpecialCharBits::Minus as u8);
+    }
+
+    for (index, digit) in digits.iter().enumerate().rev() {
+        let mut s = digit_to_bytes(*digit);
+        if decimals > 0 && index == decimals as usize {
+            s |= SpecialCharBits::ColonOrDot as u8;
+        }
+
+        segments.push(s);
+    }
+
+    segments
+}
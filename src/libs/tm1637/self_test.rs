@@ -0,0 +1,165 @@
+use crate::libs::segment_display::self_test::{
+    SegmentDisplaySelfTestFailure, SegmentDisplaySelfTestReport, SegmentDisplaySelfTestStep,
+};
+use crate::libs::tm1637::errors::TmError;
+use crate::libs::tm1637::mappings::{Brightness, DisplayState, SpecialCharBits};
+use crate::libs::tm1637::{DisplayRotation, Tm1637, DISPLAY_REGISTERS_COUNT};
+use embedded_hal::blocking::delay::{DelayMs, DelayUs};
+use embedded_hal::digital::v2::{InputPin, OutputPin};
+
+/// Number of brightness levels swept by the self test (L0 to L7).
+const BRIGHTNESS_LEVELS_COUNT: u8 = 8;
+
+/// A step of the display self test.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Tm1637SelfTestStep {
+    /// Every segment, the dots and the colon are lit.
+    AllSegments,
+
+    /// A single segment is lit; `segment` is the bit of [`SegmentBits`], 0 (A) to 7 (DP).
+    ///
+    /// [`SegmentBits`]: crate::libs::tm1637::mappings::SegmentBits
+    SegmentWalk { digit: u8, segment: u8 },
+
+    /// Every segment is lit with the brightness `level` (0 to 7).
+    Brightness { level: u8 },
+
+    /// The display is cleared and its brightness is restored.
+    Clear,
+}
+
+/// A step where the TM1637 didn't acknowledge a byte.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tm1637SelfTestFailure {
+    pub step: Tm1637SelfTestStep,
+
+    /// The byte which was not acknowledged; [None] if the GPIO itself failed.
+    pub byte: Option<u8>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Tm1637SelfTestReport {
+    pub steps_count: u16,
+    pub failures: Vec<Tm1637SelfTestFailure>,
+}
+
+impl Tm1637SelfTestReport {
+    pub fn is_passed(&self) -> bool {
+        self.failures.is_empty()
+    }
+
+    fn record<E>(&mut self, step: Tm1637SelfTestStep, res: anyhow::Result<(), TmError<E>>) {
+        self.steps_count += 1;
+
+        if let Err(e) = res {
+            let byte = match e {
+                TmError::Ack(_, b) => Some(b),
+                _ => None,
+            };
+
+            self.failures.push(Tm1637SelfTestFailure { step, byte });
+        }
+    }
+}
+
+impl From<Tm1637SelfTestStep> for SegmentDisplaySelfTestStep {
+    fn from(step: Tm1637SelfTestStep) -> Self {
+        match step {
+            Tm1637SelfTestStep::AllSegments => SegmentDisplaySelfTestStep::AllSegments,
+            Tm1637SelfTestStep::SegmentWalk { digit, segment } => {
+                SegmentDisplaySelfTestStep::SegmentWalk { digit, segment }
+            }
+            Tm1637SelfTestStep::Brightness { level } => {
+                SegmentDisplaySelfTestStep::Brightness { level }
+            }
+            Tm1637SelfTestStep::Clear => SegmentDisplaySelfTestStep::Clear,
+        }
+    }
+}
+
+impl From<Tm1637SelfTestReport> for SegmentDisplaySelfTestReport {
+    fn from(report: Tm1637SelfTestReport) -> Self {
+        Self {
+            steps_count: report.steps_count,
+            failures: report
+                .failures
+                .into_iter()
+                .map(|f| SegmentDisplaySelfTestFailure {
+                    step: f.step.into(),
+                    byte: f.byte,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl<'a, CLK, DIO, DELAY, E> Tm1637<'a, CLK, DIO, DELAY>
+where
+    CLK: OutputPin<Error = E>,
+    DIO: InputPin<Error = E> + OutputPin<Error = E>,
+    DELAY: DelayUs<u16> + DelayMs<u16>,
+{
+    /// Blocks while it lights all the segments, walks each segment (A to G and DP) across
+    /// every digit, leaving out the dot which an upside down display can't show, and sweeps
+    /// the brightness from L0 to L7, holding each step for [step_ms].
+    /// Every step is run even if the display stops acknowledging, so that the report lists
+    /// all the steps which failed.
+    pub fn run_self_test(&mut self, step_ms: u16) -> Tm1637SelfTestReport {
+        let mut report = Tm1637SelfTestReport::default();
+        let previous_display_control = self.brightness;
+        let mut all_segments = [0xFF_u8; DISPLAY_REGISTERS_COUNT];
+        if let Some(s) = all_segments.get_mut(self.display_size.saturating_sub(1) as usize) {
+            if !self.has_last_dot() {
+                *s &= !(SpecialCharBits::ColonOrDot as u8);
+            }
+        }
+
+        // send every register and the display control, whatever the display was showing
+        self.invalidate_framebuffer();
+        self.brightness = DisplayState::On as u8 | Brightness::L7 as u8;
+
+        let res = self.print_digit_segments(&all_segments);
+        report.record(Tm1637SelfTestStep::AllSegments, res);
+        self.set_delay_ms(step_ms);
+
+        for digit in 0..self.display_size {
+            for segment in 0_u8..8_u8 {
+                if segment == 7 && digit + 1 == self.display_size && !self.has_last_dot() {
+                    continue;
+                }
+
+                let mut segments = [0_u8; DISPLAY_REGISTERS_COUNT];
+                if let Some(s) = segments.get_mut(digit as usize) {
+                    *s = 1 << segment;
+                }
+
+                let res = self.print_digit_segments(&segments);
+                report.record(Tm1637SelfTestStep::SegmentWalk { digit, segment }, res);
+                self.set_delay_ms(step_ms);
+            }
+        }
+
+        let res = self.print_digit_segments(&all_segments);
+        report.record(Tm1637SelfTestStep::AllSegments, res);
+
+        for level in 0..BRIGHTNESS_LEVELS_COUNT {
+            self.brightness = DisplayState::On as u8 | level;
+
+            let res = self.write_display_state();
+            report.record(Tm1637SelfTestStep::Brightness { level }, res);
+            self.set_delay_ms(step_ms);
+        }
+
+        self.brightness = previous_display_control;
+        let res = self.clear();
+        report.record(Tm1637SelfTestStep::Clear, res);
+
+        report
+    }
+
+    /// False on an upside down display: the dot of a digit then sits in front of it, so
+    /// there is none after the last digit.
+    fn has_last_dot(&self) -> bool {
+        self.rotation == DisplayRotation::Normal
+    }
+}
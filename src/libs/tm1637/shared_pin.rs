@@ -0,0 +1,49 @@
+use embedded_hal::digital::v2::OutputPin;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// An output pin which is shared by several displays, eg: a CLK line wired to several TM1637s
+/// which have their own DIO line. A TM1637 only listens after a start condition on its DIO, so
+/// the others ignore the clock pulses which are not meant for them.
+/// The displays have to be driven from the same thread, one after another.
+pub struct SharedOutputPin<P> {
+    pin: Rc<RefCell<P>>,
+}
+
+impl<P> SharedOutputPin<P> {
+    pub fn new(pin: P) -> Self {
+        Self {
+            pin: Rc::new(RefCell::new(pin)),
+        }
+    }
+
+    /// Gives the pin back if it isn't shared anymore.
+    pub fn try_into_inner(self) -> Result<P, Self> {
+        Rc::try_unwrap(self.pin)
+            .map(RefCell::into_inner)
+            .map_err(|pin| Self { pin })
+    }
+}
+
+impl<P> Clone for SharedOutputPin<P> {
+    fn clone(&self) -> Self {
+        Self {
+            pin: Rc::clone(&self.pin),
+        }
+    }
+}
+
+impl<P> OutputPin for SharedOutputPin<P>
+where
+    P: OutputPin,
+{
+    type Error = P::Error;
+
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        self.pin.borrow_mut().set_low()
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        self.pin.borrow_mut().set_high()
+    }
+}
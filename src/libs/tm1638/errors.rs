@@ -0,0 +1,16 @@
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum Tm1638Error<E> {
+    #[error("an IO error occured")]
+    IO(E),
+
+    #[error("{0:?} an auto scroll error occured: {1:?}")]
+    AutoScroll(String, String),
+}
+
+impl<E> From<E> for Tm1638Error<E> {
+    fn from(err: E) -> Self {
+        Self::IO(err)
+    }
+}
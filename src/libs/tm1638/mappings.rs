@@ -0,0 +1,19 @@
+/// The commands of the TM1638. See data sheet for more information.
+/// This is only a subset of the possible values.
+#[repr(u8)]
+pub enum Tm1638Command {
+    /// "write data to display register"-mode with auto increment of the address
+    DataCommandWriteToDisplay = 0b0100_0000,
+
+    /// "read key scan data"-mode; followed by 4 bytes clocked out of the TM1638
+    DataCommandReadKeys = 0b0100_0010,
+
+    /// Starts at display address zero. The even addresses hold the segments of a digit,
+    /// the odd addresses hold the LED next to it (bit 0).
+    AddressCommandD0 = 0b1100_0000,
+
+    // bits 0 - 2 tell the brightness.
+    // bit 3 is display on/off
+    /// Command that sets the display off.
+    DisplayControlOff = 0b1000_0000,
+}
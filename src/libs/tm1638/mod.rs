@@ -0,0 +1,315 @@
+#![allow(clippy::integer_arithmetic, clippy::cast_possible_truncation)]
+
+// ref: https://github.com/rjbatista/tm1638-library/blob/master/TM16XX.cpp
+
+pub mod errors;
+pub mod mappings;
+
+extern crate embedded_hal as hal;
+
+use crate::libs::segment_display::animation::{SegmentAnimation, SegmentFrame};
+use crate::libs::segment_display::text_layout::TextLayout;
+use crate::libs::segment_display::SegmentDisplay;
+use crate::libs::tm1637::glyphs::GlyphRegistry;
+use crate::libs::tm1637::mappings::{Brightness, DisplayState, SpecialCharBits};
+use crate::libs::tm1637::TextAlignment;
+use crate::libs::tm1638::errors::Tm1638Error;
+use crate::libs::tm1638::mappings::Tm1638Command;
+use hal::blocking::delay::DelayUs;
+use hal::digital::v2::{InputPin, OutputPin};
+
+/// The TM1638 boards ("LED&KEY") have 8 digits, 8 LEDs and 8 buttons.
+pub const TM1638_DIGITS_COUNT: usize = 8;
+
+/// Number of key scan bytes clocked out after [`Tm1638Command::DataCommandReadKeys`].
+const KEY_SCAN_BYTES_COUNT: u8 = 4;
+
+pub struct Tm1638<'a, STB, CLK, DIO, DELAY> {
+    stb: &'a mut STB,
+    clk: &'a mut CLK,
+    dio: &'a mut DIO,
+    delay_fn: DELAY,
+
+    text_alignment: TextAlignment,
+
+    /// Bits 3-0 of the "display control"-command: display on/off and brightness.
+    brightness: u8,
+
+    delay_us: u16,
+
+    /// The segments of each digit, from left to right.
+    digits: [u8; TM1638_DIGITS_COUNT],
+
+    /// Bit n is the LED above the n-th digit from the left.
+    leds: u8,
+
+    glyphs: GlyphRegistry,
+}
+
+impl<'a, STB, CLK, DIO, DELAY, E> Tm1638<'a, STB, CLK, DIO, DELAY>
+where
+    STB: OutputPin<Error = E>,
+    CLK: OutputPin<Error = E>,
+    DIO: InputPin<Error = E> + OutputPin<Error = E>,
+    DELAY: DelayUs<u16>,
+{
+    /// * `dio` should be open drain, the TM1638 drives it while the buttons are read.
+    pub fn new(stb: &'a mut STB, clk: &'a mut CLK, dio: &'a mut DIO, delay_fn: DELAY) -> Self {
+        Self {
+            stb,
+            clk,
+            dio,
+            delay_fn,
+            text_alignment: TextAlignment::Right,
+            brightness: DisplayState::On as u8 | Brightness::L7 as u8,
+
+            delay_us: 1_u16,
+
+            digits: [0; TM1638_DIGITS_COUNT],
+            leds: 0,
+
+            glyphs: GlyphRegistry::new(),
+        }
+    }
+
+    /// Sets the alignment of the texts which are shorter than the display size.
+    pub fn set_text_alignment(&mut self, text_alignment: TextAlignment) {
+        self.text_alignment = text_alignment;
+    }
+
+    fn text_layout(&self) -> TextLayout<'_> {
+        TextLayout::new(TM1638_DIGITS_COUNT as u8, self.text_alignment, &self.glyphs)
+    }
+
+    /// Prints a text, padded or cut to the 8 digits.
+    pub fn print_text(
+        &mut self,
+        text: &str,
+        show_colon: bool,
+    ) -> anyhow::Result<(), Tm1638Error<E>> {
+        let cells = self.text_layout().padded_cells(text);
+
+        self.print_cells(&cells, show_colon)
+    }
+
+    /// Prints a frame which has been laid out by [`Tm1638::animation_frames`].
+    pub fn print_frame(&mut self, frame: &SegmentFrame) -> anyhow::Result<(), Tm1638Error<E>> {
+        self.print_cells(&frame.segments, frame.show_colon)
+    }
+
+    fn print_cells(
+        &mut self,
+        cells: &[u8],
+        show_colon: bool,
+    ) -> anyhow::Result<(), Tm1638Error<E>> {
+        let mut digits = [0_u8; TM1638_DIGITS_COUNT];
+        for (digit, segments) in digits.iter_mut().zip(cells) {
+            *digit = *segments;
+        }
+
+        // there is no colon on these boards; light the dot after the second digit instead
+        if show_colon {
+            if let Some(d) = digits.get_mut(1) {
+                *d |= SpecialCharBits::ColonOrDot as u8;
+            }
+        }
+
+        self.digits = digits;
+
+        self.write_registers()
+    }
+
+    /// Writes the segments of each digit, from left to right.
+    pub fn print_digit_segments(&mut self, segments: &[u8]) -> anyhow::Result<(), Tm1638Error<E>> {
+        for (digit, s) in self.digits.iter_mut().zip(segments) {
+            *digit = *s;
+        }
+
+        self.write_registers()
+    }
+
+    /// Switches the LED above the n-th digit from the left.
+    pub fn set_led(&mut self, pos: u8, is_on: bool) -> anyhow::Result<(), Tm1638Error<E>> {
+        if pos as usize >= TM1638_DIGITS_COUNT {
+            return Ok(());
+        }
+
+        if is_on {
+            self.leds |= 1 << pos;
+        } else {
+            self.leds &= !(1 << pos);
+        }
+
+        self.write_registers()
+    }
+
+    /// Returns the buttons which are held down; bit n is the n-th button from the left.
+    pub fn read_buttons(&mut self) -> anyhow::Result<u8, Tm1638Error<E>> {
+        self.stb.set_low()?;
+        self.write_byte(Tm1638Command::DataCommandReadKeys as u8)?;
+
+        // release the line, the TM1638 drives it from now on
+        self.dio.set_high()?;
+        self.bit_delay();
+
+        // each byte holds 2 buttons: bit 0 for the buttons 1 to 4, bit 4 for the buttons 5 to 8
+        let mut buttons = 0_u8;
+        for i in 0..KEY_SCAN_BYTES_COUNT {
+            buttons |= (self.read_byte()? & 0b0001_0001) << i;
+        }
+
+        self.stb.set_high()?;
+
+        Ok(buttons)
+    }
+
+    /// Sets the display state. The display state is the 3rd bit of the
+    /// "display control"-command.
+    /// This setting is not committed until a write operation has been made.
+    pub fn set_display_state(&mut self, ds: DisplayState) {
+        let old_brightness = self.brightness & 0b0000_0111;
+        self.brightness = ds as u8 | old_brightness;
+    }
+
+    /// Sets the brightness of the screen.
+    /// This setting is not committed until a write operation has been made.
+    pub fn set_brightness(&mut self, brightness: Brightness) {
+        let display_on = self.brightness & 0b0000_1000;
+        self.brightness = display_on | brightness as u8;
+    }
+
+    /// Clears the digits; the LEDs keep their state.
+    pub fn clear(&mut self) -> anyhow::Result<(), Tm1638Error<E>> {
+        self.digits = [0; TM1638_DIGITS_COUNT];
+
+        self.write_registers()
+    }
+
+    /// Send command that sets the display state on the micro controller.
+    pub fn write_display_state(&mut self) -> anyhow::Result<(), Tm1638Error<E>> {
+        self.send_command(Tm1638Command::DisplayControlOff as u8 | self.brightness)
+    }
+
+    /// Writes the digits and the LEDs in one auto increment burst.
+    fn write_registers(&mut self) -> anyhow::Result<(), Tm1638Error<E>> {
+        self.send_command(Tm1638Command::DataCommandWriteToDisplay as u8)?;
+
+        self.stb.set_low()?;
+        self.write_byte(Tm1638Command::AddressCommandD0 as u8)?;
+        let digits = self.digits;
+        for (pos, digit) in digits.iter().enumerate() {
+            self.write_byte(*digit)?;
+            self.write_byte((self.leds >> pos) & 0x01)?;
+        }
+        self.stb.set_high()?;
+
+        self.write_display_state()
+    }
+
+    fn send_command(&mut self, command: u8) -> anyhow::Result<(), Tm1638Error<E>> {
+        self.stb.set_low()?;
+        self.write_byte(command)?;
+        self.stb.set_high()?;
+
+        Ok(())
+    }
+
+    /// LSB first; the TM1638 samples DIO on the rising edge of CLK.
+    fn write_byte(&mut self, byte: u8) -> anyhow::Result<(), Tm1638Error<E>> {
+        for i in 0..8 {
+            self.clk.set_low()?;
+
+            if (byte >> i) & 0x01 == 0x01 {
+                self.dio.set_high()?;
+            } else {
+                self.dio.set_low()?;
+            }
+            self.bit_delay();
+
+            self.clk.set_high()?;
+            self.bit_delay();
+        }
+
+        Ok(())
+    }
+
+    /// LSB first; the TM1638 shifts the next bit out on the falling edge of CLK.
+    fn read_byte(&mut self) -> anyhow::Result<u8, Tm1638Error<E>> {
+        let mut byte = 0_u8;
+
+        for i in 0..8 {
+            self.clk.set_low()?;
+            self.bit_delay();
+
+            if self.dio.is_high()? {
+                byte |= 1 << i;
+            }
+
+            self.clk.set_high()?;
+            self.bit_delay();
+        }
+
+        Ok(byte)
+    }
+
+    fn bit_delay(&mut self) {
+        self.delay_fn.delay_us(self.delay_us);
+    }
+}
+
+impl<'a, STB, CLK, DIO, DELAY, E> SegmentDisplay for Tm1638<'a, STB, CLK, DIO, DELAY>
+where
+    STB: OutputPin<Error = E>,
+    CLK: OutputPin<Error = E>,
+    DIO: InputPin<Error = E> + OutputPin<Error = E>,
+    DELAY: DelayUs<u16>,
+{
+    type Error = Tm1638Error<E>;
+
+    fn display_size(&self) -> u8 {
+        TM1638_DIGITS_COUNT as u8
+    }
+
+    fn print_text(&mut self, text: &str, show_colon: bool) -> anyhow::Result<(), Tm1638Error<E>> {
+        Tm1638::print_text(self, text, show_colon)
+    }
+
+    fn print_frame(&mut self, frame: &SegmentFrame) -> anyhow::Result<(), Tm1638Error<E>> {
+        Tm1638::print_frame(self, frame)
+    }
+
+    fn print_digit_segments(&mut self, segments: &[u8]) -> anyhow::Result<(), Tm1638Error<E>> {
+        Tm1638::print_digit_segments(self, segments)
+    }
+
+    fn set_brightness(&mut self, brightness: Brightness) {
+        Tm1638::set_brightness(self, brightness);
+    }
+
+    fn set_display_state(&mut self, ds: DisplayState) {
+        Tm1638::set_display_state(self, ds);
+    }
+
+    fn write_display_state(&mut self) -> anyhow::Result<(), Tm1638Error<E>> {
+        Tm1638::write_display_state(self)
+    }
+
+    fn clear(&mut self) -> anyhow::Result<(), Tm1638Error<E>> {
+        Tm1638::clear(self)
+    }
+
+    fn animation_frames(
+        &self,
+        animation: &SegmentAnimation,
+    ) -> anyhow::Result<Vec<SegmentFrame>, Tm1638Error<E>> {
+        self.text_layout()
+            .animation_frames(animation)
+            .ok_or_else(|| {
+                Tm1638Error::AutoScroll(
+                    "E0026b".to_owned(),
+                    "'min_char_count_to_be_displayed' should not be greater than the 'display_size'"
+                        .to_owned(),
+                )
+            })
+    }
+}
@@ -37,23 +37,35 @@ mod utils;
 extern crate dotenv_codegen;
 
 use crate::common::adaptors::network::WifiAdaptor;
+use crate::common::adaptors::settings_store::DeviceSettingsStore;
+use crate::common::adaptors::wifi_networks::WifiNetwork;
 use crate::common::errors::common_errors::CommonError;
 use crate::common::errors::device_errors::DeviceError;
+use crate::common::models::device_settings::DeviceSettings;
 use crate::constants::env_values::EnvValues;
-use crate::libs::tm1637::mappings::{Brightness, DisplayState, GpioPinValue};
-use crate::libs::tm1637::{Tm1637, Tm1637BannerAutoScrollConfig};
+use crate::libs::segment_display::SegmentDisplaySelfTestResult;
+use crate::libs::tm1637::mappings::GpioPinValue;
 
-use embedded_svc::sys_time::SystemTime;
+use esp_idf_svc::nvs::EspDefaultNvs;
 use esp_idf_sys::link_patches;
-use log::{error, warn};
-use std::sync::mpsc::{Receiver, Sender};
+use log::warn;
+use std::sync::atomic::{AtomicBool, AtomicU8};
 use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::Duration;
 
 use crate::constants::strings::Strings;
+use crate::features::network::status::NetworkStatus;
 use crate::features::network::Network;
-use crate::features::peripheral::{Peripheral, PeripheralFeatureStartPins, PeripheralKind, PeripheralRx, PeripheralTx};
+use crate::features::peripheral::{
+    Peripheral, PeripheralFeatureStartPins, PeripheralKind, PeripheralRx, PeripheralTx,
+};
+use crate::features::provisioning::ProvisioningFeature;
+use crate::features::segment_display::text_catalog::SEGMENT_DISPLAY_TEXTS;
+use crate::features::segment_display::{
+    SegmentDisplayFeature, SegmentDisplayFeatureStartPins, SegmentDisplayRx, SegmentDisplayTx,
+};
+use crate::helpers::device_identity::DEVICE_IDENTITY;
 use crate::helpers::logs::fern_log::setup_logging;
 use crate::GpioPinValue::High;
 
@@ -86,157 +98,111 @@ fn run() -> anyhow::Result<()> {
         led_g32: per.out_g32,
         led_g25: per.out_g25,
         led_g26: per.out_g26,
-        buzzer_g14: per.out_g14
+        buzzer_g14: per.out_g14,
     };
     Peripheral::start(peripheral_feature_start_pins, peripheral_rx)?;
     Peripheral::set_peripheral(&peripheral_tx, PeripheralKind::PowerOnLed(High));
 
-    let system_time = esp_idf_svc::systime::EspSystemTime {};
+    let default_nvs = Arc::new(EspDefaultNvs::new()?);
 
-    let wifi_adaptor = WifiAdaptor::new()?;
+    // the settings saved by the provisioning portal take the place of the compiled-in values
+    let settings_store = DeviceSettingsStore::new(Arc::clone(&default_nvs))?;
+    let settings = match settings_store.load() {
+        Ok(s) => s.unwrap_or_default(),
+        Err(e) => {
+            log::error!("[E0046][main] {}", e.to_string());
+
+            DeviceSettings::default()
+        }
+    };
+    DEVICE_IDENTITY.set(
+        settings.device_name.clone(),
+        settings.device_location.clone(),
+    );
+
+    // the provisioned networks come first, the compiled-in ones are the fallback
+    let mut wifi_networks = settings.wifi_networks.clone();
+    wifi_networks.extend(EnvValues::wifi_networks()?);
+    for ssid in WifiNetwork::assign_static_ipv4s(&mut wifi_networks, EnvValues::wifi_static_ipv4()?)
+    {
+        warn!(
+            "[main] no wifi network `{}` for the static ip configuration",
+            ssid
+        );
+    }
+
+    let is_provisioning_requested =
+        wifi_networks.is_empty() || ProvisioningFeature::is_button_held(&per.in_g0);
+    let has_saved_wifi_networks = settings.has_wifi_networks();
+
+    let wifi_adaptor = WifiAdaptor::new(default_nvs, wifi_networks)?;
     let wifi_adaptor_arc = Arc::new(Mutex::new(wifi_adaptor));
-    let (seg_display_tx, seg_display_rx): (Sender<Option<String>>, Receiver<Option<String>>) =
+
+    if is_provisioning_requested {
+        // restarts the device once the settings have been saved
+        return ProvisioningFeature::run(&wifi_adaptor_arc, settings_store, settings);
+    }
+
+    let (seg_display_tx, seg_display_rx): (SegmentDisplayTx, SegmentDisplayRx) =
+        std::sync::mpsc::channel();
+    let (secondary_seg_display_tx, secondary_seg_display_rx): (SegmentDisplayTx, SegmentDisplayRx) =
         std::sync::mpsc::channel();
 
-    let net_features = Network::new();
+    // the texts which the features send to the segment display are taken from this catalog
+    SEGMENT_DISPLAY_TEXTS.set_locale(EnvValues::segment_display_locale()?);
+
+    // set by the segment display thread; false if the display is not acknowledging the commands
+    let is_segment_display_healthy = Arc::new(AtomicBool::from(true));
+
+    // set on boot or by the server; cleared by the segment display thread once the self test runs
+    let is_segment_display_self_test_requested = Arc::new(AtomicBool::from(
+        EnvValues::segment_display_self_test_on_boot()?,
+    ));
+
+    // set by the segment display thread once the self test has run; reported with the ping
+    let segment_display_self_test_result =
+        Arc::new(AtomicU8::from(SegmentDisplaySelfTestResult::NotRun as u8));
+
+    let network_status = Arc::new(NetworkStatus::new());
+
+    let net_features = Network::new(has_saved_wifi_networks);
     Network::start(
         &Arc::new(Mutex::new(net_features)),
         &wifi_adaptor_arc,
         seg_display_tx,
+        secondary_seg_display_tx,
         peripheral_tx,
+        &is_segment_display_healthy,
+        &is_segment_display_self_test_requested,
+        &segment_display_self_test_result,
+        &network_status,
     )?;
 
-    let segement_display_message: Option<String> = None;
-    let segement_display_message_arc = Arc::new(Mutex::new(segement_display_message));
-    let segement_display_message_arc_clone = Arc::clone(&segement_display_message_arc);
-    thread::Builder::new().spawn(move || loop {
-        match seg_display_rx.recv() {
-            Ok(msg) => match segement_display_message_arc_clone.lock() {
-                Ok(d) => {
-                    let mut seg_text = d;
-
-                    *seg_text = msg;
-                }
-                Err(e) => {
-                    error!("[E0029a][seg_display_rx thread] {}", e.to_string());
-                }
-            },
-            Err(e) => {
-                error!("[E0029b][seg_display_rx thread] {}", e.to_string());
-            }
-        }
-    })?;
-
-    let mut clk_g27 = per.inout_g27;
-    let mut dio_g13 = per.inout_g13;
-    thread::Builder::new().spawn(move || {
-        let mut tm = Tm1637::new(&mut clk_g27, &mut dio_g13);
-        tm.set_display_state(DisplayState::On);
-        tm.set_brightness(Brightness::L7);
-        let tm_clear_res = tm.clear();
-        if let Err(e) = tm_clear_res {
-            error!("[E0031a][peripherals] {}", e.to_string());
-        }
-
-        // show the texts of the segment display after the defined number of loop iterations
-        // this value is `5` when running time is less than `1 hour`
-        // and `2` when running time is more than `1 hour`
-        let mut min_timer_seconds_to_display_size = 5_i32;
-
-        // if the [print_seg_text_on_display_count] is in multiples of[print_seg_text_on_display_cutoff_count] then print the [segement_display_message] to the display. This is to avoid error texts from taking hogging up the display
-        let mut min_timer_seconds_to_display_counter = 0_i32;
-
-        // for some strage reason adding a [thread::delay] is not freeing the [segement_display_message] hence not able to recieve messages via the [seg_display_rx] channel. This is a hack, a way around, it works and need to investigate why this is acting weird
-        let mut next_delay = 0;
-
-        let mut show_colon = false;
-
-        loop {
-            thread::sleep(Duration::from_millis(next_delay));
-
-            let seg_text_res = segement_display_message_arc.lock();
-            match seg_text_res {
-                Ok(mut seg_text) => {
-                    if let Some(msg) = &mut *seg_text {
-                        if min_timer_seconds_to_display_counter % min_timer_seconds_to_display_size
-                            == 0
-                        {
-                            let c = Tm1637BannerAutoScrollConfig {
-                                scroll_min_char_count: tm.display_size + 1,
-                                delay_ms: 750,
-                                min_char_count_to_be_displayed: tm.display_size,
-                            };
-
-                            let tm_print_res = tm.print_string(msg, false, Some(&c), 0);
-                            if let Err(e) = tm_print_res {
-                                error!("[E0031b][peripherals] {}", e.to_string());
-                            }
-
-                            *seg_text = None;
-                            min_timer_seconds_to_display_counter += 1_i32;
-                            next_delay = 2000;
-
-                            continue;
-                        }
-                    }
-                }
-                Err(e) => {
-                    error!("[E0031c][peripherals] {}", e.to_string());
-                }
-            }
-
-            min_timer_seconds_to_display_counter += 1_i32;
-            let time_now = system_time.now();
-
-            let seconds = time_now.as_secs() % 60;
-            let minutes = (time_now.as_secs() / 60) % 60;
-            let hours = (time_now.as_secs() / 60) / 60;
-
-            // when running time is less than 1 hour then just show `00:00`
-            if hours < 1 {
-                let min_sec_t = format!("{:02}{:02}", minutes, seconds);
-
-                show_colon = !show_colon;
-
-                let res = tm.print_string(&min_sec_t, show_colon, None, 100_u16);
-                if let Err(err) = res {
-                    error!(
-                        "[E0028a][segment display printing thread] {}",
-                        err.to_string()
-                    );
-                }
-
-                next_delay = 1000;
-                min_timer_seconds_to_display_size = 5_i32;
-
-                continue;
-            }
-
-            // when running time is more than 1 hour then show `00h 00n 00c`
-            let hour_min_sec_t = format!(
-                "{}{} {:02}{} {:02}{}",
-                hours, "h", minutes, "n", seconds, "c"
-            );
-            let c = Tm1637BannerAutoScrollConfig {
-                scroll_min_char_count: tm.display_size + 1,
-                delay_ms: 750,
-                min_char_count_to_be_displayed: tm.display_size,
-            };
-
-            let res = tm.print_string(&hour_min_sec_t, false, Some(&c), 0);
-            if let Err(err) = res {
-                error!(
-                    "[E0028b][segment display printing thread] {}",
-                    err.to_string()
-                );
-            }
-
-            next_delay = 2000;
-            min_timer_seconds_to_display_size = 2_i32;
-        }
-    })?;
+    let segment_display_feature_start_pins = SegmentDisplayFeatureStartPins {
+        inout_g27: per.inout_g27,
+        inout_g13: per.inout_g13,
+        inout_g15: per.inout_g15,
+        spi2: per.spi2,
+        inout_g33: per.inout_g33,
+        inout_g4: per.inout_g4,
+        adc1: per.adc1,
+        analog_g34: per.analog_g34,
+    };
+    SegmentDisplayFeature::start(
+        segment_display_feature_start_pins,
+        seg_display_rx,
+        secondary_seg_display_rx,
+        &is_segment_display_healthy,
+        &is_segment_display_self_test_requested,
+        &segment_display_self_test_result,
+        &network_status,
+    )?;
 
-    loop {
+    // the compiled-in networks can't be joined and none has been saved in the portal yet
+    while !network_status.is_provisioning_requested() {
         thread::sleep(Duration::from_millis(5000));
     }
+
+    // restarts the device once the settings have been saved
+    ProvisioningFeature::run(&wifi_adaptor_arc, settings_store, settings)
 }